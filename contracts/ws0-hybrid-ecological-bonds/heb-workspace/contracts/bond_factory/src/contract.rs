@@ -1,17 +1,21 @@
 use cosmwasm_std::{
-    entry_point, to_json_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response,
-    StdResult, SubMsg, WasmMsg,
+    entry_point, to_json_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Reply, Response,
+    StdResult, Storage, SubMsg, WasmMsg,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 use cw_storage_plus::Bound;
-use heb_types::SeriesTerms;
+use heb_types::{parse_version, SeriesStatus, SeriesTerms};
 
 use crate::error::ContractError;
-use crate::msg::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, SeriesListResponse};
-use crate::state::{Config, CONFIG, SERIES, SERIES_COUNT};
+use crate::migrations;
+use crate::msg::{ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SeriesListResponse, SeriesResponse};
+use crate::state::{series, Config, SeriesRecord, CONFIG, SERIES_COUNT};
 
 const CONTRACT_NAME: &str = "heb-bond-factory";
-const CONTRACT_VERSION: &str = "0.1.0";
+const CONTRACT_VERSION: &str = "0.2.0";
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 200;
 
 const REPLY_ID_CREATE_SERIES: u64 = 1;
 
@@ -39,6 +43,35 @@ pub fn instantiate(deps: DepsMut, _env: Env, info: MessageInfo, msg: Instantiate
         .add_attribute("sender", info.sender))
 }
 
+#[entry_point]
+pub fn migrate(mut deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidConfig(format!(
+            "cannot migrate from contract {}",
+            stored.contract
+        )));
+    }
+    let from = parse_version(&stored.version)?;
+    if from > parse_version(CONTRACT_VERSION)? {
+        return Err(ContractError::CannotDowngrade {
+            from: stored.version,
+            to: CONTRACT_VERSION.to_string(),
+        });
+    }
+    for (version, step) in migrations::STEPS {
+        if parse_version(version)? >= from {
+            step(deps.branch())?;
+        }
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
 fn only_admin(deps: Deps, info: &MessageInfo) -> Result<(), ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
     if info.sender != Addr::unchecked(cfg.admin) {
@@ -60,7 +93,7 @@ fn validate_terms(cfg: &Config, terms: &SeriesTerms) -> Result<(), ContractError
 #[entry_point]
 pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::CreateSeries { terms } => execute_create_series(deps, env, info, terms),
+        ExecuteMsg::CreateSeries { terms } => execute_create_series(deps, env, info, *terms),
         ExecuteMsg::UpdateConfig {
             admin,
             allowed_principal_denoms,
@@ -78,9 +111,12 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> R
             fee_recipient,
             bond_series_code_id,
         ),
+        ExecuteMsg::MigrateSeries { ids, new_code_id, msg } => execute_migrate_series(deps, env, info, ids, new_code_id, msg),
+        ExecuteMsg::RefreshSeries { ids } => execute_refresh_series(deps, ids),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn execute_update_config(
     deps: DepsMut,
     info: MessageInfo,
@@ -105,9 +141,69 @@ fn execute_update_config(
     Ok(Response::new().add_attribute("action", "update_config"))
 }
 
+/// Send `WasmMsg::Migrate` to each listed series. Any failing migration reverts the batch, so the
+/// recorded code ids stay in step with the chain.
+fn execute_migrate_series(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ids: Vec<u64>,
+    new_code_id: u64,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    only_admin(deps.as_ref(), &info)?;
+    if ids.is_empty() {
+        return Err(ContractError::InvalidConfig("no series to migrate".into()));
+    }
+
+    let mut msgs = Vec::with_capacity(ids.len());
+    for id in &ids {
+        let mut record = load_record(deps.storage, *id)?;
+        if record.wasm_admin.as_deref() != Some(env.contract.address.as_str()) {
+            return Err(ContractError::NotSeriesAdmin { id: *id });
+        }
+        record.code_id = new_code_id;
+        series().save(deps.storage, *id, &record)?;
+        msgs.push(WasmMsg::Migrate {
+            contract_addr: record.address,
+            new_code_id,
+            msg: msg.clone(),
+        });
+    }
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "migrate_series")
+        .add_attribute("new_code_id", new_code_id.to_string())
+        .add_attribute("count", ids.len().to_string()))
+}
+
+fn load_record(storage: &dyn Storage, id: u64) -> Result<SeriesRecord, ContractError> {
+    series().may_load(storage, id)?.ok_or(ContractError::SeriesNotFound { id })
+}
+
+/// Copy each listed series' current status and wasm admin into its registry record.
+fn execute_refresh_series(deps: DepsMut, ids: Vec<u64>) -> Result<Response, ContractError> {
+    if ids.is_empty() {
+        return Err(ContractError::InvalidConfig("no series to refresh".into()));
+    }
+    for id in &ids {
+        let mut record = load_record(deps.storage, *id)?;
+        let state: bond_series::msg::StateResponse = deps
+            .querier
+            .query_wasm_smart(&record.address, &bond_series::msg::QueryMsg::State {})?;
+        record.status = state.status;
+        record.wasm_admin = deps.querier.query_wasm_contract_info(&record.address)?.admin;
+        series().save(deps.storage, *id, &record)?;
+    }
+    Ok(Response::new()
+        .add_attribute("action", "refresh_series")
+        .add_attribute("count", ids.len().to_string()))
+}
+
 fn execute_create_series(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     terms: SeriesTerms,
 ) -> Result<Response, ContractError> {
@@ -118,7 +214,7 @@ fn execute_create_series(
     }
     validate_terms(&cfg, &terms)?;
 
-    // Instantiate bond_series with factory as admin
+    // the factory is the wasm admin of the series it creates so MigrateSeries can upgrade them
     let instantiate_msg = bond_series::msg::InstantiateMsg {
         terms,
         admin: cfg.admin.clone(),
@@ -128,7 +224,7 @@ fn execute_create_series(
 
     let sub = SubMsg::reply_on_success(
         WasmMsg::Instantiate {
-            admin: Some(cfg.admin.clone()),
+            admin: Some(env.contract.address.to_string()),
             code_id: cfg.bond_series_code_id,
             msg: cosmwasm_std::to_json_binary(&instantiate_msg)?,
             funds: vec![],
@@ -143,7 +239,7 @@ fn execute_create_series(
 }
 
 #[entry_point]
-pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
     if msg.id != REPLY_ID_CREATE_SERIES {
         return Err(ContractError::InvalidConfig("unknown reply id".into()));
     }
@@ -161,7 +257,24 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
     let mut count = SERIES_COUNT.load(deps.storage)?;
     count += 1;
     SERIES_COUNT.save(deps.storage, &count)?;
-    SERIES.save(deps.storage, count, &addr)?;
+    // same transaction as CreateSeries, so the configured code is the one just instantiated
+    let cfg = CONFIG.load(deps.storage)?;
+    let terms: bond_series::msg::TermsResponse = deps
+        .querier
+        .query_wasm_smart(&addr, &bond_series::msg::QueryMsg::Terms {})?;
+    let record = SeriesRecord {
+        id: count,
+        address: addr.clone(),
+        borrower: terms.terms.borrower,
+        principal_denom: terms.terms.principal_denom,
+        maturity_ts: terms.terms.maturity_ts,
+        code_id: cfg.bond_series_code_id,
+        wasm_admin: Some(env.contract.address.to_string()),
+        created_at: Some(env.block.time.seconds()),
+        // instantiated without funds, so no collateral yet
+        status: SeriesStatus::Created,
+    };
+    series().save(deps.storage, count, &record)?;
 
     Ok(Response::new()
         .add_attribute("action", "series_created")
@@ -174,9 +287,59 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
         QueryMsg::SeriesList { start_after, limit } => to_json_binary(&query_series_list(deps, start_after, limit)?),
+        QueryMsg::Series { id } => to_json_binary(&query_series(deps, id)?),
+        QueryMsg::SeriesByAddress { address } => to_json_binary(&query_series_by_address(deps, address)?),
+        QueryMsg::SeriesByBorrower { borrower, start_after, limit } => {
+            to_json_binary(&query_series_by_borrower(deps, borrower, start_after, limit)?)
+        }
+        QueryMsg::SeriesByDenom { denom, start_after, limit } => {
+            to_json_binary(&query_series_by_denom(deps, denom, start_after, limit)?)
+        }
+        QueryMsg::SeriesByMaturity { from, to, start_after, limit } => {
+            to_json_binary(&query_series_by_maturity(deps, from, to, start_after, limit)?)
+        }
+    }
+}
+
+fn series_response(r: SeriesRecord) -> SeriesResponse {
+    SeriesResponse {
+        id: r.id,
+        address: r.address,
+        borrower: r.borrower,
+        principal_denom: r.principal_denom,
+        maturity_ts: r.maturity_ts,
+        code_id: r.code_id,
+        wasm_admin: r.wasm_admin,
+        created_at: r.created_at,
+        status: r.status,
     }
 }
 
+fn series_page(
+    items: impl Iterator<Item = StdResult<(u64, SeriesRecord)>>,
+    limit: Option<u32>,
+) -> StdResult<SeriesListResponse> {
+    let lim = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let series = items
+        .take(lim)
+        .map(|item| item.map(|(_, r)| series_response(r)))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(SeriesListResponse { series })
+}
+
+fn query_series(deps: Deps, id: u64) -> StdResult<SeriesResponse> {
+    Ok(series_response(series().load(deps.storage, id)?))
+}
+
+fn query_series_by_address(deps: Deps, address: String) -> StdResult<SeriesResponse> {
+    let (_, record) = series()
+        .idx
+        .address
+        .item(deps.storage, address.clone())?
+        .ok_or_else(|| cosmwasm_std::StdError::not_found(format!("series at {address}")))?;
+    Ok(series_response(record))
+}
+
 fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let cfg = CONFIG.load(deps.storage)?;
     Ok(ConfigResponse {
@@ -189,19 +352,51 @@ fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     })
 }
 
-fn query_series_list(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<SeriesListResponse> {
-    let lim = limit.unwrap_or(50).min(200) as usize;
-    let start_id = start_after
-        .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(0);
+fn query_series_list(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<SeriesListResponse> {
+    let start = start_after.map(Bound::exclusive);
+    series_page(series().range(deps.storage, start, None, Order::Ascending), limit)
+}
 
-    let mut out = vec![];
-    for item in SERIES
-        .range(deps.storage, Some(Bound::exclusive(start_id + 1)), None, cosmwasm_std::Order::Ascending)
-        .take(lim)
-    {
-        let (_k, v) = item?;
-        out.push(v);
-    }
-    Ok(SeriesListResponse { series: out })
+fn query_series_by_borrower(
+    deps: Deps,
+    borrower: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SeriesListResponse> {
+    let start = start_after.map(Bound::exclusive);
+    let items = series().idx.borrower.prefix(borrower).range(deps.storage, start, None, Order::Ascending);
+    series_page(items, limit)
+}
+
+fn query_series_by_denom(
+    deps: Deps,
+    denom: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SeriesListResponse> {
+    let start = start_after.map(Bound::exclusive);
+    let items = series().idx.principal_denom.prefix(denom).range(deps.storage, start, None, Order::Ascending);
+    series_page(items, limit)
+}
+
+/// Index keys are `(maturity_ts, id)`, so the cursor resumes after the given series' own key.
+fn query_series_by_maturity(
+    deps: Deps,
+    from: Option<u64>,
+    to: Option<u64>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SeriesListResponse> {
+    let from = from.unwrap_or(0);
+    let cursor = match start_after {
+        Some(id) => Some((series().load(deps.storage, id)?.maturity_ts, id)),
+        None => None,
+    };
+    let min = match cursor {
+        Some(key) if key.0 >= from => Bound::exclusive(key),
+        _ => Bound::inclusive((from, 0)),
+    };
+    let max = to.map(|to| Bound::inclusive((to, u64::MAX)));
+    let items = series().idx.maturity.range(deps.storage, Some(min), max, Order::Ascending);
+    series_page(items, limit)
 }
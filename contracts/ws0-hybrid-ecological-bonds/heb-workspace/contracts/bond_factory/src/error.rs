@@ -13,4 +13,13 @@ pub enum ContractError {
 
     #[error("Principal denom not allowed")]
     DenomNotAllowed,
+
+    #[error("Series {id} not found")]
+    SeriesNotFound { id: u64 },
+
+    #[error("Factory is not the wasm admin of series {id}; hand it over with UpdateAdmin and RefreshSeries")]
+    NotSeriesAdmin { id: u64 },
+
+    #[error("Cannot migrate from {from} down to {to}")]
+    CannotDowngrade { from: String, to: String },
 }
@@ -1,6 +1,10 @@
 pub mod contract;
 pub mod error;
+pub mod migrations;
 pub mod msg;
 pub mod state;
 
-pub use crate::contract::{execute, instantiate, query, reply};
+#[cfg(test)]
+mod tests;
+
+pub use crate::contract::{execute, instantiate, migrate, query, reply};
@@ -0,0 +1,89 @@
+use cosmwasm_std::{DepsMut, StdResult};
+
+pub type Step = fn(DepsMut) -> StdResult<()>;
+
+/// State transformers in version order. Each is keyed by the last version whose layout it
+/// rewrites, and `migrate` runs every one at or above the stored version.
+pub const STEPS: &[(&str, Step)] = &[("0.1.0", v0_1::migrate)];
+
+/// v0.1 kept only a bare address per series. Rebuilds each as an indexed record from the series'
+/// own terms and state and its contract info; the creation time was never stored and stays unknown.
+pub mod v0_1 {
+    use cosmwasm_std::{DepsMut, Order, StdResult, Uint128};
+    use cw_storage_plus::Map;
+    use heb_types::SeriesStatus;
+    use serde::Deserialize;
+
+    use crate::state::{series, SeriesRecord};
+
+    /// series id -> address
+    pub const SERIES: Map<u64, String> = Map::new("series");
+
+    // only the fields the registry needs, so older series layouts still parse
+    #[derive(Deserialize)]
+    struct Terms {
+        borrower: String,
+        principal_denom: String,
+        maturity_ts: u64,
+    }
+
+    #[derive(Deserialize)]
+    struct TermsResponse {
+        terms: Terms,
+    }
+
+    /// Series v0.1 reported no `status`; it is derived the way the series' own v0.1 migration does.
+    #[derive(Deserialize)]
+    struct StateResponse {
+        #[serde(default)]
+        status: Option<SeriesStatus>,
+        #[serde(default)]
+        sale_open: bool,
+        #[serde(default)]
+        total_principal_sold: Uint128,
+        #[serde(default)]
+        collateral_locked: Uint128,
+    }
+
+    impl StateResponse {
+        fn status(&self) -> SeriesStatus {
+            match self.status {
+                Some(status) => status,
+                None if self.sale_open => SeriesStatus::SaleOpen,
+                None if !self.total_principal_sold.is_zero() => SeriesStatus::Active,
+                None if !self.collateral_locked.is_zero() => SeriesStatus::Collateralized,
+                None => SeriesStatus::Created,
+            }
+        }
+    }
+
+    pub fn migrate(deps: DepsMut) -> StdResult<()> {
+        let old = SERIES
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (id, address) in old {
+            let info = deps.querier.query_wasm_contract_info(&address)?;
+            let terms: TermsResponse = deps
+                .querier
+                .query_wasm_smart(&address, &bond_series::msg::QueryMsg::Terms {})?;
+            let state: StateResponse = deps
+                .querier
+                .query_wasm_smart(&address, &bond_series::msg::QueryMsg::State {})?;
+            let record = SeriesRecord {
+                id,
+                address,
+                borrower: terms.terms.borrower,
+                principal_denom: terms.terms.principal_denom,
+                maturity_ts: terms.terms.maturity_ts,
+                code_id: info.code_id,
+                // factory 0.1 made its admin account the wasm admin, not itself
+                wasm_admin: info.admin,
+                created_at: None,
+                status: state.status(),
+            };
+            series().save(deps.storage, id, &record)?;
+            SERIES.remove(deps.storage, id);
+        }
+        Ok(())
+    }
+}
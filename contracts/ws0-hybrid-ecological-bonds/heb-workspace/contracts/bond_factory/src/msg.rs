@@ -1,5 +1,6 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use heb_types::SeriesTerms;
+use cosmwasm_std::Binary;
+use heb_types::{SeriesStatus, SeriesTerms};
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -13,7 +14,7 @@ pub struct InstantiateMsg {
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    CreateSeries { terms: SeriesTerms },
+    CreateSeries { terms: Box<SeriesTerms> },
     UpdateConfig {
         admin: Option<String>,
         allowed_principal_denoms: Option<Vec<String>>,
@@ -22,15 +23,36 @@ pub enum ExecuteMsg {
         fee_recipient: Option<String>,
         bond_series_code_id: Option<u64>,
     },
+    /// Admin: migrate the listed series to `new_code_id` with `msg` in one transaction. Only
+    /// series whose recorded wasm admin is the factory can be migrated: every series it created,
+    /// and series from factory 0.1 once handed over with `UpdateAdmin` and refreshed.
+    MigrateSeries { ids: Vec<u64>, new_code_id: u64, msg: Binary },
+    /// Anyone: re-read the listed series' status and wasm admin into the registry.
+    RefreshSeries { ids: Vec<u64> },
 }
 
+#[cw_serde]
+pub struct MigrateMsg {}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
     #[returns(ConfigResponse)]
     Config {},
+    /// All series by id; `start_after` is the last id of the previous page.
+    #[returns(SeriesListResponse)]
+    SeriesList { start_after: Option<u64>, limit: Option<u32> },
+    #[returns(SeriesResponse)]
+    Series { id: u64 },
+    #[returns(SeriesResponse)]
+    SeriesByAddress { address: String },
+    #[returns(SeriesListResponse)]
+    SeriesByBorrower { borrower: String, start_after: Option<u64>, limit: Option<u32> },
     #[returns(SeriesListResponse)]
-    SeriesList { start_after: Option<String>, limit: Option<u32> },
+    SeriesByDenom { denom: String, start_after: Option<u64>, limit: Option<u32> },
+    /// Series maturing in `[from, to]`, ordered by maturity then id. `start_after` is a series id.
+    #[returns(SeriesListResponse)]
+    SeriesByMaturity { from: Option<u64>, to: Option<u64>, start_after: Option<u64>, limit: Option<u32> },
 }
 
 #[cw_serde]
@@ -43,7 +65,24 @@ pub struct ConfigResponse {
     pub bond_series_code_id: u64,
 }
 
+#[cw_serde]
+pub struct SeriesResponse {
+    pub id: u64,
+    pub address: String,
+    pub borrower: String,
+    pub principal_denom: String,
+    pub maturity_ts: u64,
+    /// code the series runs, as of its creation or last `MigrateSeries`
+    pub code_id: u64,
+    /// as of registration or the last `RefreshSeries`; `MigrateSeries` needs it to be the factory
+    pub wasm_admin: Option<String>,
+    /// `None` for series registered before factory 0.2
+    pub created_at: Option<u64>,
+    /// as of creation or the last `RefreshSeries`; query the series for the live status
+    pub status: SeriesStatus,
+}
+
 #[cw_serde]
 pub struct SeriesListResponse {
-    pub series: Vec<String>,
+    pub series: Vec<SeriesResponse>,
 }
@@ -1,5 +1,6 @@
 use cosmwasm_schema::cw_serde;
-use cw_storage_plus::{Item, Map};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, MultiIndex, UniqueIndex};
+use heb_types::SeriesStatus;
 
 #[cw_serde]
 pub struct Config {
@@ -13,6 +14,53 @@ pub struct Config {
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
-/// series id -> address (string). For v0.1, we just store sequential ids.
-pub const SERIES: Map<u64, String> = Map::new("series");
 pub const SERIES_COUNT: Item<u64> = Item::new("series_count");
+
+/// Registry entry for a series the factory created.
+#[cw_serde]
+pub struct SeriesRecord {
+    pub id: u64,
+    pub address: String,
+    pub borrower: String,
+    pub principal_denom: String,
+    pub maturity_ts: u64,
+    /// code the series runs, kept current by `MigrateSeries`
+    pub code_id: u64,
+    /// wasm admin of the series as of registration or the last `RefreshSeries`. The factory for
+    /// every series it created; factory 0.1 left its own admin account in charge
+    pub wasm_admin: Option<String>,
+    /// creation block time; `None` for series registered before factory 0.2
+    pub created_at: Option<u64>,
+    /// as of creation or the last `RefreshSeries`
+    pub status: SeriesStatus,
+}
+
+pub struct SeriesIndexes<'a> {
+    pub borrower: MultiIndex<'a, String, SeriesRecord, u64>,
+    pub principal_denom: MultiIndex<'a, String, SeriesRecord, u64>,
+    pub maturity: MultiIndex<'a, u64, SeriesRecord, u64>,
+    pub address: UniqueIndex<'a, String, SeriesRecord, u64>,
+}
+
+impl<'a> IndexList<SeriesRecord> for SeriesIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<SeriesRecord>> + '_> {
+        let v: Vec<&dyn Index<SeriesRecord>> =
+            vec![&self.borrower, &self.principal_denom, &self.maturity, &self.address];
+        Box::new(v.into_iter())
+    }
+}
+
+/// series id -> record, indexed by borrower, principal denom, maturity and address.
+pub fn series<'a>() -> IndexedMap<'a, u64, SeriesRecord, SeriesIndexes<'a>> {
+    let indexes = SeriesIndexes {
+        borrower: MultiIndex::new(|_pk, r| r.borrower.clone(), "series_records", "series_records__borrower"),
+        principal_denom: MultiIndex::new(
+            |_pk, r| r.principal_denom.clone(),
+            "series_records",
+            "series_records__denom",
+        ),
+        maturity: MultiIndex::new(|_pk, r| r.maturity_ts, "series_records", "series_records__maturity"),
+        address: UniqueIndex::new(|r| r.address.clone(), "series_records__address"),
+    };
+    IndexedMap::new("series_records", indexes)
+}
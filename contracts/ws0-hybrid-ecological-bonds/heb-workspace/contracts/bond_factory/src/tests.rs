@@ -0,0 +1,93 @@
+mod migration {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{
+        from_json, to_json_binary, Binary, ContractInfoResponse, ContractResult, SystemResult,
+        WasmQuery,
+    };
+    use heb_types::SeriesStatus;
+
+    use crate::contract::migrate;
+    use crate::error::ContractError;
+    use crate::migrations::v0_1::SERIES;
+    use crate::msg::MigrateMsg;
+    use crate::state::{series, SERIES_COUNT};
+
+    #[test]
+    fn rebuilds_v0_1_series_as_indexed_records() {
+        let mut deps = mock_dependencies();
+        cw2::set_contract_version(&mut deps.storage, "heb-bond-factory", "0.1.0").unwrap();
+        SERIES
+            .save(&mut deps.storage, 1, &"series1".to_string())
+            .unwrap();
+        SERIES
+            .save(&mut deps.storage, 2, &"series2".to_string())
+            .unwrap();
+        SERIES_COUNT.save(&mut deps.storage, &2).unwrap();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::ContractInfo { contract_addr } => {
+                let code_id = if contract_addr == "series1" { 7 } else { 9 };
+                let mut info = ContractInfoResponse::default();
+                info.code_id = code_id;
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&info).unwrap()))
+            }
+            WasmQuery::Smart { contract_addr, msg } => {
+                let maturity = if contract_addr == "series1" { 2_000 } else { 1_000 };
+                let res = match from_json(msg).unwrap() {
+                    bond_series::msg::QueryMsg::Terms {} => format!(
+                        r#"{{"terms":{{"borrower":"borrower","principal_denom":"uusdc","maturity_ts":{maturity}}},"admin":"admin"}}"#
+                    ),
+                    bond_series::msg::QueryMsg::State {} if contract_addr == "series1" => {
+                        r#"{"status":"active","paused":false}"#.to_string()
+                    }
+                    // series v0.1 reported no status
+                    bond_series::msg::QueryMsg::State {} => r#"{"sale_open":false,"paused":false,"total_principal_sold":"3000","total_principal_outstanding":"2500","collateral_locked":"1000000","global_interest_index":"1","last_accrual_ts":1700000000,"last_price":null,"last_impact":null}"#.to_string(),
+                    other => panic!("unexpected series query {other:?}"),
+                };
+                SystemResult::Ok(ContractResult::Ok(Binary::from(res.as_bytes())))
+            }
+            other => panic!("unexpected query {other:?}"),
+        });
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        let first = series().load(&deps.storage, 1).unwrap();
+        assert_eq!(first.address, "series1");
+        assert_eq!(first.borrower, "borrower");
+        assert_eq!(first.principal_denom, "uusdc");
+        assert_eq!(first.maturity_ts, 2_000);
+        assert_eq!(first.code_id, 7);
+        assert_eq!(first.created_at, None);
+        assert_eq!(first.status, SeriesStatus::Active);
+        let (_, second) = series()
+            .idx
+            .address
+            .item(&deps.storage, "series2".to_string())
+            .unwrap()
+            .unwrap();
+        assert_eq!((second.id, second.code_id), (2, 9));
+        assert_eq!(second.status, SeriesStatus::Active);
+        // the old map is emptied
+        assert!(SERIES.is_empty(&deps.storage));
+        let version = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.version, "0.2.0");
+    }
+
+    #[test]
+    fn refuses_downgrade_and_foreign_contracts() {
+        let mut deps = mock_dependencies();
+        cw2::set_contract_version(&mut deps.storage, "heb-bond-factory", "0.10.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::CannotDowngrade {
+                from: "0.10.0".to_string(),
+                to: "0.2.0".to_string()
+            }
+        );
+
+        cw2::set_contract_version(&mut deps.storage, "heb-bond-series", "0.1.0").unwrap();
+        assert!(matches!(
+            migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err(),
+            ContractError::InvalidConfig(_)
+        ));
+    }
+}
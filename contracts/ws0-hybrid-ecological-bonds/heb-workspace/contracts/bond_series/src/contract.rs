@@ -1,22 +1,53 @@
 use cosmwasm_std::{
-    entry_point, to_json_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo,
-    Response, StdResult, Timestamp,
+    entry_point, to_json_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal256, Deps, DepsMut, Env, Event,
+    IbcMsg, IbcTimeout, MessageInfo, Order, Response, StdError, StdResult, Storage, Uint128, Uint256,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
+use cw_storage_plus::Bound;
 use cw_utils::must_pay;
+use heb_types::{parse_version, ImpactMode, SeriesStatus};
 
+use crate::ecocredit::query_total_retired;
 use crate::error::ContractError;
-use crate::math::{accrue_index, parse_u128, to_string_u128};
+use crate::math::{
+    accrue_index, accrued_interest, bps_of_ceil, collateral_ratio_bps, collateral_value, initial_index, required_collateral,
+    factor_share, liquidation_collateral_out, liquidation_repay_for, scale_balance, scale_factor, BPS_DENOM,
+};
+use crate::migrations;
+use crate::token::{self, DEFAULT_LIMIT, MAX_LIMIT};
+use crate::tokenfactory::{
+    bond_denom, burn_msg, create_denom_msg, is_token_factory, mint_msg, set_before_send_hook_msg,
+};
 use crate::msg::{
-    AccruedInterestResponse, BalanceResponse, CollateralRatioResponse, ExecuteMsg, ImpactStatusResponse,
-    InstantiateMsg, PriceStatusResponse, QueryMsg, StateResponse, TermsResponse,
+    AccruedInterestResponse, AmortizationResponse, AprReason, BalanceResponse, CallQuoteResponse, CollateralHealth, CollateralRatioResponse, CouponResponse,
+    CouponScheduleResponse, CouponStatus, EffectiveAprResponse, ExecuteMsg, ExpectedRecoveryResponse, ImpactStatusResponse,
+    InstallmentResponse, InstallmentStatus, InstantiateMsg, MigrateMsg, PendingRedemptionResponse, PriceStatusResponse, QueryMsg, SaleCollateralResponse, StateResponse, SudoMsg, TermsResponse,
+};
+use crate::oracle::{
+    encode_price_calldata, OracleRequestPacketData, ASK_COUNT, EXECUTE_GAS, MIN_COUNT, PREPARE_GAS,
+    PRICE_MULTIPLIER, PRICE_REQUEST_TIMEOUT_SECONDS, PRICE_SYMBOL,
+};
+use crate::state::{
+    AccountIndex, Config, Coupon, CouponBalance, CouponRecord, ImpactPoint, PendingPriceRequest, PricePoint, RecoverySnapshot,
+    SeriesState, ACCOUNTS, CONFIG, COUPONS, COUPON_BALANCES, PENDING_PRICE_REQUEST, PRICE_REQUEST_NONCE, STATE,
 };
-use crate::state::{AccountIndex, Config, ImpactPoint, PricePoint, SeriesState, ACCOUNTS, CONFIG, STATE};
 
 const CONTRACT_NAME: &str = "heb-bond-series";
-const CONTRACT_VERSION: &str = "0.1.0";
+const CONTRACT_VERSION: &str = "0.2.0";
+
+/// Close factor used when `SeriesTerms::close_factor_bps` is unset.
+pub const DEFAULT_CLOSE_FACTOR_BPS: u32 = 5_000;
+
+/// Grace period used when `SeriesTerms::default_grace_period_seconds` is unset.
+pub const DEFAULT_GRACE_PERIOD_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+/// Grace period used when `SeriesTerms::coupon_grace_period_seconds` is unset.
+pub const DEFAULT_COUPON_GRACE_PERIOD_SECONDS: u64 = 3 * 24 * 60 * 60;
 
-fn now_ts(env: &Env) -> u64 {
+/// Longest coupon schedule instantiate will generate, maturity included.
+pub const MAX_COUPONS: usize = 1_000;
+
+pub(crate) fn now_ts(env: &Env) -> u64 {
     env.block.time.seconds()
 }
 
@@ -27,44 +58,425 @@ fn require_not_paused(state: &SeriesState) -> Result<(), ContractError> {
     Ok(())
 }
 
+fn require_status(st: &SeriesState, allowed: &[SeriesStatus]) -> Result<(), ContractError> {
+    if !allowed.contains(&st.status) {
+        return Err(ContractError::InvalidStatus { status: st.status });
+    }
+    Ok(())
+}
+
+/// Settled and cancelled series only pay out what is left.
+fn require_live(st: &SeriesState) -> Result<(), ContractError> {
+    if matches!(st.status, SeriesStatus::Settled | SeriesStatus::Cancelled) {
+        return Err(ContractError::InvalidStatus { status: st.status });
+    }
+    Ok(())
+}
+
+/// Move the series to `next` if the transition table allows it, returning the transition event.
+fn transition(st: &mut SeriesState, next: SeriesStatus) -> Result<Option<Event>, ContractError> {
+    if st.status == next {
+        return Ok(None);
+    }
+    if !st.status.can_transition_to(next) {
+        return Err(ContractError::InvalidTransition { from: st.status, to: next });
+    }
+    let event = Event::new("series_status")
+        .add_attribute("from", st.status.as_str())
+        .add_attribute("to", next.as_str());
+    st.status = next;
+    Ok(Some(event))
+}
+
+/// A matured series whose debt is fully repaid is settled.
+fn settle_if_repaid(env: &Env, cfg: &Config, st: &mut SeriesState) -> Result<Option<Event>, ContractError> {
+    let settling = matches!(
+        st.status,
+        SeriesStatus::Matured | SeriesStatus::Liquidating | SeriesStatus::Defaulted
+    );
+    if settling && is_matured(env, cfg) && series_debt(st)?.is_zero() {
+        return transition(st, SeriesStatus::Settled);
+    }
+    Ok(None)
+}
+
+/// Leave `Liquidating` once a fresh price puts the position back at or above the liquidation ratio.
+fn exit_liquidating(env: &Env, cfg: &Config, st: &mut SeriesState) -> Result<Option<Event>, ContractError> {
+    if st.status != SeriesStatus::Liquidating {
+        return Ok(None);
+    }
+    let Ok(price) = require_fresh_price(env, cfg, st) else {
+        return Ok(None);
+    };
+    let cr = collateral_ratio_bps(st.collateral_locked, price.price, series_debt(st)?)?;
+    if cr < Uint256::from(cfg.terms.liquidation_ratio_bps) {
+        return Ok(None);
+    }
+    let next = if is_matured(env, cfg) { SeriesStatus::Matured } else { SeriesStatus::Active };
+    transition(st, next)
+}
+
+/// Status changes that follow from the passage of time alone. `st` must be accrued to now.
+fn advance_status(env: &Env, cfg: &Config, st: &mut SeriesState) -> Result<Vec<Event>, ContractError> {
+    if !is_matured(env, cfg) {
+        return Ok(vec![]);
+    }
+    let next = match st.status {
+        SeriesStatus::SaleOpen if st.total_principal_sold.is_zero() => SeriesStatus::SaleClosed,
+        SeriesStatus::SaleOpen | SeriesStatus::Active | SeriesStatus::Liquidating => SeriesStatus::Matured,
+        status => status,
+    };
+    let mut events: Vec<Event> = transition(st, next)?.into_iter().collect();
+    events.extend(settle_if_repaid(env, cfg, st)?);
+    Ok(events)
+}
+
+fn close_factor_bps(cfg: &Config) -> u32 {
+    cfg.terms.close_factor_bps.unwrap_or(DEFAULT_CLOSE_FACTOR_BPS)
+}
+
 fn is_matured(env: &Env, cfg: &Config) -> bool {
     now_ts(env) >= cfg.terms.maturity_ts
 }
 
-/// Accrue global and account-level interest indexes.
-fn accrue(deps: DepsMut, env: &Env) -> Result<(), ContractError> {
-    let cfg = CONFIG.load(deps.storage)?;
-    let mut st = STATE.load(deps.storage)?;
+/// Earliest time a series with unpaid debt can be declared in default.
+fn default_after(cfg: &Config) -> u64 {
+    let grace = cfg.terms.default_grace_period_seconds.unwrap_or(DEFAULT_GRACE_PERIOD_SECONDS);
+    cfg.terms.maturity_ts.saturating_add(grace)
+}
+
+fn coupon_grace_period(cfg: &Config) -> u64 {
+    cfg.terms.coupon_grace_period_seconds.unwrap_or(DEFAULT_COUPON_GRACE_PERIOD_SECONDS)
+}
 
-    let t = now_ts(env);
+/// Coupon dates every `period` seconds after `start`, ending with `maturity_ts`. Stops generating
+/// past `MAX_COUPONS`, so a result longer than that means the schedule is too long.
+fn coupon_dates(start: u64, maturity_ts: u64, period: u64) -> Vec<u64> {
+    let mut dates: Vec<u64> = (1..=MAX_COUPONS as u64)
+        .map_while(|k| start.checked_add(k.checked_mul(period)?))
+        .take_while(|ts| *ts < maturity_ts)
+        .collect();
+    dates.push(maturity_ts);
+    dates
+}
+
+fn next_coupon(storage: &dyn Storage, after: Option<u64>) -> StdResult<Option<Coupon>> {
+    COUPONS
+        .range(storage, after.map(Bound::exclusive), None, Order::Ascending)
+        .next()
+        .transpose()
+        .map(|c| c.map(|(_, coupon)| coupon))
+}
+
+/// Record of the coupon on `ts`, looking at coupons recorded but not yet saved first.
+fn coupon_record(storage: &dyn Storage, recorded: &[Coupon], ts: u64) -> StdResult<CouponRecord> {
+    let coupon = match recorded.iter().find(|c| c.ts == ts) {
+        Some(c) => c.clone(),
+        None => COUPONS.load(storage, ts)?,
+    };
+    coupon.record.ok_or_else(|| StdError::generic_err(format!("coupon {ts} not recorded")))
+}
+
+/// Walk the coupon dates and grace deadlines up to `t` in time order, accruing to each: a coupon
+/// date fixes the coupon's amount due and record-date supply, a deadline moves the late mark.
+/// Returns the coupons recorded, for the caller to save.
+fn pass_coupon_dates(storage: &dyn Storage, cfg: &Config, st: &mut SeriesState, t: u64) -> StdResult<Vec<Coupon>> {
+    let grace = coupon_grace_period(cfg);
+    let mut recorded: Vec<Coupon> = vec![];
+    loop {
+        let next = next_coupon(storage, st.last_coupon_ts)?.filter(|c| c.ts <= t);
+        let due = next_coupon(storage, st.coupon_due_ts)?
+            .map(|c| c.ts)
+            .filter(|ts| Some(*ts) <= st.last_coupon_ts && ts.saturating_add(grace) <= t);
+        match (next, due) {
+            (Some(mut coupon), due) if due.is_none_or(|ts| coupon.ts <= ts.saturating_add(grace)) => {
+                accrue_state(cfg, st, coupon.ts)?;
+                let before = match st.last_coupon_ts {
+                    Some(ts) => coupon_record(storage, &recorded, ts)?.interest_credited,
+                    None => Uint128::zero(),
+                };
+                coupon.record = Some(CouponRecord {
+                    amount_due: st.interest_credited.checked_sub(before)?,
+                    interest_credited: st.interest_credited,
+                    supply: st.total_supply,
+                    redemption_factor: st.redemption_factor,
+                });
+                st.last_coupon_ts = Some(coupon.ts);
+                recorded.push(coupon);
+            }
+            (_, Some(ts)) => {
+                accrue_state(cfg, st, ts.saturating_add(grace))?;
+                st.coupon_due_credited = coupon_record(storage, &recorded, ts)?.interest_credited;
+                st.coupon_due_ts = Some(ts);
+            }
+            _ => return Ok(recorded),
+        }
+    }
+}
+
+/// Premium of the latest call date at or before `t`; `None` when the series is not callable at `t`.
+fn call_premium_bps(cfg: &Config, t: u64) -> Option<u32> {
+    cfg.terms
+        .call_schedule
+        .iter()
+        .flatten()
+        .filter(|c| c.ts <= t)
+        .max_by_key(|c| c.ts)
+        .map(|c| c.premium_bps)
+}
+
+/// Each installment's date with the principal due through it, cumulative in schedule order so the
+/// amounts round to exactly their total share of the principal sold.
+fn installments_due(cfg: &Config, st: &SeriesState) -> Vec<(u64, Uint128)> {
+    let mut bps = 0u128;
+    cfg.terms
+        .amortization
+        .iter()
+        .flatten()
+        .map(|i| {
+            bps += i.principal_bps as u128;
+            (i.ts, st.total_principal_sold.multiply_ratio(bps, BPS_DENOM))
+        })
+        .collect()
+}
+
+/// Scheduled principal not paid yet through the current installment, the first one dated at or
+/// after `t`; later installments are not payable yet.
+fn installments_payable(cfg: &Config, st: &SeriesState, t: u64) -> Uint128 {
+    let due = installments_due(cfg, st);
+    let through = due.iter().find(|(ts, _)| *ts >= t).or(due.last()).map(|(_, due)| *due).unwrap_or_default();
+    through.saturating_sub(st.installments_paid)
+}
+
+/// Redeem `amount` bond tokens from every holder pro-rata and credit them `payout`, par plus any
+/// premium, to collect with `ClaimRedemption`. Only the redemption factor and premium index move;
+/// each account takes its share when it is next synced, so the cost does not grow with the number
+/// of holders.
+fn redeem_pro_rata(st: &mut SeriesState, amount: Uint128, payout: Uint128) -> StdResult<()> {
+    let supply = st.total_supply;
+    let premium = scale_factor(st.redemption_factor, payout.checked_sub(amount)?, supply)?;
+    st.redemption_premium_index = st.redemption_premium_index.checked_add(premium)?;
+    st.redemption_factor = scale_factor(st.redemption_factor, supply.checked_sub(amount)?, supply)?;
+    st.redemption_reserve = st.redemption_reserve.checked_add(payout)?;
+    st.redeemed_unsynced = st.redeemed_unsynced.checked_add(amount)?;
+    st.total_supply -= amount;
+    Ok(())
+}
+
+/// Book the tokens a sync took off an account against the redemptions that called for them.
+/// Balances round up, so accounts keep fractions of a token; once those add up to whole tokens
+/// they go back into circulation, backed by the par already paid for them. Tokens taken beyond
+/// what the redemptions left unsynced are retired.
+fn book_synced_redemption(st: &mut SeriesState, redeemed: Uint128, kept: Decimal256) -> StdResult<()> {
+    let unsynced = redeemed.min(st.redeemed_unsynced);
+    st.redeemed_unsynced -= unsynced;
+    // principal already repaid for them stays in the reserve
+    retire_tokens(st, redeemed - unsynced)?;
+
+    st.redemption_carry = st.redemption_carry.checked_add(kept)?;
+    let whole = Uint128::try_from(st.redemption_carry.to_uint_floor())?.min(st.redeemed_unsynced);
+    if !whole.is_zero() {
+        st.redemption_carry -= Decimal256::from_atomics(whole, 0).map_err(|e| StdError::generic_err(e.to_string()))?;
+        st.redeemed_unsynced -= whole;
+        st.total_supply = st.total_supply.checked_add(whole)?;
+        st.redemption_reserve = st.redemption_reserve.checked_sub(whole)?;
+        st.principal_reserve = st.principal_reserve.checked_add(whole)?;
+    }
+    Ok(())
+}
+
+/// Take `amount` bond tokens out of circulation without paying them out. The borrower no longer
+/// owes their principal; returns the part of it that was already repaid into the reserve.
+pub(crate) fn retire_tokens(st: &mut SeriesState, amount: Uint128) -> StdResult<Uint128> {
+    st.total_supply = st.total_supply.checked_sub(amount)?;
+    let forgiven = amount.min(st.total_principal_outstanding);
+    st.total_principal_outstanding -= forgiven;
+    Ok(amount - forgiven)
+}
+
+/// Interest the borrower has paid in over the life of the series, claimed or not.
+fn interest_paid_in(st: &SeriesState) -> Uint128 {
+    st.interest_credited.saturating_sub(interest_unfunded(st))
+}
+
+/// A coupon past its grace period is not fully paid.
+fn coupon_late(st: &SeriesState) -> bool {
+    interest_paid_in(st) < st.coupon_due_credited
+}
+
+/// Interest accrues until the series is settled, and no later than maturity if that happens early.
+/// Past maturity it runs at the base rate through the grace period and until `DeclareDefault`.
+fn accrual_time(cfg: &Config, st: &SeriesState, t: u64) -> u64 {
+    if st.status == SeriesStatus::Settled {
+        t.min(cfg.terms.maturity_ts)
+    } else {
+        t
+    }
+}
+
+/// Seconds since `price` was stored.
+fn price_age(env: &Env, price: &PricePoint) -> u64 {
+    now_ts(env).saturating_sub(price.ts)
+}
+
+/// Last price, if it is no older than `max_price_age_seconds`.
+fn require_fresh_price(env: &Env, cfg: &Config, st: &SeriesState) -> Result<PricePoint, ContractError> {
+    match &st.last_price {
+        Some(p) if price_age(env, p) <= cfg.terms.oracle.max_price_age_seconds => Ok(p.clone()),
+        _ => Err(ContractError::OracleStale),
+    }
+}
+
+/// Global interest index advanced from `last_accrual_ts` to `t`, without persisting it.
+fn projected_index(cfg: &Config, st: &SeriesState, t: u64) -> StdResult<Decimal256> {
+    let t = accrual_time(cfg, st, t);
     if t <= st.last_accrual_ts {
-        return Ok(());
+        return Ok(st.global_interest_index);
     }
     let dt = t - st.last_accrual_ts;
+    accrue_index(st.global_interest_index, dt, effective_apr_bps(cfg, st))
+}
 
-    // TODO: effective rate should include penalty logic based on impact checkpoints.
-    let new_index = accrue_index(&st.global_interest_index, dt, cfg.terms.base_rate_apr_bps)?;
+/// Base APR, plus the penalty APR while the series is in default, a coupon is late or the last
+/// evaluated impact checkpoint is unmet.
+///
+/// Only `CheckpointImpact`, `DeclareDefault`, coupon grace deadlines and interest payments change
+/// the outcome, and all of them accrue first, so each stretch of time is charged at the rate that
+/// applied during it.
+fn effective_apr_bps(cfg: &Config, st: &SeriesState) -> u32 {
+    let penalty = match &st.last_impact {
+        _ if st.status == SeriesStatus::Defaulted || coupon_late(st) => true,
+        Some(i) => !i.met,
+        None => false,
+    };
+    if penalty {
+        cfg.terms.base_rate_apr_bps.saturating_add(cfg.terms.penalty_rate_apr_bps)
+    } else {
+        cfg.terms.base_rate_apr_bps
+    }
+}
 
-    st.global_interest_index = new_index;
+/// Advance the global index to `t` and credit the interest it adds on the whole supply. Interest
+/// stops accruing once the series is settled.
+fn accrue_state(cfg: &Config, st: &mut SeriesState, t: u64) -> StdResult<()> {
+    let t = accrual_time(cfg, st, t);
+    if t <= st.last_accrual_ts {
+        return Ok(());
+    }
+    let index = projected_index(cfg, st, t)?;
+    let interest = accrued_interest(st.total_supply, st.global_interest_index, index)?;
+    st.interest_accrued = st.interest_accrued.checked_add(interest)?;
+    st.interest_credited = st.interest_credited.checked_add(interest)?;
+    let per_token = (index - st.global_interest_index).checked_mul(st.redemption_factor)?;
+    st.holder_interest_index = st.holder_interest_index.checked_add(per_token)?;
+    st.global_interest_index = index;
     st.last_accrual_ts = t;
+    Ok(())
+}
+
+/// Pass coupon dates that are due and accrue the global interest index to now.
+pub(crate) fn accrue(deps: DepsMut, env: &Env) -> Result<(), ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut st = STATE.load(deps.storage)?;
+    for coupon in pass_coupon_dates(deps.storage, &cfg, &mut st, now_ts(env))? {
+        COUPONS.save(deps.storage, coupon.ts, &coupon)?;
+    }
+    accrue_state(&cfg, &mut st, now_ts(env))?;
     STATE.save(deps.storage, &st)?;
     Ok(())
 }
 
-/// Sync a single account accrued interest based on global index.
-fn sync_account(deps: DepsMut, addr: &Addr) -> Result<(), ContractError> {
-    let st = STATE.load(deps.storage)?;
-    let mut acc = ACCOUNTS
-        .may_load(deps.storage, addr.as_str())?
-        .unwrap_or(AccountIndex {
-            balance: "0".to_string(),
-            index: st.global_interest_index.clone(),
-            accrued: "0".to_string(),
-        });
+/// State as the next execute would see it, with coupon dates passed and interest accrued to now.
+fn projected_state(deps: Deps, env: &Env, cfg: &Config) -> StdResult<SeriesState> {
+    let mut st = STATE.load(deps.storage)?;
+    pass_coupon_dates(deps.storage, cfg, &mut st, now_ts(env))?;
+    accrue_state(cfg, &mut st, now_ts(env))?;
+    Ok(st)
+}
+
+/// Collateral covering the full `principal_cap` at `initial_collateral_ratio_bps`.
+fn sale_collateral_required(cfg: &Config, price: Decimal256) -> StdResult<Uint128> {
+    required_collateral(cfg.terms.principal_cap, cfg.terms.initial_collateral_ratio_bps, price)
+}
+
+/// Interest due to holders that the borrower has not paid in yet.
+fn interest_unfunded(st: &SeriesState) -> Uint128 {
+    st.interest_accrued.saturating_sub(st.interest_funded)
+}
+
+/// Outstanding principal plus unfunded interest.
+fn series_debt(st: &SeriesState) -> StdResult<Uint128> {
+    Ok(st.total_principal_outstanding.checked_add(interest_unfunded(st))?)
+}
 
-    // TODO: compute delta = balance * (global_index - acc.index), add to accrued
-    acc.index = st.global_interest_index.clone();
+/// Principal a liquidation could repay at `price`: `close_factor_bps` of the debt, bounded by the
+/// outstanding principal and by what the locked collateral covers including the bonus.
+fn max_liquidation_repay(cfg: &Config, st: &SeriesState, price: Decimal256) -> StdResult<Uint128> {
+    let max_close = bps_of_ceil(series_debt(st)?, close_factor_bps(cfg))?;
+    let covered = liquidation_repay_for(st.collateral_locked, cfg.terms.liquidation_bonus_bps, price)?;
+    Ok(max_close.min(st.total_principal_outstanding).min(covered))
+}
+
+/// Load an account, or a fresh zero-balance account checkpointed at the current state.
+fn load_account(deps: Deps, addr: &str, st: &SeriesState) -> StdResult<AccountIndex> {
+    Ok(ACCOUNTS.may_load(deps.storage, addr)?.unwrap_or(AccountIndex {
+        balance: Uint128::zero(),
+        index: st.holder_interest_index,
+        accrued: Uint128::zero(),
+        redeemed: Uint128::zero(),
+        redemption_payout: Uint128::zero(),
+        redemption_factor: st.redemption_factor,
+        redemption_premium_index: st.redemption_premium_index,
+    }))
+}
+
+/// Bring `acc` up to the state's indexes: credit the interest its balance earned while redemptions
+/// shrank it, take its whole tokens redeemed since out of the balance at par plus their share of
+/// the premium, and checkpoint. Returns the tokens taken and the fraction of a token kept.
+fn settle_account(acc: &mut AccountIndex, st: &SeriesState) -> StdResult<(Uint128, Decimal256)> {
+    let earned = factor_share(acc.balance, acc.redemption_factor, acc.index, st.holder_interest_index)?;
+    acc.accrued = acc.accrued.checked_add(earned)?;
+    acc.index = st.holder_interest_index;
+
+    let (balance, kept) = scale_balance(acc.balance, acc.redemption_factor, st.redemption_factor)?;
+    let redeemed = acc.balance - balance;
+    let premium = factor_share(
+        redeemed,
+        acc.redemption_factor - st.redemption_factor,
+        acc.redemption_premium_index,
+        st.redemption_premium_index,
+    )?;
+    acc.balance = balance;
+    acc.redeemed = acc.redeemed.checked_add(redeemed)?;
+    acc.redemption_payout = acc.redemption_payout.checked_add(redeemed)?.checked_add(premium)?;
+    acc.redemption_factor = st.redemption_factor;
+    acc.redemption_premium_index = st.redemption_premium_index;
+    Ok((redeemed, kept))
+}
+
+/// Sync a single account with the global indexes: interest accrued and pro-rata redemptions.
+///
+/// Must run after `accrue` and before any change to the account balance. A sync can move supply
+/// and reserves, so callers reload `SeriesState` after it.
+pub(crate) fn sync_account(deps: DepsMut, addr: &Addr) -> Result<(), ContractError> {
+    let mut st = STATE.load(deps.storage)?;
+    let mut acc = load_account(deps.as_ref(), addr.as_str(), &st)?;
+    // the balance has not moved since the latest coupon date unless a sync since then recorded it
+    if let Some(ts) = st.last_coupon_ts {
+        if !COUPON_BALANCES.has(deps.storage, (addr.as_str(), ts)) {
+            let recorded = CouponBalance {
+                balance: acc.balance,
+                redemption_factor: acc.redemption_factor,
+            };
+            COUPON_BALANCES.save(deps.storage, (addr.as_str(), ts), &recorded)?;
+        }
+    }
+    let (redeemed, kept) = settle_account(&mut acc, &st)?;
     ACCOUNTS.save(deps.storage, addr.as_str(), &acc)?;
+    if !redeemed.is_zero() || !kept.is_zero() {
+        book_synced_redemption(&mut st, redeemed, kept)?;
+        STATE.save(deps.storage, &st)?;
+    }
     Ok(())
 }
 
@@ -79,6 +491,33 @@ pub fn instantiate(deps: DepsMut, env: Env, _info: MessageInfo, msg: Instantiate
     if msg.terms.maturity_ts <= now_ts(&env) {
         return Err(ContractError::InvalidConfig("maturity must be in future".into()));
     }
+    if let Some(cf) = msg.terms.close_factor_bps {
+        if cf == 0 || cf as u128 > BPS_DENOM {
+            return Err(ContractError::InvalidConfig("close factor must be in (0, 10000] bps".into()));
+        }
+    }
+    let coupons = match msg.terms.coupon_period_seconds {
+        Some(0) => return Err(ContractError::InvalidConfig("coupon period must be positive".into())),
+        Some(period) => coupon_dates(now_ts(&env), msg.terms.maturity_ts, period),
+        None => vec![],
+    };
+    if coupons.len() > MAX_COUPONS {
+        return Err(ContractError::InvalidConfig(format!("coupon schedule exceeds {MAX_COUPONS} dates")));
+    }
+    let call_dates: Vec<u64> = msg.terms.call_schedule.iter().flatten().map(|c| c.ts).collect();
+    if call_dates.windows(2).any(|w| w[0] >= w[1]) || call_dates.last().is_some_and(|ts| *ts >= msg.terms.maturity_ts) {
+        return Err(ContractError::InvalidConfig("call dates must increase and precede maturity".into()));
+    }
+    let installments = msg.terms.amortization.as_deref().unwrap_or_default();
+    if installments.windows(2).any(|w| w[0].ts >= w[1].ts)
+        || installments.last().is_some_and(|i| i.ts > msg.terms.maturity_ts)
+    {
+        return Err(ContractError::InvalidConfig("installment dates must increase up to maturity".into()));
+    }
+    let installment_bps: u128 = installments.iter().map(|i| i.principal_bps as u128).sum();
+    if installments.iter().any(|i| i.principal_bps == 0) || installment_bps > BPS_DENOM {
+        return Err(ContractError::InvalidConfig("installments must be positive and total at most 10000 bps".into()));
+    }
 
     let cfg = Config {
         admin: msg.admin,
@@ -88,38 +527,136 @@ pub fn instantiate(deps: DepsMut, env: Env, _info: MessageInfo, msg: Instantiate
     };
 
     let st = SeriesState {
-        sale_open: false,
+        status: SeriesStatus::Created,
         paused: false,
-        total_principal_sold: "0".to_string(),
-        total_principal_outstanding: "0".to_string(),
-        collateral_locked: "0".to_string(),
-        global_interest_index: "1".to_string(),
+        total_principal_sold: Uint128::zero(),
+        total_principal_outstanding: Uint128::zero(),
+        principal_reserve: Uint128::zero(),
+        redemption_reserve: Uint128::zero(),
+        redeemed_unsynced: Uint128::zero(),
+        installments_paid: Uint128::zero(),
+        total_supply: Uint128::zero(),
+        interest_accrued: Uint128::zero(),
+        interest_funded: Uint128::zero(),
+        interest_credited: Uint128::zero(),
+        collateral_locked: Uint128::zero(),
+        global_interest_index: initial_index(),
         last_accrual_ts: now_ts(&env),
+        holder_interest_index: initial_index(),
+        redemption_factor: initial_index(),
+        redemption_premium_index: Decimal256::zero(),
+        redemption_carry: Decimal256::zero(),
         last_price: None,
         last_impact: None,
+        defaulted_at: None,
+        recovery: None,
+        last_coupon_ts: None,
+        coupon_due_ts: None,
+        coupon_due_credited: Uint128::zero(),
     };
 
     CONFIG.save(deps.storage, &cfg)?;
     STATE.save(deps.storage, &st)?;
+    for ts in &coupons {
+        COUPONS.save(deps.storage, *ts, &Coupon { ts: *ts, record: None })?;
+    }
 
-    Ok(Response::new().add_attribute("action", "instantiate"))
+    let mut res = Response::new()
+        .add_attribute("action", "instantiate")
+        .add_attribute("coupons", coupons.len().to_string());
+    if is_token_factory(&cfg) {
+        let series = &env.contract.address;
+        res = res
+            .add_message(create_denom_msg(series))
+            .add_message(set_before_send_hook_msg(series))
+            .add_attribute("bond_denom", bond_denom(series));
+    }
+    Ok(res)
 }
 
 #[entry_point]
-pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> Result<Response, ContractError> {
-    match msg {
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidConfig(format!(
+            "cannot migrate from contract {}",
+            stored.contract
+        )));
+    }
+    let from = parse_version(&stored.version)?;
+    if from > parse_version(CONTRACT_VERSION)? {
+        return Err(ContractError::CannotDowngrade {
+            from: stored.version,
+            to: CONTRACT_VERSION.to_string(),
+        });
+    }
+    for (version, step) in migrations::STEPS {
+        if parse_version(version)? >= from {
+            step(deps.storage)?;
+        }
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+#[entry_point]
+pub fn execute(mut deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> Result<Response, ContractError> {
+    // apply time-driven transitions (maturity) before the handler checks the status
+    accrue(deps.branch(), &env)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut st = STATE.load(deps.storage)?;
+    let events = advance_status(&env, &cfg, &mut st)?;
+    STATE.save(deps.storage, &st)?;
+
+    let res = match msg {
         ExecuteMsg::DepositCollateral {} => execute_deposit_collateral(deps, env, info),
+        ExecuteMsg::WithdrawCollateral { amount } => execute_withdraw_collateral(deps, env, info, amount),
         ExecuteMsg::OpenSale {} => execute_open_sale(deps, env, info),
+        ExecuteMsg::CloseSale {} => execute_close_sale(deps, env, info),
+        ExecuteMsg::Cancel {} => execute_cancel(deps, env, info),
         ExecuteMsg::Buy { min_tokens } => execute_buy(deps, env, info, min_tokens),
         ExecuteMsg::Repay {} => execute_repay(deps, env, info),
+        ExecuteMsg::PayCoupon {} => execute_pay_coupon(deps, env, info),
         ExecuteMsg::ClaimInterest {} => execute_claim_interest(deps, env, info),
         ExecuteMsg::RedeemAtMaturity { amount } => execute_redeem_at_maturity(deps, env, info, amount),
+        ExecuteMsg::ClaimDefaultRecovery { amount } => execute_claim_default_recovery(deps, env, info, amount),
+        ExecuteMsg::CallBonds { amount } => execute_call_bonds(deps, env, info, amount),
+        ExecuteMsg::ClaimRedemption {} => execute_claim_redemption(deps, env, info),
         ExecuteMsg::Liquidate { max_repay } => execute_liquidate(deps, env, info, max_repay),
         ExecuteMsg::UpdateOraclePrice {} => execute_update_oracle_price(deps, env, info),
         ExecuteMsg::CheckpointImpact {} => execute_checkpoint_impact(deps, env, info),
+        ExecuteMsg::DeclareDefault {} => execute_declare_default(deps, env, info),
         ExecuteMsg::Pause {} => execute_pause(deps, env, info),
         ExecuteMsg::Unpause {} => execute_unpause(deps, env, info),
-        ExecuteMsg::Transfer { recipient, amount } => execute_transfer(deps, env, info, recipient, amount),
+        ExecuteMsg::Transfer { recipient, amount } => token::execute_transfer(deps, env, info, recipient, amount),
+        ExecuteMsg::Send { contract, amount, msg } => token::execute_send(deps, env, info, contract, amount, msg),
+        ExecuteMsg::Burn { amount } => token::execute_burn(deps, env, info, amount),
+        ExecuteMsg::IncreaseAllowance { spender, amount, expires } => {
+            token::execute_increase_allowance(deps, env, info, spender, amount, expires)
+        }
+        ExecuteMsg::DecreaseAllowance { spender, amount, expires } => {
+            token::execute_decrease_allowance(deps, env, info, spender, amount, expires)
+        }
+        ExecuteMsg::TransferFrom { owner, recipient, amount } => {
+            token::execute_transfer_from(deps, env, info, owner, recipient, amount)
+        }
+        ExecuteMsg::SendFrom { owner, contract, amount, msg } => {
+            token::execute_send_from(deps, env, info, owner, contract, amount, msg)
+        }
+        ExecuteMsg::BurnFrom { owner, amount } => token::execute_burn_from(deps, env, info, owner, amount),
+    }?;
+    Ok(res.add_events(events))
+}
+
+#[entry_point]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::BlockBeforeSend { from, to, amount } => token::sudo_block_before_send(deps, env, from, to, amount),
+        SudoMsg::TrackBeforeSend { .. } => Ok(Response::new()),
     }
 }
 
@@ -158,17 +695,93 @@ fn execute_deposit_collateral(deps: DepsMut, env: Env, info: MessageInfo) -> Res
     if is_matured(&env, &cfg) {
         return Err(ContractError::Matured);
     }
+    require_status(
+        &st,
+        &[
+            SeriesStatus::Created,
+            SeriesStatus::Collateralized,
+            SeriesStatus::SaleOpen,
+            SeriesStatus::SaleClosed,
+            SeriesStatus::Active,
+            SeriesStatus::Liquidating,
+        ],
+    )?;
 
     let paid = must_pay(&info, &cfg.terms.collateral_denom)?;
-    let current = parse_u128(&st.collateral_locked)?;
-    st.collateral_locked = to_string_u128(current + paid.u128());
+    st.collateral_locked = st.collateral_locked.checked_add(paid)?;
+    let event = match st.status {
+        SeriesStatus::Created => transition(&mut st, SeriesStatus::Collateralized)?,
+        _ => exit_liquidating(&env, &cfg, &mut st)?,
+    };
 
     STATE.save(deps.storage, &st)?;
     Ok(Response::new()
+        .add_events(event)
         .add_attribute("action", "deposit_collateral")
         .add_attribute("amount", paid.to_string()))
 }
 
+fn execute_withdraw_collateral(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    accrue(deps.branch(), &env)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != Addr::unchecked(cfg.terms.borrower.clone()) {
+        return Err(ContractError::Unauthorized);
+    }
+    let mut st = STATE.load(deps.storage)?;
+    require_status(
+        &st,
+        &[
+            SeriesStatus::Collateralized,
+            SeriesStatus::SaleOpen,
+            SeriesStatus::SaleClosed,
+            SeriesStatus::Active,
+            SeriesStatus::Matured,
+            SeriesStatus::Settled,
+            SeriesStatus::Cancelled,
+        ],
+    )?;
+    if amount.is_zero() || amount > st.collateral_locked {
+        return Err(ContractError::InsufficientFunds);
+    }
+    let remaining = st.collateral_locked - amount;
+
+    let settled = matches!(st.status, SeriesStatus::Settled | SeriesStatus::Cancelled);
+    let mut res = Response::new();
+    if !settled {
+        require_not_paused(&st)?;
+        let price = require_fresh_price(&env, &cfg, &st)?.price;
+        // an open sale can still mint up to the cap, so keep the OpenSale gate covered
+        let mut debt = series_debt(&st)?;
+        if st.status == SeriesStatus::SaleOpen {
+            debt = debt.max(cfg.terms.principal_cap);
+        }
+        let cr_after = collateral_ratio_bps(remaining, price, debt)?;
+        if cr_after < Uint256::from(cfg.terms.initial_collateral_ratio_bps) {
+            return Err(ContractError::CollateralTooLow);
+        }
+        res = res
+            .add_attribute("price", price.to_string())
+            .add_attribute("cr_after_bps", cr_after.to_string());
+    }
+
+    st.collateral_locked = remaining;
+    STATE.save(deps.storage, &st)?;
+
+    Ok(res
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin::new(amount.u128(), cfg.terms.collateral_denom)],
+        })
+        .add_attribute("action", "withdraw_collateral")
+        .add_attribute("amount", amount)
+        .add_attribute("settled", settled.to_string()))
+}
+
 fn execute_open_sale(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
     if info.sender != Addr::unchecked(cfg.terms.borrower.clone()) {
@@ -179,71 +792,160 @@ fn execute_open_sale(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Respo
     if is_matured(&env, &cfg) {
         return Err(ContractError::Matured);
     }
-    st.sale_open = true;
+    require_status(&st, &[SeriesStatus::Collateralized, SeriesStatus::SaleClosed])?;
+    let price = require_fresh_price(&env, &cfg, &st)?.price;
+    let required = sale_collateral_required(&cfg, price)?;
+    if st.collateral_locked < required {
+        return Err(ContractError::CollateralTooLow);
+    }
+    let event = transition(&mut st, SeriesStatus::SaleOpen)?;
+    STATE.save(deps.storage, &st)?;
+    Ok(Response::new()
+        .add_events(event)
+        .add_attribute("action", "open_sale")
+        .add_attribute("price", price.to_string())
+        .add_attribute("collateral_required", required)
+        .add_attribute("collateral_locked", st.collateral_locked))
+}
+
+fn execute_close_sale(deps: DepsMut, _env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != Addr::unchecked(cfg.terms.borrower.clone()) {
+        return Err(ContractError::Unauthorized);
+    }
+    let mut st = STATE.load(deps.storage)?;
+    if st.status != SeriesStatus::SaleOpen {
+        return Err(ContractError::SaleNotOpen);
+    }
+    let next = if st.total_principal_sold.is_zero() {
+        SeriesStatus::SaleClosed
+    } else {
+        SeriesStatus::Active
+    };
+    let event = transition(&mut st, next)?;
     STATE.save(deps.storage, &st)?;
-    Ok(Response::new().add_attribute("action", "open_sale"))
+    Ok(Response::new()
+        .add_events(event)
+        .add_attribute("action", "close_sale")
+        .add_attribute("total_principal_sold", st.total_principal_sold))
+}
+
+fn execute_cancel(deps: DepsMut, _env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != Addr::unchecked(cfg.terms.borrower.clone()) && info.sender != Addr::unchecked(cfg.admin.clone()) {
+        return Err(ContractError::Unauthorized);
+    }
+    let mut st = STATE.load(deps.storage)?;
+    require_status(
+        &st,
+        &[SeriesStatus::Created, SeriesStatus::Collateralized, SeriesStatus::SaleClosed],
+    )?;
+    let event = transition(&mut st, SeriesStatus::Cancelled)?;
+    STATE.save(deps.storage, &st)?;
+    Ok(Response::new()
+        .add_events(event)
+        .add_attribute("action", "cancel")
+        .add_attribute("collateral_locked", st.collateral_locked))
 }
 
 fn execute_buy(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    _min_tokens: Option<String>,
+    min_tokens: Option<Uint128>,
 ) -> Result<Response, ContractError> {
     accrue(deps.branch(), &env)?;
     let cfg = CONFIG.load(deps.storage)?;
-    let mut st = STATE.load(deps.storage)?;
+    let st = STATE.load(deps.storage)?;
     require_not_paused(&st)?;
-    if !st.sale_open {
-        return Err(ContractError::SaleNotOpen);
-    }
     if is_matured(&env, &cfg) {
         return Err(ContractError::Matured);
     }
+    if st.status != SeriesStatus::SaleOpen {
+        return Err(ContractError::SaleNotOpen);
+    }
+    // installments redeemed every token while the sale was open; new tokens would have no factor
+    if st.redemption_factor.is_zero() {
+        return Err(ContractError::FullyRedeemed);
+    }
+    require_fresh_price(&env, &cfg, &st)?;
 
     let paid = must_pay(&info, &cfg.terms.principal_denom)?;
-    let paid_u = paid.u128();
 
-    // TODO: enforce principal_cap
-    // Mint bond tokens 1:1 with principal paid
+    // fill up to the cap; only buyers that set a slippage floor accept a partial fill
+    let remaining = cfg.terms.principal_cap.saturating_sub(st.total_principal_sold);
+    let filled = paid.min(remaining);
+    let min_tokens = match min_tokens {
+        Some(min_tokens) => min_tokens,
+        None if filled < paid => return Err(ContractError::PrincipalCapExceeded { remaining }),
+        None => paid,
+    };
+    if filled.is_zero() || filled < min_tokens {
+        return Err(ContractError::MinTokensNotMet { filled, min_tokens });
+    }
+    let refund = paid - filled;
+
+    // Mint bond tokens 1:1 with principal filled
     let buyer = info.sender.clone();
     sync_account(deps.branch(), &buyer)?;
     let mut acc = ACCOUNTS.load(deps.storage, buyer.as_str())?;
-    let bal = parse_u128(&acc.balance)?;
-    acc.balance = to_string_u128(bal + paid_u);
+    acc.balance = acc.balance.checked_add(filled)?;
     ACCOUNTS.save(deps.storage, buyer.as_str(), &acc)?;
 
     // Update totals
-    let sold = parse_u128(&st.total_principal_sold)?;
-    let out = parse_u128(&st.total_principal_outstanding)?;
-    st.total_principal_sold = to_string_u128(sold + paid_u);
-    st.total_principal_outstanding = to_string_u128(out + paid_u);
+    let mut st = STATE.load(deps.storage)?;
+    st.total_principal_sold = st.total_principal_sold.checked_add(filled)?;
+    st.total_principal_outstanding = st.total_principal_outstanding.checked_add(filled)?;
+    st.total_supply = st.total_supply.checked_add(filled)?;
+    let sale_closed = st.total_principal_sold >= cfg.terms.principal_cap;
+    let mut event = None;
+    if sale_closed {
+        event = transition(&mut st, SeriesStatus::Active)?;
+    }
     STATE.save(deps.storage, &st)?;
 
     // Fee + proceeds transfer to borrower
-    let fee_bps = cfg.protocol_fee_bps as u128;
-    let fee = (paid_u * fee_bps) / 10_000u128;
-    let net = paid_u - fee;
+    let fee = filled.multiply_ratio(cfg.protocol_fee_bps, BPS_DENOM);
+    let net = filled - fee;
 
     let mut msgs = vec![];
-    if fee > 0 {
+    if !fee.is_zero() {
         msgs.push(BankMsg::Send {
             to_address: cfg.fee_recipient.clone(),
-            amount: vec![Coin::new(fee, cfg.terms.principal_denom.clone())],
+            amount: vec![Coin::new(fee.u128(), cfg.terms.principal_denom.clone())],
         });
     }
     msgs.push(BankMsg::Send {
         to_address: cfg.terms.borrower.clone(),
-        amount: vec![Coin::new(net, cfg.terms.principal_denom.clone())],
+        amount: vec![Coin::new(net.u128(), cfg.terms.principal_denom.clone())],
     });
+    if !refund.is_zero() {
+        msgs.push(BankMsg::Send {
+            to_address: buyer.to_string(),
+            amount: vec![Coin::new(refund.u128(), cfg.terms.principal_denom.clone())],
+        });
+    }
 
-    Ok(Response::new()
+    let mut res = Response::new().add_events(event);
+    if is_token_factory(&cfg) {
+        // mint into the series and forward, so the before-send hook sees a send from the series
+        let tokens = Coin::new(filled.u128(), bond_denom(&env.contract.address));
+        res = res.add_message(mint_msg(&env.contract.address, &tokens)).add_message(BankMsg::Send {
+            to_address: buyer.to_string(),
+            amount: vec![tokens],
+        });
+    }
+
+    Ok(res
         .add_messages(msgs)
         .add_attribute("action", "buy")
-        .add_attribute("paid", paid.to_string()))
+        .add_attribute("paid", paid.to_string())
+        .add_attribute("filled", filled.to_string())
+        .add_attribute("refund", refund.to_string())
+        .add_attribute("sale_closed", sale_closed.to_string()))
 }
 
-fn execute_repay(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+fn execute_repay(mut deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
     accrue(deps.branch(), &env)?;
     let cfg = CONFIG.load(deps.storage)?;
     if info.sender != Addr::unchecked(cfg.terms.borrower.clone()) {
@@ -251,39 +953,133 @@ fn execute_repay(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response,
     }
 
     let mut st = STATE.load(deps.storage)?;
-    require_not_paused(&st)?;
+    require_status(
+        &st,
+        &[
+            SeriesStatus::SaleOpen,
+            SeriesStatus::Active,
+            SeriesStatus::Liquidating,
+            SeriesStatus::Matured,
+            SeriesStatus::Defaulted,
+        ],
+    )?;
+    if st.recovery.is_some() {
+        return Err(ContractError::RecoveryStarted);
+    }
     let paid = must_pay(&info, &cfg.terms.principal_denom)?;
-    // TODO: apply to interest first, then principal.
-    // v0.1: reduce principal outstanding directly for now.
-    let out = parse_u128(&st.total_principal_outstanding)?;
-    let repay = paid.u128().min(out);
-    st.total_principal_outstanding = to_string_u128(out - repay);
+
+    // waterfall: unfunded interest into the reserve, then installments, then principal left for
+    // maturity, then refund
+    let to_interest = paid.min(interest_unfunded(&st));
+    st.interest_funded = st.interest_funded.checked_add(to_interest)?;
+    let to_principal = (paid - to_interest).min(st.total_principal_outstanding);
+    st.total_principal_outstanding -= to_principal;
+    let to_installments = to_principal.min(installments_payable(&cfg, &st, now_ts(&env))).min(st.total_supply);
+    st.installments_paid = st.installments_paid.checked_add(to_installments)?;
+    if !to_installments.is_zero() {
+        redeem_pro_rata(&mut st, to_installments, to_installments)?;
+    }
+    st.principal_reserve = st.principal_reserve.checked_add(to_principal - to_installments)?;
+    let mut events: Vec<Event> = exit_liquidating(&env, &cfg, &mut st)?.into_iter().collect();
+    events.extend(settle_if_repaid(&env, &cfg, &mut st)?);
+    // amortized away before maturity
+    if st.status == SeriesStatus::Active && st.total_supply.is_zero() && series_debt(&st)?.is_zero() {
+        events.extend(transition(&mut st, SeriesStatus::Settled)?);
+    }
     STATE.save(deps.storage, &st)?;
 
-    Ok(Response::new()
+    let refund = paid - to_interest - to_principal;
+    let mut res = Response::new().add_events(events);
+    if !refund.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin::new(refund.u128(), cfg.terms.principal_denom)],
+        });
+    }
+
+    Ok(res
         .add_attribute("action", "repay")
-        .add_attribute("amount", repay.to_string()))
+        .add_attribute("amount", (to_interest + to_principal).to_string())
+        .add_attribute("interest_funded", to_interest.to_string())
+        .add_attribute("principal_repaid", to_principal.to_string())
+        .add_attribute("installments_paid", to_installments.to_string())
+        .add_attribute("refund", refund.to_string()))
+}
+
+/// Fund unpaid interest without touching principal. Coupons are paid in date order because the
+/// late mark only compares the total paid in against what was due.
+fn execute_pay_coupon(mut deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    accrue(deps.branch(), &env)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != Addr::unchecked(cfg.terms.borrower.clone()) {
+        return Err(ContractError::Unauthorized);
+    }
+    if cfg.terms.coupon_period_seconds.is_none() {
+        return Err(ContractError::NoCouponSchedule);
+    }
+
+    let mut st = STATE.load(deps.storage)?;
+    require_status(
+        &st,
+        &[SeriesStatus::SaleOpen, SeriesStatus::Active, SeriesStatus::Liquidating, SeriesStatus::Matured],
+    )?;
+    let paid = must_pay(&info, &cfg.terms.principal_denom)?;
+    let funded = paid.min(interest_unfunded(&st));
+    if funded.is_zero() {
+        return Err(ContractError::NoInterestDue);
+    }
+    st.interest_funded = st.interest_funded.checked_add(funded)?;
+    let mut events: Vec<Event> = exit_liquidating(&env, &cfg, &mut st)?.into_iter().collect();
+    events.extend(settle_if_repaid(&env, &cfg, &mut st)?);
+    STATE.save(deps.storage, &st)?;
+
+    let refund = paid - funded;
+    let mut res = Response::new().add_events(events);
+    if !refund.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin::new(refund.u128(), cfg.terms.principal_denom)],
+        });
+    }
+
+    Ok(res
+        .add_attribute("action", "pay_coupon")
+        .add_attribute("amount", funded)
+        .add_attribute("refund", refund)
+        .add_attribute("coupon_late", coupon_late(&st).to_string()))
 }
 
-fn execute_claim_interest(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+fn execute_claim_interest(mut deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
     accrue(deps.branch(), &env)?;
     let sender = info.sender.clone();
     sync_account(deps.branch(), &sender)?;
     let mut acc = ACCOUNTS.load(deps.storage, sender.as_str())?;
 
-    let accrued = parse_u128(&acc.accrued)?;
-    if accrued == 0 {
+    let accrued = acc.accrued;
+    if accrued.is_zero() {
         return Err(ContractError::NothingToClaim);
     }
 
     let cfg = CONFIG.load(deps.storage)?;
-    acc.accrued = "0".to_string();
+    let mut st = STATE.load(deps.storage)?;
+    if accrued > st.interest_funded {
+        return Err(ContractError::InterestNotFunded {
+            funded: st.interest_funded,
+            requested: accrued,
+        });
+    }
+    acc.accrued = Uint128::zero();
     ACCOUNTS.save(deps.storage, sender.as_str(), &acc)?;
 
+    // per-account credits round down, so the global total can only be ahead of their sum
+    st.interest_funded -= accrued;
+    st.interest_accrued = st.interest_accrued.saturating_sub(accrued);
+    STATE.save(deps.storage, &st)?;
+
     // Pay interest in principal denom (stablecoin)
     let msg = BankMsg::Send {
         to_address: sender.to_string(),
-        amount: vec![Coin::new(accrued, cfg.terms.principal_denom)],
+        amount: vec![Coin::new(accrued.u128(), cfg.terms.principal_denom)],
     };
 
     Ok(Response::new()
@@ -292,173 +1088,480 @@ fn execute_claim_interest(deps: DepsMut, env: Env, info: MessageInfo) -> Result<
         .add_attribute("amount", accrued.to_string()))
 }
 
+/// Burn `amount` of the sender's bond tokens ahead of a payout. In TokenFactory mode the tokens
+/// must be attached as funds and the returned message burns them.
+fn burn_for_payout(
+    mut deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    cfg: &Config,
+    amount: Uint128,
+) -> Result<Option<CosmosMsg>, ContractError> {
+    let mut burn = None;
+    if is_token_factory(cfg) {
+        let denom = bond_denom(&env.contract.address);
+        if must_pay(info, &denom)? != amount {
+            return Err(ContractError::InsufficientFunds);
+        }
+        burn = Some(burn_msg(&env.contract.address, &Coin::new(amount.u128(), denom)));
+    }
+
+    sync_account(deps.branch(), &info.sender)?;
+    let mut acc = ACCOUNTS.load(deps.storage, info.sender.as_str())?;
+    if amount.is_zero() || amount > acc.balance {
+        return Err(ContractError::InsufficientFunds);
+    }
+    acc.balance -= amount;
+    ACCOUNTS.save(deps.storage, info.sender.as_str(), &acc)?;
+
+    let mut st = STATE.load(deps.storage)?;
+    st.total_supply = st.total_supply.checked_sub(amount)?;
+    STATE.save(deps.storage, &st)?;
+    Ok(burn)
+}
+
+/// Redeem bond tokens 1:1 for principal out of the principal reserve.
 fn execute_redeem_at_maturity(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    amount: String,
+    amount: Uint128,
 ) -> Result<Response, ContractError> {
     accrue(deps.branch(), &env)?;
     let cfg = CONFIG.load(deps.storage)?;
     if !is_matured(&env, &cfg) {
         return Err(ContractError::NotMatured);
     }
-
-    let sender = info.sender.clone();
-    sync_account(deps.branch(), &sender)?;
-    let mut acc = ACCOUNTS.load(deps.storage, sender.as_str())?;
-    let amt = parse_u128(&amount)?;
-    let bal = parse_u128(&acc.balance)?;
-    if amt == 0 || amt > bal {
-        return Err(ContractError::InsufficientFunds);
+    let st = STATE.load(deps.storage)?;
+    if !matches!(st.status, SeriesStatus::Matured | SeriesStatus::Liquidating | SeriesStatus::Settled) {
+        return Err(ContractError::InvalidStatus { status: st.status });
+    }
+    if amount > st.principal_reserve {
+        return Err(ContractError::PrincipalNotRepaid {
+            available: st.principal_reserve,
+            requested: amount,
+        });
     }
 
-    // Burn tokens (reduce balance)
-    acc.balance = to_string_u128(bal - amt);
-    ACCOUNTS.save(deps.storage, sender.as_str(), &acc)?;
-
-    // Pay principal in stablecoin
+    let burn = burn_for_payout(deps.branch(), &env, &info, &cfg, amount)?;
     let mut st = STATE.load(deps.storage)?;
-    let out = parse_u128(&st.total_principal_outstanding)?;
-    let pay = amt.min(out);
-    st.total_principal_outstanding = to_string_u128(out - pay);
+    st.principal_reserve -= amount;
     STATE.save(deps.storage, &st)?;
 
-    let msg = BankMsg::Send {
-        to_address: sender.to_string(),
-        amount: vec![Coin::new(pay, cfg.terms.principal_denom)],
-    };
-
     Ok(Response::new()
-        .add_message(msg)
+        .add_messages(burn)
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin::new(amount.u128(), cfg.terms.principal_denom)],
+        })
         .add_attribute("action", "redeem_at_maturity")
-        .add_attribute("amount", pay.to_string()))
+        .add_attribute("amount", amount.to_string()))
 }
 
-fn execute_liquidate(
-    deps: DepsMut,
+/// Burn bond tokens of a defaulted series for a pro-rata share of the collateral and the principal
+/// reserve, at the rate fixed by the recovery snapshot.
+fn execute_claim_default_recovery(
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    max_repay: String,
+    amount: Uint128,
 ) -> Result<Response, ContractError> {
     accrue(deps.branch(), &env)?;
     let cfg = CONFIG.load(deps.storage)?;
     let mut st = STATE.load(deps.storage)?;
-    require_not_paused(&st)?;
+    require_status(&st, &[SeriesStatus::Defaulted])?;
+    // the first claim fixes the pools; repayments stop from here on
+    let snap = st
+        .recovery
+        .get_or_insert_with(|| RecoverySnapshot {
+            ts: now_ts(&env),
+            supply: st.total_supply,
+            collateral: st.collateral_locked,
+            principal_reserve: st.principal_reserve,
+        })
+        .clone();
+    STATE.save(deps.storage, &st)?;
 
-    // TODO: enforce oracle freshness + compute collateral ratio using price.
-    // For now, require a price to be present; otherwise block.
-    let price = st.last_price.clone().ok_or(ContractError::OracleStale)?;
-    let _ = price;
+    let burn = burn_for_payout(deps.branch(), &env, &info, &cfg, amount)?;
+    let mut st = STATE.load(deps.storage)?;
+    // fractions of redeemed tokens booked back after the snapshot can add a token or two to the
+    // supply it fixed
+    let collateral_out = snap.collateral.multiply_ratio(amount, snap.supply).min(st.collateral_locked);
+    let principal_out = snap.principal_reserve.multiply_ratio(amount, snap.supply).min(st.principal_reserve);
+    st.collateral_locked = st.collateral_locked.checked_sub(collateral_out)?;
+    st.principal_reserve = st.principal_reserve.checked_sub(principal_out)?;
+    // once every token is claimed the borrower can withdraw the rounding dust
+    let event = match st.total_supply.is_zero() {
+        true => transition(&mut st, SeriesStatus::Settled)?,
+        false => None,
+    };
+    STATE.save(deps.storage, &st)?;
 
-    let paid = must_pay(&info, &cfg.terms.principal_denom)?;
-    let max_repay_u = parse_u128(&max_repay)?;
-    let repay_u = paid.u128().min(max_repay_u);
+    let mut payout = vec![];
+    if !collateral_out.is_zero() {
+        payout.push(Coin::new(collateral_out.u128(), cfg.terms.collateral_denom));
+    }
+    if !principal_out.is_zero() {
+        payout.push(Coin::new(principal_out.u128(), cfg.terms.principal_denom));
+    }
+    let mut res = Response::new().add_events(event).add_messages(burn);
+    if !payout.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: payout,
+        });
+    }
 
-    // Reduce outstanding
-    let out = parse_u128(&st.total_principal_outstanding)?;
-    let applied = repay_u.min(out);
-    st.total_principal_outstanding = to_string_u128(out - applied);
+    Ok(res
+        .add_attribute("action", "claim_default_recovery")
+        .add_attribute("amount", amount)
+        .add_attribute("collateral_out", collateral_out)
+        .add_attribute("principal_out", principal_out))
+}
 
-    // Transfer collateral to liquidator (placeholder: 1:1, ignores price + bonus)
-    let coll = parse_u128(&st.collateral_locked)?;
-    let give = applied.min(coll);
-    st.collateral_locked = to_string_u128(coll - give);
+/// Funds `CallBonds { amount }` needs at `t`; `premium_bps` is `None` when the series is not callable then.
+fn call_quote(cfg: &Config, st: &SeriesState, t: u64, amount: Uint128) -> StdResult<CallQuoteResponse> {
+    let premium_bps = call_premium_bps(cfg, t);
+    let premium = bps_of_ceil(amount, premium_bps.unwrap_or_default())?;
+    let interest = interest_unfunded(st);
+    Ok(CallQuoteResponse {
+        premium_bps,
+        principal: amount,
+        premium,
+        interest,
+        total: amount.checked_add(premium)?.checked_add(interest)?,
+    })
+}
 
-    STATE.save(deps.storage, &st)?;
+/// Call bonds before maturity. All unpaid interest is funded and `amount` tokens are redeemed
+/// pro-rata across holders, who claim par plus the premium with `ClaimRedemption`.
+fn execute_call_bonds(mut deps: DepsMut, env: Env, info: MessageInfo, amount: Uint128) -> Result<Response, ContractError> {
+    accrue(deps.branch(), &env)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    if info.sender != Addr::unchecked(cfg.terms.borrower.clone()) {
+        return Err(ContractError::Unauthorized);
+    }
+    let mut st = STATE.load(deps.storage)?;
+    require_not_paused(&st)?;
+    require_status(&st, &[SeriesStatus::Active, SeriesStatus::Liquidating])?;
+    let quote = call_quote(&cfg, &st, now_ts(&env), amount)?;
+    let premium_bps = quote.premium_bps.ok_or(ContractError::NotCallable)?;
+    let max = st.total_principal_outstanding.min(st.total_supply);
+    if amount.is_zero() || amount > max {
+        return Err(ContractError::CallTooLarge { max });
+    }
+    let paid = must_pay(&info, &cfg.terms.principal_denom)?;
+    if paid < quote.total {
+        return Err(ContractError::CallNotFunded { required: quote.total, paid });
+    }
 
-    let msg = BankMsg::Send {
-        to_address: info.sender.to_string(),
-        amount: vec![Coin::new(give, cfg.terms.collateral_denom)],
+    st.interest_funded = st.interest_funded.checked_add(quote.interest)?;
+    st.total_principal_outstanding -= amount;
+    redeem_pro_rata(&mut st, amount, amount + quote.premium)?;
+    let event = if st.total_supply.is_zero() && series_debt(&st)?.is_zero() {
+        transition(&mut st, SeriesStatus::Settled)?
+    } else {
+        exit_liquidating(&env, &cfg, &mut st)?
     };
+    STATE.save(deps.storage, &st)?;
 
-    Ok(Response::new()
-        .add_message(msg)
-        .add_attribute("action", "liquidate")
-        .add_attribute("repaid", applied.to_string())
-        .add_attribute("collateral_out", give.to_string()))
-}
+    let refund = paid - quote.total;
+    let mut res = Response::new().add_events(event);
+    if !refund.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin::new(refund.u128(), cfg.terms.principal_denom)],
+        });
+    }
 
-fn execute_update_oracle_price(deps: DepsMut, _env: Env, _info: MessageInfo) -> Result<Response, ContractError> {
-    // TODO: Implement Band IBC oracle request + reply processing.
-    // For skeleton purposes, we allow admin to set mock price via a future admin-only message.
-    Ok(Response::new().add_attribute("action", "update_oracle_price_todo"))
+    Ok(res
+        .add_attribute("action", "call_bonds")
+        .add_attribute("amount", amount)
+        .add_attribute("premium_bps", premium_bps.to_string())
+        .add_attribute("premium", quote.premium)
+        .add_attribute("interest_funded", quote.interest)
+        .add_attribute("refund", refund))
 }
 
-fn execute_checkpoint_impact(deps: DepsMut, env: Env, _info: MessageInfo) -> Result<Response, ContractError> {
+/// Pay out the principal and premium credited to the sender by pro-rata redemptions.
+fn execute_claim_redemption(mut deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    accrue(deps.branch(), &env)?;
+    sync_account(deps.branch(), &info.sender)?;
     let cfg = CONFIG.load(deps.storage)?;
-    let mut st = STATE.load(deps.storage)?;
-    require_not_paused(&st)?;
+    let mut acc = ACCOUNTS.load(deps.storage, info.sender.as_str())?;
+    if acc.redeemed.is_zero() {
+        return Err(ContractError::NothingToClaim);
+    }
+
+    let mut res = Response::new();
+    if is_token_factory(&cfg) {
+        // redeemed tokens already left the ledger balance; the native ones come back to be burned
+        let denom = bond_denom(&env.contract.address);
+        if must_pay(&info, &denom)? != acc.redeemed {
+            return Err(ContractError::InsufficientFunds);
+        }
+        res = res.add_message(burn_msg(&env.contract.address, &Coin::new(acc.redeemed.u128(), denom)));
+    }
+    let (redeemed, payout) = (acc.redeemed, acc.redemption_payout);
+    acc.redeemed = Uint128::zero();
+    acc.redemption_payout = Uint128::zero();
+    ACCOUNTS.save(deps.storage, info.sender.as_str(), &acc)?;
 
-    // TODO: query on-chain ecocredit module for batch retired supply.
-    // Skeleton: mark last checkpoint as unmet.
-    let checkpoint = cfg.terms.impact.checkpoints.first().cloned();
-    if let Some(cp) = checkpoint {
-        st.last_impact = Some(ImpactPoint {
-            checkpoint_ts: cp.ts,
-            retired_total: "0".to_string(),
-            target_retired: cp.target_retired,
-            met: false,
+    let mut st = STATE.load(deps.storage)?;
+    st.redemption_reserve = st.redemption_reserve.checked_sub(payout)?;
+    STATE.save(deps.storage, &st)?;
+    if !payout.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin::new(payout.u128(), cfg.terms.principal_denom)],
         });
-        STATE.save(deps.storage, &st)?;
     }
 
-    Ok(Response::new().add_attribute("action", "checkpoint_impact"))
+    Ok(res
+        .add_attribute("action", "claim_redemption")
+        .add_attribute("redeemed", redeemed)
+        .add_attribute("payout", payout))
 }
 
-fn execute_transfer(
-    deps: DepsMut,
+/// Repay part of an undercollateralized position in exchange for collateral at the oracle price
+/// plus `liquidation_bonus_bps`. At most `close_factor_bps` of the debt can be repaid per call;
+/// whatever the liquidator sent beyond the applied repayment is refunded.
+fn execute_liquidate(
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    recipient: String,
-    amount: String,
+    max_repay: Uint128,
 ) -> Result<Response, ContractError> {
     accrue(deps.branch(), &env)?;
-    let sender = info.sender.clone();
-    let rcpt = deps.api.addr_validate(&recipient)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut st = STATE.load(deps.storage)?;
+    require_not_paused(&st)?;
+    require_status(
+        &st,
+        &[SeriesStatus::SaleOpen, SeriesStatus::Active, SeriesStatus::Liquidating, SeriesStatus::Matured],
+    )?;
 
-    sync_account(deps.branch(), &sender)?;
-    sync_account(deps.branch(), &rcpt)?;
+    let price = require_fresh_price(&env, &cfg, &st)?.price;
+    let paid = must_pay(&info, &cfg.terms.principal_denom)?;
 
-    let mut sacc = ACCOUNTS.load(deps.storage, sender.as_str())?;
-    let mut racc = ACCOUNTS.load(deps.storage, rcpt.as_str())?;
+    let debt = series_debt(&st)?;
+    let cr_before = collateral_ratio_bps(st.collateral_locked, price, debt)?;
+    if st.total_principal_outstanding.is_zero()
+        || cr_before >= Uint256::from(cfg.terms.liquidation_ratio_bps)
+    {
+        return Err(ContractError::NotLiquidatable);
+    }
 
-    let amt = parse_u128(&amount)?;
-    let sbal = parse_u128(&sacc.balance)?;
-    if amt == 0 || amt > sbal {
+    // repayment goes to principal; bounded by the close factor and the locked collateral
+    let close_factor = close_factor_bps(&cfg);
+    let bonus_bps = cfg.terms.liquidation_bonus_bps;
+    let repay = paid.min(max_repay).min(max_liquidation_repay(&cfg, &st, price)?);
+    let collateral_out = liquidation_collateral_out(repay, bonus_bps, price)?
+        .min(Uint256::from(st.collateral_locked));
+    let collateral_out = Uint128::try_from(collateral_out)
+        .map_err(|_| StdError::generic_err("collateral out overflow"))?;
+    if repay.is_zero() || collateral_out.is_zero() {
         return Err(ContractError::InsufficientFunds);
     }
 
-    sacc.balance = to_string_u128(sbal - amt);
-    let rbal = parse_u128(&racc.balance)?;
-    racc.balance = to_string_u128(rbal + amt);
+    // collateral at par for the repayment; everything above it is the bonus
+    let par = liquidation_collateral_out(repay, 0, price)?;
+    let bonus_paid = Uint256::from(collateral_out).saturating_sub(par);
+
+    st.total_principal_outstanding = st.total_principal_outstanding.checked_sub(repay)?;
+    st.principal_reserve = st.principal_reserve.checked_add(repay)?;
+    st.collateral_locked = st.collateral_locked.checked_sub(collateral_out)?;
+    let cr_after = collateral_ratio_bps(st.collateral_locked, price, series_debt(&st)?)?;
+
+    let mut events: Vec<Event> = if cr_after < Uint256::from(cfg.terms.liquidation_ratio_bps) {
+        transition(&mut st, SeriesStatus::Liquidating)?.into_iter().collect()
+    } else {
+        exit_liquidating(&env, &cfg, &mut st)?.into_iter().collect()
+    };
+    events.extend(settle_if_repaid(&env, &cfg, &mut st)?);
+    STATE.save(deps.storage, &st)?;
+
+    let mut res = Response::new().add_events(events).add_message(BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![Coin::new(collateral_out.u128(), cfg.terms.collateral_denom)],
+    });
+    let refund = paid - repay;
+    if !refund.is_zero() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin::new(refund.u128(), cfg.terms.principal_denom)],
+        });
+    }
+
+    Ok(res
+        .add_attribute("action", "liquidate")
+        .add_attribute("liquidator", info.sender)
+        .add_attribute("price", price.to_string())
+        .add_attribute("cr_before_bps", cr_before.to_string())
+        .add_attribute("cr_after_bps", cr_after.to_string())
+        .add_attribute("close_factor_bps", close_factor.to_string())
+        .add_attribute("repaid", repay.to_string())
+        .add_attribute("collateral_out", collateral_out.to_string())
+        .add_attribute("bonus_paid", bonus_paid.to_string())
+        .add_attribute("refund", refund.to_string()))
+}
+
+/// Send a Band price request over IBC. The price lands in `ibc_packet_receive`.
+fn execute_update_oracle_price(deps: DepsMut, env: Env, _info: MessageInfo) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let st = STATE.load(deps.storage)?;
+    require_not_paused(&st)?;
+    require_live(&st)?;
+
+    // at most one request in flight; an unanswered request past its timeout no longer blocks
+    if let Some(pending) = PENDING_PRICE_REQUEST.may_load(deps.storage)? {
+        if now_ts(&env) < pending.timeout_ts {
+            return Err(ContractError::OracleRequestPending);
+        }
+    }
+
+    let nonce = PRICE_REQUEST_NONCE.may_load(deps.storage)?.unwrap_or_default() + 1;
+    PRICE_REQUEST_NONCE.save(deps.storage, &nonce)?;
+
+    let client_id = format!("{}-price-{}", env.contract.address, nonce);
+    let channel_id = cfg.terms.oracle.band_ibc_channel;
+    let timeout = env.block.time.plus_seconds(PRICE_REQUEST_TIMEOUT_SECONDS);
+    let packet = OracleRequestPacketData {
+        client_id: client_id.clone(),
+        oracle_script_id: cfg.terms.oracle.regen_price_script_id.into(),
+        calldata: encode_price_calldata(PRICE_SYMBOL, PRICE_MULTIPLIER),
+        ask_count: ASK_COUNT.into(),
+        min_count: MIN_COUNT.into(),
+        fee_limit: vec![],
+        prepare_gas: PREPARE_GAS.into(),
+        execute_gas: EXECUTE_GAS.into(),
+    };
 
-    ACCOUNTS.save(deps.storage, sender.as_str(), &sacc)?;
-    ACCOUNTS.save(deps.storage, rcpt.as_str(), &racc)?;
+    PENDING_PRICE_REQUEST.save(
+        deps.storage,
+        &PendingPriceRequest {
+            client_id: client_id.clone(),
+            channel_id: channel_id.clone(),
+            sent_at: now_ts(&env),
+            timeout_ts: timeout.seconds(),
+            sequence: None,
+            band_request_id: None,
+        },
+    )?;
+
+    let msg = IbcMsg::SendPacket {
+        channel_id: channel_id.clone(),
+        data: to_json_binary(&packet)?,
+        timeout: IbcTimeout::with_timestamp(timeout),
+    };
 
     Ok(Response::new()
-        .add_attribute("action", "transfer")
-        .add_attribute("from", sender)
-        .add_attribute("to", recipient)
-        .add_attribute("amount", amount))
+        .add_message(msg)
+        .add_attribute("action", "update_oracle_price")
+        .add_attribute("client_id", client_id)
+        .add_attribute("channel_id", channel_id))
+}
+
+/// Evaluate the latest due impact checkpoint against the retired supply of the tracked batches.
+fn execute_checkpoint_impact(mut deps: DepsMut, env: Env, _info: MessageInfo) -> Result<Response, ContractError> {
+    // settle interest at the old rate up to the status change
+    accrue(deps.branch(), &env)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut st = STATE.load(deps.storage)?;
+    require_not_paused(&st)?;
+    require_live(&st)?;
+
+    let impact = &cfg.terms.impact;
+    if impact.mode != ImpactMode::OnChainEcocreditBatches {
+        return Err(ContractError::InvalidConfig("impact mode not supported yet".into()));
+    }
+    let now = now_ts(&env);
+    let cp = impact
+        .checkpoints
+        .iter()
+        .filter(|cp| cp.ts <= now)
+        .max_by_key(|cp| cp.ts)
+        .ok_or(ContractError::NoCheckpointDue)?;
+
+    let retired_total = query_total_retired(&deps.querier, &impact.batch_ids)?;
+    let point = ImpactPoint {
+        checkpoint_ts: cp.ts,
+        retired_total,
+        target_retired: cp.target_retired,
+        met: retired_total >= cp.target_retired,
+    };
+    st.last_impact = Some(point.clone());
+    STATE.save(deps.storage, &st)?;
+    let apr_bps = effective_apr_bps(&cfg, &st);
+
+    Ok(Response::new()
+        .add_attribute("action", "checkpoint_impact")
+        .add_attribute("checkpoint_ts", point.checkpoint_ts.to_string())
+        .add_attribute("retired_total", point.retired_total)
+        .add_attribute("target_retired", point.target_retired)
+        .add_attribute("met", point.met.to_string())
+        .add_attribute("apr_bps", apr_bps.to_string()))
+}
+
+/// Put a matured series with unpaid debt into default once the grace period has passed. The
+/// locked collateral is then reserved for holders and penalty interest accrues from now on.
+fn execute_declare_default(mut deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    accrue(deps.branch(), &env)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut st = STATE.load(deps.storage)?;
+    require_status(&st, &[SeriesStatus::Matured, SeriesStatus::Liquidating])?;
+    let ends_at = default_after(&cfg);
+    if now_ts(&env) < ends_at {
+        return Err(ContractError::GracePeriodActive { ends_at });
+    }
+
+    let event = transition(&mut st, SeriesStatus::Defaulted)?;
+    st.defaulted_at = Some(now_ts(&env));
+    STATE.save(deps.storage, &st)?;
+
+    Ok(Response::new()
+        .add_events(event)
+        .add_attribute("action", "declare_default")
+        .add_attribute("keeper", info.sender)
+        .add_attribute("debt", series_debt(&st)?)
+        .add_attribute("collateral_claimable", st.collateral_locked)
+        .add_attribute("apr_bps", effective_apr_bps(&cfg, &st).to_string()))
 }
 
 #[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::Terms {} => to_json_binary(&query_terms(deps)?),
-        QueryMsg::State {} => to_json_binary(&query_state(deps)?),
+        QueryMsg::Terms {} => to_json_binary(&query_terms(deps, env)?),
+        QueryMsg::State {} => to_json_binary(&query_state(deps, env)?),
         QueryMsg::Balance { address } => to_json_binary(&query_balance(deps, address)?),
-        QueryMsg::AccruedInterest { address } => to_json_binary(&query_accrued(deps, address)?),
-        QueryMsg::CollateralRatio {} => to_json_binary(&query_collateral_ratio(deps)?),
-        QueryMsg::PriceStatus {} => to_json_binary(&query_price_status(deps)?),
+        QueryMsg::AccruedInterest { address } => to_json_binary(&query_accrued(deps, env, address)?),
+        QueryMsg::CollateralRatio {} => to_json_binary(&query_collateral_ratio(deps, env)?),
+        QueryMsg::PriceStatus {} => to_json_binary(&query_price_status(deps, env)?),
         QueryMsg::ImpactStatus {} => to_json_binary(&query_impact_status(deps)?),
+        QueryMsg::EffectiveApr {} => to_json_binary(&query_effective_apr(deps, env)?),
+        QueryMsg::SaleCollateral {} => to_json_binary(&query_sale_collateral(deps, env)?),
+        QueryMsg::ExpectedRecovery { address } => to_json_binary(&query_expected_recovery(deps, env, address)?),
+        QueryMsg::CouponSchedule { holder, start_after, limit } => {
+            to_json_binary(&query_coupon_schedule(deps, env, holder, start_after, limit)?)
+        }
+        QueryMsg::CallQuote { amount } => to_json_binary(&query_call_quote(deps, env, amount)?),
+        QueryMsg::PendingRedemption { address } => to_json_binary(&query_pending_redemption(deps, env, address)?),
+        QueryMsg::Amortization {} => to_json_binary(&query_amortization(deps, env)?),
+        QueryMsg::TokenInfo {} => to_json_binary(&token::query_token_info(deps)?),
+        QueryMsg::Allowance { owner, spender } => to_json_binary(&token::query_allowance(deps, owner, spender)?),
+        QueryMsg::AllAllowances { owner, start_after, limit } => {
+            to_json_binary(&token::query_all_allowances(deps, owner, start_after, limit)?)
+        }
+        QueryMsg::AllAccounts { start_after, limit } => {
+            to_json_binary(&token::query_all_accounts(deps, start_after, limit)?)
+        }
     }
 }
 
-fn query_terms(deps: Deps) -> StdResult<TermsResponse> {
+fn query_terms(deps: Deps, env: Env) -> StdResult<TermsResponse> {
     let cfg = CONFIG.load(deps.storage)?;
     Ok(TermsResponse {
+        bond_denom: is_token_factory(&cfg).then(|| bond_denom(&env.contract.address)),
         terms: cfg.terms,
         admin: cfg.admin,
         protocol_fee_bps: cfg.protocol_fee_bps,
@@ -466,70 +1569,140 @@ fn query_terms(deps: Deps) -> StdResult<TermsResponse> {
     })
 }
 
-fn query_state(deps: Deps) -> StdResult<StateResponse> {
+fn query_state(deps: Deps, env: Env) -> StdResult<StateResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
     let st = STATE.load(deps.storage)?;
+    // status as the next execute would see it; totals stay as stored
+    let mut projected = projected_state(deps, &env, &cfg)?;
+    advance_status(&env, &cfg, &mut projected).map_err(|e| StdError::generic_err(e.to_string()))?;
     Ok(StateResponse {
-        sale_open: st.sale_open,
+        status: projected.status,
+        sale_open: projected.status == SeriesStatus::SaleOpen,
         paused: st.paused,
         total_principal_sold: st.total_principal_sold,
         total_principal_outstanding: st.total_principal_outstanding,
+        principal_reserve: st.principal_reserve,
+        redemption_reserve: st.redemption_reserve,
+        total_supply: st.total_supply,
+        interest_accrued: st.interest_accrued,
+        interest_funded: st.interest_funded,
         collateral_locked: st.collateral_locked,
         global_interest_index: st.global_interest_index,
         last_accrual_ts: st.last_accrual_ts,
-        last_price: st.last_price.map(|p| PriceStatusResponse { price: p.price, ts: p.ts }),
+        last_price: st.last_price.map(|p| price_status(&env, &cfg, p)),
         last_impact: st.last_impact.map(|i| ImpactStatusResponse {
             checkpoint_ts: i.checkpoint_ts,
             retired_total: i.retired_total,
             target_retired: i.target_retired,
             met: i.met,
         }),
+        defaulted_at: st.defaulted_at,
     })
 }
 
 fn query_balance(deps: Deps, address: String) -> StdResult<BalanceResponse> {
     let st = STATE.load(deps.storage)?;
-    let acc = ACCOUNTS
-        .may_load(deps.storage, address.as_str())?
-        .unwrap_or(AccountIndex {
-            balance: "0".to_string(),
-            index: st.global_interest_index,
-            accrued: "0".to_string(),
-        });
+    let mut acc = load_account(deps, &address, &st)?;
+    settle_account(&mut acc, &st)?;
     Ok(BalanceResponse { balance: acc.balance })
 }
 
-fn query_accrued(deps: Deps, address: String) -> StdResult<AccruedInterestResponse> {
-    let st = STATE.load(deps.storage)?;
-    let acc = ACCOUNTS
-        .may_load(deps.storage, address.as_str())?
-        .unwrap_or(AccountIndex {
-            balance: "0".to_string(),
-            index: st.global_interest_index,
-            accrued: "0".to_string(),
-        });
+/// Accrued interest projected to the current block time, as if `ClaimInterest` ran now.
+fn query_accrued(deps: Deps, env: Env, address: String) -> StdResult<AccruedInterestResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let st = projected_state(deps, &env, &cfg)?;
+    let mut acc = load_account(deps, &address, &st)?;
+    settle_account(&mut acc, &st)?;
     Ok(AccruedInterestResponse { accrued: acc.accrued })
 }
 
-fn query_collateral_ratio(_deps: Deps) -> StdResult<CollateralRatioResponse> {
-    // TODO: compute using oracle price + debt
-    Ok(CollateralRatioResponse { ratio_bps: None })
+/// Collateral health at the last oracle price, with interest projected to the current block time.
+fn query_collateral_ratio(deps: Deps, env: Env) -> StdResult<CollateralRatioResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let st = projected_state(deps, &env, &cfg)?;
+    let debt = series_debt(&st)?;
+
+    let mut res = CollateralRatioResponse {
+        ratio_bps: None,
+        health: None,
+        collateral_locked: st.collateral_locked,
+        collateral_value: None,
+        principal_outstanding: st.total_principal_outstanding,
+        interest_accrued: interest_unfunded(&st),
+        debt,
+        price: None,
+        liquidation_price: None,
+        max_liquidation_repay: Uint128::zero(),
+    };
+    let Some(p) = st.last_price.clone() else {
+        return Ok(res);
+    };
+
+    let value = collateral_value(st.collateral_locked, p.price)?;
+    let ratio = collateral_ratio_bps(st.collateral_locked, p.price, debt)?;
+    let ratio_bps = Uint128::try_from(ratio)
+        .ok()
+        .and_then(|r| u32::try_from(r.u128()).ok())
+        .unwrap_or(u32::MAX);
+    let health = if ratio_bps < cfg.terms.liquidation_ratio_bps && !debt.is_zero() {
+        CollateralHealth::Liquidatable
+    } else if ratio_bps < cfg.terms.initial_collateral_ratio_bps && !debt.is_zero() {
+        CollateralHealth::AtRisk
+    } else {
+        CollateralHealth::Healthy
+    };
+    let is_fresh = price_age(&env, &p) <= cfg.terms.oracle.max_price_age_seconds;
+    if health == CollateralHealth::Liquidatable && is_fresh {
+        res.max_liquidation_repay = max_liquidation_repay(&cfg, &st, p.price)?;
+    }
+    // CR == liquidation_ratio_bps  <=>  P = liquidation_ratio_bps * D / (10_000 * C)
+    if !debt.is_zero() && !st.collateral_locked.is_zero() {
+        res.liquidation_price = Some(Decimal256::checked_from_ratio(
+            Uint256::from(debt).checked_mul(Uint256::from(cfg.terms.liquidation_ratio_bps))?,
+            Uint256::from(st.collateral_locked).checked_mul(Uint256::from(BPS_DENOM))?,
+        )
+        .map_err(|e| StdError::generic_err(e.to_string()))?);
+    }
+
+    res.ratio_bps = Some(ratio_bps);
+    res.health = Some(health);
+    res.collateral_value = Some(
+        Uint128::try_from(value).map_err(|_| StdError::generic_err("collateral value overflow"))?,
+    );
+    res.price = Some(price_status(&env, &cfg, p));
+    Ok(res)
+}
+
+fn price_status(env: &Env, cfg: &Config, p: PricePoint) -> PriceStatusResponse {
+    let age = price_age(env, &p);
+    PriceStatusResponse {
+        price: p.price,
+        ts: p.ts,
+        is_fresh: age <= cfg.terms.oracle.max_price_age_seconds,
+        age_seconds: Some(age),
+    }
 }
 
-fn query_price_status(deps: Deps) -> StdResult<PriceStatusResponse> {
+fn query_price_status(deps: Deps, env: Env) -> StdResult<PriceStatusResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
     let st = STATE.load(deps.storage)?;
-    let p = st.last_price.unwrap_or(PricePoint {
-        price: "0".to_string(),
-        ts: 0,
-    });
-    Ok(PriceStatusResponse { price: p.price, ts: p.ts })
+    Ok(match st.last_price {
+        Some(p) => price_status(&env, &cfg, p),
+        None => PriceStatusResponse {
+            price: Decimal256::zero(),
+            ts: 0,
+            is_fresh: false,
+            age_seconds: None,
+        },
+    })
 }
 
 fn query_impact_status(deps: Deps) -> StdResult<ImpactStatusResponse> {
     let st = STATE.load(deps.storage)?;
     let i = st.last_impact.unwrap_or(ImpactPoint {
         checkpoint_ts: 0,
-        retired_total: "0".to_string(),
-        target_retired: "0".to_string(),
+        retired_total: Uint128::zero(),
+        target_retired: Uint128::zero(),
         met: false,
     });
     Ok(ImpactStatusResponse {
@@ -539,3 +1712,211 @@ fn query_impact_status(deps: Deps) -> StdResult<ImpactStatusResponse> {
         met: i.met,
     })
 }
+
+fn query_effective_apr(deps: Deps, env: Env) -> StdResult<EffectiveAprResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let st = projected_state(deps, &env, &cfg)?;
+    let checkpoint_ts = st.last_impact.as_ref().map(|i| i.checkpoint_ts);
+    let (reason, checkpoint_ts) = match &st.last_impact {
+        _ if st.status == SeriesStatus::Defaulted => (AprReason::Defaulted, checkpoint_ts),
+        _ if coupon_late(&st) => (AprReason::CouponLate, checkpoint_ts),
+        None => (AprReason::NoCheckpoint, None),
+        Some(i) if i.met => (AprReason::ImpactMet, Some(i.checkpoint_ts)),
+        Some(i) => (AprReason::ImpactMissed, Some(i.checkpoint_ts)),
+    };
+    Ok(EffectiveAprResponse {
+        apr_bps: effective_apr_bps(&cfg, &st),
+        base_rate_apr_bps: cfg.terms.base_rate_apr_bps,
+        penalty_rate_apr_bps: cfg.terms.penalty_rate_apr_bps,
+        penalty_active: matches!(reason, AprReason::ImpactMissed | AprReason::Defaulted | AprReason::CouponLate),
+        reason,
+        checkpoint_ts,
+    })
+}
+
+/// Collateral `OpenSale` requires at the last price, and how much the borrower still has to add.
+fn query_sale_collateral(deps: Deps, env: Env) -> StdResult<SaleCollateralResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let st = STATE.load(deps.storage)?;
+    let required = st
+        .last_price
+        .as_ref()
+        .map(|p| sale_collateral_required(&cfg, p.price))
+        .transpose()?;
+    Ok(SaleCollateralResponse {
+        collateral_locked: st.collateral_locked,
+        required,
+        shortfall: required.map(|r| r.saturating_sub(st.collateral_locked)),
+        price: st.last_price.map(|p| price_status(&env, &cfg, p)),
+    })
+}
+
+/// Holder's pro-rata share of the collateral against what they are owed.
+fn query_expected_recovery(deps: Deps, env: Env, address: String) -> StdResult<ExpectedRecoveryResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut st = projected_state(deps, &env, &cfg)?;
+    advance_status(&env, &cfg, &mut st).map_err(|e| StdError::generic_err(e.to_string()))?;
+
+    let mut acc = load_account(deps, addr.as_str(), &st)?;
+    settle_account(&mut acc, &st)?;
+    let claim = acc.balance.checked_add(acc.accrued)?;
+    let (supply, collateral, reserve) = match &st.recovery {
+        Some(snap) => (snap.supply, snap.collateral, snap.principal_reserve),
+        None => (st.total_supply, st.collateral_locked, st.principal_reserve),
+    };
+    let (collateral_share, reserve_share) = if supply.is_zero() {
+        (Uint128::zero(), Uint128::zero())
+    } else {
+        (collateral.multiply_ratio(acc.balance, supply), reserve.multiply_ratio(acc.balance, supply))
+    };
+    let collateral_value = st
+        .last_price
+        .as_ref()
+        .map(|p| -> StdResult<_> {
+            Uint128::try_from(collateral_value(collateral_share, p.price)?)
+                .map_err(|_| StdError::generic_err("collateral value overflow"))
+        })
+        .transpose()?;
+    let recovery_bps = collateral_value.filter(|_| !claim.is_zero()).map(|v| {
+        let recovered = Uint256::from(v) + Uint256::from(reserve_share);
+        let bps = recovered * Uint256::from(BPS_DENOM) / Uint256::from(claim);
+        Uint128::try_from(bps)
+            .ok()
+            .and_then(|r| u32::try_from(r.u128()).ok())
+            .unwrap_or(u32::MAX)
+    });
+
+    Ok(ExpectedRecoveryResponse {
+        status: st.status,
+        collateral_claimable: st.status == SeriesStatus::Defaulted,
+        default_after: default_after(&cfg),
+        balance: acc.balance,
+        claim,
+        collateral_share,
+        collateral_value,
+        reserve_share,
+        recovery_bps,
+    })
+}
+
+/// `addr`'s balance on coupon date `ts`: the first balance recorded at or after it or, if the
+/// account has not moved since, its stored balance, carried to the redemption factor of `ts`.
+fn coupon_balance(deps: Deps, addr: &Addr, record: &CouponRecord, ts: u64, st: &SeriesState) -> StdResult<Uint128> {
+    let recorded = COUPON_BALANCES
+        .prefix(addr.as_str())
+        .range(deps.storage, Some(Bound::inclusive(ts)), None, Order::Ascending)
+        .next()
+        .transpose()?;
+    let stored = match recorded {
+        Some((_, stored)) => stored,
+        None => {
+            let acc = load_account(deps, addr.as_str(), st)?;
+            CouponBalance { balance: acc.balance, redemption_factor: acc.redemption_factor }
+        }
+    };
+    Ok(scale_balance(stored.balance, stored.redemption_factor, record.redemption_factor)?.0)
+}
+
+/// Coupon schedule with the amounts fixed so far. Coupons are paid in date order, so each one is
+/// covered by the interest paid in beyond everything due before it.
+fn query_coupon_schedule(
+    deps: Deps,
+    env: Env,
+    holder: Option<String>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<CouponScheduleResponse> {
+    let holder = holder.map(|h| deps.api.addr_validate(&h)).transpose()?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut st = STATE.load(deps.storage)?;
+    let recorded = pass_coupon_dates(deps.storage, &cfg, &mut st, now_ts(&env))?;
+    let paid_in = interest_paid_in(&st);
+    let grace = coupon_grace_period(&cfg);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let coupons = COUPONS
+        .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (ts, stored) = item?;
+            let coupon = recorded.iter().find(|c| c.ts == ts).cloned().unwrap_or(stored);
+            let late_after = ts.saturating_add(grace);
+            let Some(record) = coupon.record else {
+                return Ok(CouponResponse {
+                    ts,
+                    status: CouponStatus::Scheduled,
+                    late_after,
+                    amount_due: None,
+                    amount_paid: Uint128::zero(),
+                    record_supply: None,
+                    holder_balance: None,
+                });
+            };
+            let due_before = record.interest_credited.checked_sub(record.amount_due)?;
+            let amount_paid = paid_in.saturating_sub(due_before).min(record.amount_due);
+            let status = if amount_paid == record.amount_due {
+                CouponStatus::Paid
+            } else if now_ts(&env) >= late_after {
+                CouponStatus::Late
+            } else {
+                CouponStatus::Due
+            };
+            Ok(CouponResponse {
+                ts,
+                status,
+                late_after,
+                amount_due: Some(record.amount_due),
+                amount_paid,
+                record_supply: Some(record.supply),
+                holder_balance: holder.as_ref().map(|h| coupon_balance(deps, h, &record, ts, &st)).transpose()?,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(CouponScheduleResponse { coupons })
+}
+
+fn query_call_quote(deps: Deps, env: Env, amount: Uint128) -> StdResult<CallQuoteResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let st = projected_state(deps, &env, &cfg)?;
+    call_quote(&cfg, &st, now_ts(&env), amount)
+}
+
+fn query_pending_redemption(deps: Deps, env: Env, address: String) -> StdResult<PendingRedemptionResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let st = projected_state(deps, &env, &cfg)?;
+    let mut acc = load_account(deps, addr.as_str(), &st)?;
+    settle_account(&mut acc, &st)?;
+    Ok(PendingRedemptionResponse {
+        redeemed: acc.redeemed,
+        payout: acc.redemption_payout,
+    })
+}
+
+fn query_amortization(deps: Deps, env: Env) -> StdResult<AmortizationResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let st = STATE.load(deps.storage)?;
+    let mut installments = vec![];
+    let (mut prev, mut overdue) = (Uint128::zero(), Uint128::zero());
+    for (ts, through) in installments_due(&cfg, &st) {
+        let amount_due = through - prev;
+        let amount_paid = st.installments_paid.clamp(prev, through) - prev;
+        let status = if amount_paid == amount_due {
+            InstallmentStatus::Paid
+        } else if ts <= now_ts(&env) {
+            overdue += amount_due - amount_paid;
+            InstallmentStatus::Overdue
+        } else {
+            InstallmentStatus::Scheduled
+        };
+        installments.push(InstallmentResponse { ts, status, amount_due, amount_paid });
+        prev = through;
+    }
+    Ok(AmortizationResponse {
+        installments,
+        total_due: prev,
+        total_paid: st.installments_paid,
+        overdue,
+    })
+}
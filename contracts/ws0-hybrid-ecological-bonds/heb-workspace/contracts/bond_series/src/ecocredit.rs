@@ -0,0 +1,69 @@
+//! Regen ecocredit module queries used by `CheckpointImpact` in `OnChainEcocreditBatches` mode.
+//!
+//! Requests go through the Stargate query path with a protobuf-encoded body; wasmd returns the
+//! response as JSON with the proto field names.
+
+use std::str::FromStr;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    Binary, Decimal256, QuerierWrapper, QueryRequest, StdError, StdResult, Uint128, Uint256,
+};
+
+/// gRPC path of `regen.ecocredit.v1.Query/Supply`.
+pub const SUPPLY_QUERY_PATH: &str = "/regen.ecocredit.v1.Query/Supply";
+
+/// Credit amounts are decimal strings; checkpoints count them in micro-credits (1e-6).
+pub const CREDIT_MICRO_UNITS: u128 = 1_000_000;
+
+/// `QuerySupplyResponse`. Amounts are decimal strings such as `"12.5"`.
+#[cw_serde]
+pub struct QuerySupplyResponse {
+    pub tradable_amount: String,
+    pub retired_amount: String,
+    pub cancelled_amount: String,
+}
+
+/// Protobuf encoding of `QuerySupplyRequest { batch_denom = 1 }`.
+pub fn encode_supply_request(batch_denom: &str) -> Binary {
+    let mut out = vec![0x0a];
+    let mut len = batch_denom.len();
+    while len >= 0x80 {
+        out.push((len as u8 & 0x7f) | 0x80);
+        len >>= 7;
+    }
+    out.push(len as u8);
+    out.extend_from_slice(batch_denom.as_bytes());
+    Binary::from(out)
+}
+
+/// Parse a credit amount into micro-credits, rounding down.
+pub fn parse_credit_amount(amount: &str) -> StdResult<Uint128> {
+    if amount.is_empty() {
+        return Ok(Uint128::zero());
+    }
+    let credits = Decimal256::from_str(amount)
+        .map_err(|e| StdError::generic_err(format!("bad credit amount {amount}: {e}")))?;
+    let micro = credits.checked_mul(Decimal256::from_ratio(CREDIT_MICRO_UNITS, 1u128))?;
+    Uint128::try_from(micro.to_uint_floor())
+        .map_err(|_| StdError::generic_err("credit amount overflow"))
+}
+
+/// Retired supply of one credit batch, in micro-credits.
+pub fn query_retired(querier: &QuerierWrapper, batch_denom: &str) -> StdResult<Uint128> {
+    let resp: QuerySupplyResponse = querier.query(&QueryRequest::Stargate {
+        path: SUPPLY_QUERY_PATH.to_string(),
+        data: encode_supply_request(batch_denom),
+    })?;
+    parse_credit_amount(&resp.retired_amount)
+}
+
+/// Retired supply summed over `batch_ids`, in micro-credits.
+pub fn query_total_retired(querier: &QuerierWrapper, batch_ids: &[String]) -> StdResult<Uint128> {
+    let total = batch_ids
+        .iter()
+        .try_fold(Uint256::zero(), |acc, batch| -> StdResult<_> {
+            Ok(acc.checked_add(Uint256::from(query_retired(querier, batch)?))?)
+        })?;
+    Uint128::try_from(total).map_err(|_| StdError::generic_err("retired total overflow"))
+}
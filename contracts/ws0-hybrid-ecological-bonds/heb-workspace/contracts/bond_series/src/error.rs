@@ -1,3 +1,5 @@
+use cosmwasm_std::Uint128;
+use heb_types::SeriesStatus;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -5,15 +7,33 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] cosmwasm_std::StdError),
 
+    #[error("{0}")]
+    Payment(#[from] cw_utils::PaymentError),
+
+    #[error("{0}")]
+    Overflow(#[from] cosmwasm_std::OverflowError),
+
     #[error("Unauthorized")]
     Unauthorized,
 
     #[error("Paused")]
     Paused,
 
+    #[error("Not allowed while the series is {status}")]
+    InvalidStatus { status: SeriesStatus },
+
+    #[error("Series cannot move from {from} to {to}")]
+    InvalidTransition { from: SeriesStatus, to: SeriesStatus },
+
+    #[error("Default grace period runs until {ends_at}")]
+    GracePeriodActive { ends_at: u64 },
+
     #[error("Sale not open")]
     SaleNotOpen,
 
+    #[error("Every bond token has been redeemed")]
+    FullyRedeemed,
+
     #[error("Bond has matured")]
     Matured,
 
@@ -29,12 +49,69 @@ pub enum ContractError {
     #[error("Oracle price stale or missing")]
     OracleStale,
 
+    #[error("Oracle price request already in flight")]
+    OracleRequestPending,
+
+    #[error("Invalid IBC channel: {0}")]
+    InvalidIbcChannel(String),
+
     #[error("Collateral ratio too low")]
     CollateralTooLow,
 
+    #[error("Position is not liquidatable")]
+    NotLiquidatable,
+
+    #[error("No impact checkpoint is due yet")]
+    NoCheckpointDue,
+
+    #[error("Purchase exceeds principal cap ({remaining} remaining)")]
+    PrincipalCapExceeded { remaining: Uint128 },
+
+    #[error("Would mint {filled} tokens, below min_tokens {min_tokens}")]
+    MinTokensNotMet { filled: Uint128, min_tokens: Uint128 },
+
+    #[error("Interest reserve too low: {funded} funded, {requested} requested")]
+    InterestNotFunded { funded: Uint128, requested: Uint128 },
+
+    #[error("Principal reserve too low: {available} available, {requested} requested")]
+    PrincipalNotRepaid { available: Uint128, requested: Uint128 },
+
+    #[error("Bonds are not callable now")]
+    NotCallable,
+
+    #[error("Call exceeds the {max} bonds that can be called")]
+    CallTooLarge { max: Uint128 },
+
+    #[error("Call needs {required}, got {paid}")]
+    CallNotFunded { required: Uint128, paid: Uint128 },
+
+    #[error("Default recovery has started")]
+    RecoveryStarted,
+
+    #[error("No allowance for this account")]
+    NoAllowance,
+
+    #[error("Allowance is expired")]
+    AllowanceExpired,
+
+    #[error("Cannot set allowance to own account")]
+    CannotSetOwnAccount,
+
+    #[error("Bond tokens are native in TokenFactory mode; move them with bank sends")]
+    NativeBondToken,
+
+    #[error("Series has no coupon schedule")]
+    NoCouponSchedule,
+
+    #[error("No interest is due")]
+    NoInterestDue,
+
     #[error("Nothing to claim")]
     NothingToClaim,
 
     #[error("Invalid config: {0}")]
     InvalidConfig(String),
+
+    #[error("Cannot migrate from {from} down to {to}")]
+    CannotDowngrade { from: String, to: String },
 }
@@ -0,0 +1,225 @@
+use cosmwasm_std::{
+    entry_point, from_json, to_json_binary, Binary, DepsMut, Env, IbcBasicResponse, IbcChannel,
+    IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg, IbcOrder, IbcPacket,
+    IbcPacketAckMsg, IbcPacketReceiveMsg, IbcPacketTimeoutMsg, IbcReceiveResponse, Never, StdError,
+    StdResult, Storage,
+};
+
+use crate::contract::now_ts;
+use crate::error::ContractError;
+use crate::oracle::{
+    decode_price_result, AcknowledgementMsg, BandAcknowledgement, OracleRequestPacketData,
+    OracleResponsePacketData, ResolveStatus, BAND_IBC_VERSION, PRICE_MULTIPLIER,
+};
+use crate::state::{PendingPriceRequest, PricePoint, CONFIG, PENDING_PRICE_REQUEST, STATE};
+
+fn validate_channel(
+    channel: &IbcChannel,
+    counterparty_version: Option<&str>,
+) -> Result<(), ContractError> {
+    if channel.order != IbcOrder::Unordered {
+        return Err(ContractError::InvalidIbcChannel(
+            "only unordered channels are supported".into(),
+        ));
+    }
+    if channel.version != BAND_IBC_VERSION {
+        return Err(ContractError::InvalidIbcChannel(format!(
+            "version must be {BAND_IBC_VERSION}"
+        )));
+    }
+    if let Some(version) = counterparty_version {
+        if version != BAND_IBC_VERSION {
+            return Err(ContractError::InvalidIbcChannel(format!(
+                "counterparty version must be {BAND_IBC_VERSION}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn ack_success() -> Binary {
+    to_json_binary(&AcknowledgementMsg::Result(Binary::from(vec![1]))).unwrap()
+}
+
+fn ack_fail(err: String) -> Binary {
+    to_json_binary(&AcknowledgementMsg::Error(err)).unwrap()
+}
+
+#[entry_point]
+pub fn ibc_channel_open(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<(), ContractError> {
+    validate_channel(msg.channel(), msg.counterparty_version())
+}
+
+#[entry_point]
+pub fn ibc_channel_connect(
+    _deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    validate_channel(msg.channel(), msg.counterparty_version())?;
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_connect")
+        .add_attribute("channel_id", &msg.channel().endpoint.channel_id))
+}
+
+#[entry_point]
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel_id = &msg.channel().endpoint.channel_id;
+    if let Some(pending) = PENDING_PRICE_REQUEST.may_load(deps.storage)? {
+        if &pending.channel_id == channel_id {
+            PENDING_PRICE_REQUEST.remove(deps.storage);
+        }
+    }
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_close")
+        .add_attribute("channel_id", channel_id))
+}
+
+/// Handle a BandChain `OracleResponsePacketData`. Never fails the IBC transaction: any problem is
+/// reported through an error acknowledgement instead.
+#[entry_point]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, Never> {
+    match receive_price(deps, env, &msg.packet) {
+        Ok(res) => Ok(res),
+        Err(err) => Ok(IbcReceiveResponse::new()
+            .set_ack(ack_fail(err.to_string()))
+            .add_attribute("action", "oracle_price_received")
+            .add_attribute("error", err.to_string())),
+    }
+}
+
+fn receive_price(
+    deps: DepsMut,
+    env: Env,
+    packet: &IbcPacket,
+) -> Result<IbcReceiveResponse, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if packet.dest.channel_id != cfg.terms.oracle.band_ibc_channel {
+        return Err(ContractError::InvalidIbcChannel(
+            packet.dest.channel_id.clone(),
+        ));
+    }
+    let resp: OracleResponsePacketData = from_json(&packet.data)?;
+
+    // correlate on the Band request id that the acknowledgement of our request packet returned;
+    // relayers may deliver the response first, and until then the client id nonce identifies it
+    let pending = PENDING_PRICE_REQUEST
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("no price request in flight"))?;
+    if let Some(request_id) = pending.band_request_id {
+        if request_id != resp.request_id.u64() {
+            return Err(StdError::generic_err(format!(
+                "unexpected request_id {}",
+                resp.request_id
+            ))
+            .into());
+        }
+    }
+    if resp.client_id != pending.client_id {
+        return Err(
+            StdError::generic_err(format!("unexpected client_id {}", resp.client_id)).into(),
+        );
+    }
+
+    // the request is answered either way; only a successful, well-formed result updates the price
+    PENDING_PRICE_REQUEST.remove(deps.storage);
+    let price = match resp.resolve_status {
+        ResolveStatus::Success => decode_price_result(&resp.result, PRICE_MULTIPLIER),
+        status => Err(StdError::generic_err(format!(
+            "request resolved with {status:?}"
+        ))),
+    };
+    let price = match price {
+        Ok(price) => price,
+        Err(err) => {
+            return Ok(IbcReceiveResponse::new()
+                .set_ack(ack_fail(err.to_string()))
+                .add_attribute("action", "oracle_price_received")
+                .add_attribute("request_id", resp.request_id)
+                .add_attribute("error", err.to_string()))
+        }
+    };
+
+    // the price is as old as Band's resolution, not as our receipt of it
+    let mut st = STATE.load(deps.storage)?;
+    st.last_price = Some(PricePoint {
+        price,
+        ts: resp.resolve_time.u64().min(now_ts(&env)),
+    });
+    STATE.save(deps.storage, &st)?;
+
+    Ok(IbcReceiveResponse::new()
+        .set_ack(ack_success())
+        .add_attribute("action", "oracle_price_received")
+        .add_attribute("request_id", resp.request_id)
+        .add_attribute("price", price.to_string()))
+}
+
+/// The in-flight request, if `packet` is its request packet: matched on the packet sequence once
+/// the acknowledgement recorded it, on the client id before that.
+fn pending_request_for(
+    storage: &dyn Storage,
+    packet: &IbcPacket,
+) -> StdResult<Option<PendingPriceRequest>> {
+    let req: OracleRequestPacketData = from_json(&packet.data)?;
+    let pending = PENDING_PRICE_REQUEST.may_load(storage)?;
+    Ok(pending.filter(|p| match p.sequence {
+        Some(sequence) => sequence == packet.sequence,
+        None => p.client_id == req.client_id,
+    }))
+}
+
+#[entry_point]
+pub fn ibc_packet_ack(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let res = IbcBasicResponse::new()
+        .add_attribute("action", "oracle_request_ack")
+        .add_attribute("sequence", msg.original_packet.sequence.to_string());
+
+    let Some(mut pending) = pending_request_for(deps.storage, &msg.original_packet)? else {
+        return Ok(res.add_attribute("stale", "true"));
+    };
+
+    match from_json(&msg.acknowledgement.data)? {
+        AcknowledgementMsg::Result(data) => {
+            let ack: BandAcknowledgement = from_json(&data)?;
+            pending.sequence = Some(msg.original_packet.sequence);
+            pending.band_request_id = Some(ack.request_id.u64());
+            PENDING_PRICE_REQUEST.save(deps.storage, &pending)?;
+            Ok(res.add_attribute("request_id", ack.request_id))
+        }
+        AcknowledgementMsg::Error(err) => {
+            PENDING_PRICE_REQUEST.remove(deps.storage);
+            Ok(res.add_attribute("error", err))
+        }
+    }
+}
+
+#[entry_point]
+pub fn ibc_packet_timeout(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    if pending_request_for(deps.storage, &msg.packet)?.is_some() {
+        PENDING_PRICE_REQUEST.remove(deps.storage);
+    }
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "oracle_request_timeout")
+        .add_attribute("sequence", msg.packet.sequence.to_string()))
+}
@@ -1,7 +1,16 @@
 pub mod contract;
+pub mod ecocredit;
 pub mod error;
+pub mod ibc;
 pub mod math;
+pub mod migrations;
 pub mod msg;
+pub mod oracle;
 pub mod state;
+pub mod token;
+pub mod tokenfactory;
 
-pub use crate::contract::{execute, instantiate, query};
+#[cfg(test)]
+mod tests;
+
+pub use crate::contract::{execute, instantiate, migrate, query, sudo};
@@ -1,18 +1,188 @@
-use cosmwasm_std::{StdError, StdResult};
+//! Fixed-point helpers. Indexes and prices are `Decimal256`, whose atomics are integers scaled by
+//! `SCALE` (see integration-notes.md, "Deterministic math conventions").
 
-/// NOTE: v0.1 uses decimal math as strings to avoid committing to a fixed-point scale too early.
-/// Teams should replace this with a robust fixed-point library (e.g., Decimal256) once design is locked.
+use cosmwasm_std::{Decimal256, StdError, StdResult, Uint128, Uint256};
 
-pub fn parse_u128(s: &str) -> StdResult<u128> {
-    s.parse::<u128>().map_err(|e| StdError::generic_err(format!("bad u128: {e}")))
+/// Fixed-point scale for interest indexes and prices (S = 1e18, same as `Decimal256`).
+pub const SCALE: u128 = 1_000_000_000_000_000_000;
+
+/// Seconds per (non-leap) year used for APR -> per-second conversion.
+pub const SECONDS_PER_YEAR: u64 = 31_536_000;
+
+/// Basis point denominator.
+pub const BPS_DENOM: u128 = 10_000;
+
+/// Starting value of every interest index (1.0).
+pub fn initial_index() -> Decimal256 {
+    Decimal256::one()
+}
+
+/// Compute new interest index after `dt` seconds at `rate_apr_bps`.
+///
+/// `I_new = I_old + I_old * apr_bps * dt / (10_000 * SECONDS_PER_YEAR)`, evaluated on the scaled
+/// atomics and rounded up so that any rounding dust is charged to the borrower, never to lenders.
+pub fn accrue_index(current_index: Decimal256, dt: u64, rate_apr_bps: u32) -> StdResult<Decimal256> {
+    if dt == 0 || rate_apr_bps == 0 {
+        return Ok(current_index);
+    }
+
+    let index = current_index.atomics();
+    let numerator = index
+        .checked_mul(Uint256::from(rate_apr_bps))?
+        .checked_mul(Uint256::from(dt))?;
+    let denominator = Uint256::from(BPS_DENOM) * Uint256::from(SECONDS_PER_YEAR);
+    let growth = div_ceil(numerator, denominator);
+
+    Ok(Decimal256::new(index.checked_add(growth)?))
+}
+
+fn div_ceil(numerator: Uint256, denominator: Uint256) -> Uint256 {
+    let q = numerator / denominator;
+    if (q * denominator) == numerator {
+        q
+    } else {
+        q + Uint256::one()
+    }
+}
+
+/// Interest earned by `balance` bond tokens while the index moved from `from_index` to
+/// `to_index`: `balance * (to_index - from_index) / S`.
+///
+/// Rounded down so the sum of per-account credits never exceeds what the global index owes.
+pub fn accrued_interest(
+    balance: Uint128,
+    from_index: Decimal256,
+    to_index: Decimal256,
+) -> StdResult<Uint128> {
+    if balance.is_zero() || to_index <= from_index {
+        return Ok(Uint128::zero());
+    }
+    let delta = Uint256::from(balance).checked_mul((to_index - from_index).atomics())?
+        / Uint256::from(SCALE);
+    Uint128::try_from(delta).map_err(|_| StdError::generic_err("accrued interest overflow"))
+}
+
+/// Value of `collateral` in principal units at `price`: `V = C * P / S`, rounded down.
+pub fn collateral_value(collateral: Uint128, price: Decimal256) -> StdResult<Uint256> {
+    Ok(Uint256::from(collateral).checked_mul(price.atomics())? / Uint256::from(SCALE))
+}
+
+/// Collateral ratio in bps: `CR = V * 10_000 / max(D, 1)`.
+pub fn collateral_ratio_bps(
+    collateral: Uint128,
+    price: Decimal256,
+    debt: Uint128,
+) -> StdResult<Uint256> {
+    let value = collateral_value(collateral, price)?;
+    Ok(value.checked_mul(Uint256::from(BPS_DENOM))? / Uint256::from(debt.max(Uint128::one())))
+}
+
+/// Collateral a liquidator receives for repaying `repay` principal units:
+/// `repay * (10_000 + bonus_bps) / 10_000 * S / P`, rounded down in the borrower's favour.
+pub fn liquidation_collateral_out(
+    repay: Uint128,
+    bonus_bps: u32,
+    price: Decimal256,
+) -> StdResult<Uint256> {
+    if price.is_zero() {
+        return Err(StdError::generic_err("zero price"));
+    }
+    let numerator = Uint256::from(repay)
+        .checked_mul(Uint256::from(BPS_DENOM + bonus_bps as u128))?
+        .checked_mul(Uint256::from(SCALE))?;
+    let denominator = Uint256::from(BPS_DENOM).checked_mul(price.atomics())?;
+    Ok(numerator / denominator)
+}
+
+/// Largest repayment whose bonus-adjusted collateral payout fits in `collateral`; the inverse of
+/// `liquidation_collateral_out`, rounded down.
+pub fn liquidation_repay_for(
+    collateral: Uint128,
+    bonus_bps: u32,
+    price: Decimal256,
+) -> StdResult<Uint128> {
+    let numerator = Uint256::from(collateral)
+        .checked_mul(price.atomics())?
+        .checked_mul(Uint256::from(BPS_DENOM))?;
+    let denominator =
+        Uint256::from(BPS_DENOM + bonus_bps as u128).checked_mul(Uint256::from(SCALE))?;
+    Uint128::try_from(numerator / denominator)
+        .map_err(|_| StdError::generic_err("repay amount overflow"))
+}
+
+/// `bps` of `amount`, rounded up so a non-zero amount always yields a non-zero share.
+pub fn bps_of_ceil(amount: Uint128, bps: u32) -> StdResult<Uint128> {
+    let numerator = Uint256::from(amount).checked_mul(Uint256::from(bps))?;
+    let share = div_ceil(numerator, Uint256::from(BPS_DENOM));
+    Uint128::try_from(share).map_err(|_| StdError::generic_err("bps share overflow"))
+}
+
+/// Collateral needed so that `debt` sits at `ratio_bps` at `price`:
+/// `debt * ratio_bps / 10_000 * S / P`, rounded up against the borrower.
+pub fn required_collateral(debt: Uint128, ratio_bps: u32, price: Decimal256) -> StdResult<Uint128> {
+    if price.is_zero() {
+        return Err(StdError::generic_err("zero price"));
+    }
+    let numerator = Uint256::from(debt)
+        .checked_mul(Uint256::from(ratio_bps))?
+        .checked_mul(Uint256::from(SCALE))?;
+    let denominator = Uint256::from(BPS_DENOM).checked_mul(price.atomics())?;
+    Uint128::try_from(div_ceil(numerator, denominator))
+        .map_err(|_| StdError::generic_err("required collateral overflow"))
+}
+
+/// `factor * numerator / denominator`, rounded down. Moves the redemption factor and payout index
+/// when `numerator` of `denominator` tokens are redeemed.
+pub fn scale_factor(
+    factor: Decimal256,
+    numerator: Uint128,
+    denominator: Uint128,
+) -> StdResult<Decimal256> {
+    let atomics = factor
+        .atomics()
+        .checked_multiply_ratio(numerator, denominator)
+        .map_err(|e| StdError::generic_err(format!("redemption factor: {e}")))?;
+    Ok(Decimal256::new(atomics))
 }
 
-pub fn to_string_u128(x: u128) -> String {
-    x.to_string()
+/// `balance` checkpointed at redemption factor `from` carried to factor `to`:
+/// `balance * to / from`, rounded up so that a holder only ever gives up whole tokens. Returns the
+/// balance and the fraction of a token the rounding kept, itself rounded up.
+pub fn scale_balance(
+    balance: Uint128,
+    from: Decimal256,
+    to: Decimal256,
+) -> StdResult<(Uint128, Decimal256)> {
+    if balance.is_zero() || from == to {
+        return Ok((balance, Decimal256::zero()));
+    }
+    if from.is_zero() {
+        return Err(StdError::generic_err("zero redemption factor"));
+    }
+    let numerator = Uint256::from(balance).checked_mul(to.atomics())?;
+    let denominator = from.atomics();
+    let scaled = div_ceil(numerator, denominator);
+    let kept = scaled.checked_mul(denominator)? - numerator;
+    let kept = div_ceil(kept.checked_mul(Uint256::from(SCALE))?, denominator);
+    let scaled =
+        Uint128::try_from(scaled).map_err(|_| StdError::generic_err("balance overflow"))?;
+    Ok((scaled, Decimal256::new(kept)))
 }
 
-/// Placeholder: compute new interest index after `dt` seconds at `rate_apr_bps`.
-pub fn accrue_index(current_index: &str, _dt: u64, _rate_apr_bps: u32) -> StdResult<String> {
-    // TODO: implement fixed point math (e.g., index *= (1 + rate_per_second * dt))
-    Ok(current_index.to_string())
+/// What `balance`, checkpointed at redemption factor `factor`, is owed while an index kept per
+/// token at factor 1.0 moved from `from_index` to `to_index`: `balance * (to - from) / factor`,
+/// rounded down. At factor 1.0 this is `accrued_interest`.
+pub fn factor_share(
+    balance: Uint128,
+    factor: Decimal256,
+    from_index: Decimal256,
+    to_index: Decimal256,
+) -> StdResult<Uint128> {
+    if balance.is_zero() || to_index <= from_index {
+        return Ok(Uint128::zero());
+    }
+    let share = Uint256::from(balance)
+        .checked_mul((to_index - from_index).atomics())?
+        .checked_div(factor.atomics())?;
+    Uint128::try_from(share).map_err(|_| StdError::generic_err("index share overflow"))
 }
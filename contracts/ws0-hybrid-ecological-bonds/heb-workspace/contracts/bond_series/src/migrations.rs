@@ -0,0 +1,252 @@
+use cosmwasm_std::{StdResult, Storage};
+
+pub type Step = fn(&mut dyn Storage) -> StdResult<()>;
+
+/// State transformers in version order. Each is keyed by the last version whose layout it
+/// rewrites, and `migrate` runs every one at or above the stored version.
+pub const STEPS: &[(&str, Step)] = &[("0.1.0", v0_1::migrate)];
+
+/// v0.1 stored every amount, index and price as a decimal `String`. Rewrites that state in place
+/// with the typed v0.2 layout; storage keys are unchanged.
+pub mod v0_1 {
+    use std::str::FromStr;
+
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{Decimal256, Order, StdError, StdResult, Storage, Uint128};
+    use cw_storage_plus::{Item, Map};
+    use heb_types::{
+        BandPriceConfig, ImpactCheckpoint, ImpactConfig, ImpactMode, SeriesStatus, SeriesTerms,
+    };
+
+    use crate::math::accrued_interest;
+    use crate::state::{
+        AccountIndex, Config, ImpactPoint, PricePoint, SeriesState, ACCOUNTS, CONFIG, STATE,
+    };
+
+    #[cw_serde]
+    pub struct ImpactCheckpointV0_1 {
+        pub ts: u64,
+        pub target_retired: String,
+    }
+
+    #[cw_serde]
+    pub struct ImpactConfigV0_1 {
+        pub mode: ImpactMode,
+        pub batch_ids: Vec<String>,
+        pub band_impact_script_id: Option<u64>,
+        pub checkpoints: Vec<ImpactCheckpointV0_1>,
+    }
+
+    #[cw_serde]
+    pub struct SeriesTermsV0_1 {
+        pub borrower: String,
+        pub collateral_denom: String,
+        pub principal_denom: String,
+        pub principal_cap: String,
+        pub maturity_ts: u64,
+        pub base_rate_apr_bps: u32,
+        pub penalty_rate_apr_bps: u32,
+        pub coupon_period_seconds: Option<u64>,
+        pub initial_collateral_ratio_bps: u32,
+        pub liquidation_ratio_bps: u32,
+        pub liquidation_bonus_bps: u32,
+        pub oracle: BandPriceConfig,
+        pub impact: ImpactConfigV0_1,
+    }
+
+    #[cw_serde]
+    pub struct ConfigV0_1 {
+        pub admin: String,
+        pub protocol_fee_bps: u32,
+        pub fee_recipient: String,
+        pub terms: SeriesTermsV0_1,
+    }
+
+    #[cw_serde]
+    pub struct PricePointV0_1 {
+        pub price: String,
+        pub ts: u64,
+    }
+
+    #[cw_serde]
+    pub struct ImpactPointV0_1 {
+        pub checkpoint_ts: u64,
+        pub retired_total: String,
+        pub target_retired: String,
+        pub met: bool,
+    }
+
+    #[cw_serde]
+    pub struct SeriesStateV0_1 {
+        pub sale_open: bool,
+        pub paused: bool,
+        pub total_principal_sold: String,
+        pub total_principal_outstanding: String,
+        pub collateral_locked: String,
+        pub global_interest_index: String,
+        pub last_accrual_ts: u64,
+        pub last_price: Option<PricePointV0_1>,
+        pub last_impact: Option<ImpactPointV0_1>,
+    }
+
+    #[cw_serde]
+    pub struct AccountIndexV0_1 {
+        pub balance: String,
+        pub index: String,
+        pub accrued: String,
+    }
+
+    pub const CONFIG_V0_1: Item<ConfigV0_1> = Item::new("config");
+    pub const STATE_V0_1: Item<SeriesStateV0_1> = Item::new("state");
+    pub const ACCOUNTS_V0_1: Map<&str, AccountIndexV0_1> = Map::new("accounts");
+
+    fn amount(field: &str, s: &str) -> StdResult<Uint128> {
+        Uint128::from_str(s).map_err(|e| StdError::generic_err(format!("{field}: {e}")))
+    }
+
+    fn decimal(field: &str, s: &str) -> StdResult<Decimal256> {
+        Decimal256::from_str(s).map_err(|e| StdError::generic_err(format!("{field}: {e}")))
+    }
+
+    pub fn migrate(storage: &mut dyn Storage) -> StdResult<()> {
+        let old = CONFIG_V0_1.load(storage)?;
+        let t = old.terms;
+        let checkpoints = t
+            .impact
+            .checkpoints
+            .into_iter()
+            .map(|cp| {
+                Ok(ImpactCheckpoint {
+                    ts: cp.ts,
+                    target_retired: amount("target_retired", &cp.target_retired)?,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+        let cfg = Config {
+            admin: old.admin,
+            protocol_fee_bps: old.protocol_fee_bps,
+            fee_recipient: old.fee_recipient,
+            terms: SeriesTerms {
+                borrower: t.borrower,
+                collateral_denom: t.collateral_denom,
+                principal_denom: t.principal_denom,
+                principal_cap: amount("principal_cap", &t.principal_cap)?,
+                maturity_ts: t.maturity_ts,
+                base_rate_apr_bps: t.base_rate_apr_bps,
+                penalty_rate_apr_bps: t.penalty_rate_apr_bps,
+                coupon_period_seconds: t.coupon_period_seconds,
+                initial_collateral_ratio_bps: t.initial_collateral_ratio_bps,
+                liquidation_ratio_bps: t.liquidation_ratio_bps,
+                liquidation_bonus_bps: t.liquidation_bonus_bps,
+                close_factor_bps: None,
+                token_mode: None,
+                default_grace_period_seconds: None,
+                coupon_grace_period_seconds: None,
+                call_schedule: None,
+                amortization: None,
+                oracle: t.oracle,
+                impact: ImpactConfig {
+                    mode: t.impact.mode,
+                    batch_ids: t.impact.batch_ids,
+                    band_impact_script_id: t.impact.band_impact_script_id,
+                    checkpoints,
+                },
+            },
+        };
+        CONFIG.save(storage, &cfg)?;
+
+        let accounts = ACCOUNTS_V0_1
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| {
+                let (addr, old) = item?;
+                let acc = AccountIndex {
+                    balance: amount("balance", &old.balance)?,
+                    index: decimal("index", &old.index)?,
+                    accrued: amount("accrued", &old.accrued)?,
+                    redeemed: Uint128::zero(),
+                    redemption_payout: Uint128::zero(),
+                    redemption_factor: Decimal256::one(),
+                    redemption_premium_index: Decimal256::zero(),
+                };
+                Ok((addr, acc))
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let old = STATE_V0_1.load(storage)?;
+        let global_interest_index = decimal("global_interest_index", &old.global_interest_index)?;
+        // v0.1 kept no supply or interest totals; rebuild them from the accounts
+        let mut total_supply = Uint128::zero();
+        let mut interest_accrued = Uint128::zero();
+        for (_, acc) in &accounts {
+            total_supply = total_supply.checked_add(acc.balance)?;
+            interest_accrued = interest_accrued
+                .checked_add(acc.accrued)?
+                .checked_add(accrued_interest(acc.balance, acc.index, global_interest_index)?)?;
+        }
+        let total_principal_sold = amount("total_principal_sold", &old.total_principal_sold)?;
+        let collateral_locked = amount("collateral_locked", &old.collateral_locked)?;
+        let total_principal_outstanding =
+            amount("total_principal_outstanding", &old.total_principal_outstanding)?;
+        // v0.1 repay cut the outstanding principal and kept the funds for redemption at maturity
+        let principal_reserve = total_supply.saturating_sub(total_principal_outstanding);
+        // maturity is applied on the next execute
+        let status = if old.sale_open {
+            SeriesStatus::SaleOpen
+        } else if !total_principal_sold.is_zero() {
+            SeriesStatus::Active
+        } else if !collateral_locked.is_zero() {
+            SeriesStatus::Collateralized
+        } else {
+            SeriesStatus::Created
+        };
+        let st = SeriesState {
+            status,
+            paused: old.paused,
+            total_principal_sold,
+            total_principal_outstanding,
+            total_supply,
+            interest_accrued,
+            interest_funded: Uint128::zero(),
+            interest_credited: interest_accrued,
+            principal_reserve,
+            redemption_reserve: Uint128::zero(),
+            redeemed_unsynced: Uint128::zero(),
+            installments_paid: Uint128::zero(),
+            collateral_locked,
+            global_interest_index,
+            last_accrual_ts: old.last_accrual_ts,
+            holder_interest_index: global_interest_index,
+            redemption_factor: Decimal256::one(),
+            redemption_premium_index: Decimal256::zero(),
+            redemption_carry: Decimal256::zero(),
+            last_price: old
+                .last_price
+                .map(|p| -> StdResult<_> {
+                    Ok(PricePoint { price: decimal("price", &p.price)?, ts: p.ts })
+                })
+                .transpose()?,
+            last_impact: old
+                .last_impact
+                .map(|i| -> StdResult<_> {
+                    Ok(ImpactPoint {
+                        checkpoint_ts: i.checkpoint_ts,
+                        retired_total: amount("retired_total", &i.retired_total)?,
+                        target_retired: amount("target_retired", &i.target_retired)?,
+                        met: i.met,
+                    })
+                })
+                .transpose()?,
+            defaulted_at: None,
+            recovery: None,
+            last_coupon_ts: None,
+            coupon_due_ts: None,
+            coupon_due_credited: Uint128::zero(),
+        };
+        STATE.save(storage, &st)?;
+
+        for (addr, acc) in accounts {
+            ACCOUNTS.save(storage, &addr, &acc)?;
+        }
+        Ok(())
+    }
+}
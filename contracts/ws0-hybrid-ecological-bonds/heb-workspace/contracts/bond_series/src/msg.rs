@@ -1,5 +1,7 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use heb_types::SeriesTerms;
+use cosmwasm_std::{Binary, Coin, Decimal256, Uint128};
+use cw20::Expiration;
+use heb_types::{SeriesStatus, SeriesTerms};
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -15,24 +17,84 @@ pub struct InstantiateMsg {
 #[cw_serde]
 pub enum ExecuteMsg {
     DepositCollateral {},
+    /// Borrower takes back collateral. Needs a fresh price and the remaining collateral to stay
+    /// at or above `initial_collateral_ratio_bps`, unless the series has matured and all principal
+    /// and interest are repaid.
+    WithdrawCollateral { amount: Uint128 },
     OpenSale {},
-    Buy { min_tokens: Option<String> },
+    /// Borrower ends the sale early. The series becomes `Active` if any bond was sold, otherwise
+    /// `SaleClosed`.
+    CloseSale {},
+    /// Borrower or admin abandons a series before any bond is sold; collateral stays withdrawable.
+    Cancel {},
+    /// Buy bond tokens 1:1 with the principal sent. Without `min_tokens` the purchase must fit
+    /// under `principal_cap`; with it, the purchase is partially filled up to the cap and the rest
+    /// refunded, failing if fewer than `min_tokens` would be minted.
+    Buy { min_tokens: Option<Uint128> },
+    /// Pays unfunded interest first, then principal: amortization installments oldest first up to
+    /// the current one, then the principal left for maturity. Installment payments redeem every
+    /// holder pro-rata.
+    /// Open while paused.
     Repay {},
+    /// Borrower funds interest due on the coupon schedule, oldest coupon first. Only interest is
+    /// paid; anything beyond the interest accrued so far is refunded.
+    PayCoupon {},
     ClaimInterest {},
-    RedeemAtMaturity { amount: String },
-    Liquidate { max_repay: String },
+    /// Burn `amount` bond tokens for principal, paid from what the borrower and liquidators have
+    /// repaid. In TokenFactory mode the tokens must be attached as funds.
+    RedeemAtMaturity { amount: Uint128 },
+    /// Burn `amount` bond tokens of a defaulted series for their pro-rata share of the collateral
+    /// and the principal reserve. The first claim fixes the pools; repayment closes from then on.
+    /// In TokenFactory mode the tokens must be attached as funds.
+    ClaimDefaultRecovery { amount: Uint128 },
+    /// Borrower calls `amount` bonds early at the premium of the current call date, funding all
+    /// unpaid interest, the called principal and the premium (see `CallQuote`). Every holder's
+    /// balance is redeemed pro-rata when their account is next synced; the excess sent is
+    /// refunded.
+    CallBonds { amount: Uint128 },
+    /// Collect principal and premium for the sender's tokens redeemed by calls and amortization
+    /// installments. In TokenFactory mode the redeemed tokens must be attached as funds.
+    ClaimRedemption {},
+    Liquidate { max_repay: Uint128 },
 
-    /// v0.1: placeholder. wire Band IBC oracle update flow here.
+    /// Request a fresh REGEN price from BandChain over IBC (see docs/ORACLE_SPEC.md).
     UpdateOraclePrice {},
 
     /// v0.1: on-chain ecocredit checkpoint evaluation
     CheckpointImpact {},
 
+    /// Keeper call: move a matured series with unpaid debt into `Defaulted` once the grace period
+    /// after maturity has passed. Penalty interest accrues from then on.
+    DeclareDefault {},
+
     Pause {},
     Unpause {},
 
-    /// CW20-like transfers (minimal subset)
-    Transfer { recipient: String, amount: String },
+    // CW20 interface; interest is settled for every account whose balance moves. Disabled in
+    // TokenFactory mode, where bond tokens move through the bank module.
+    Transfer { recipient: String, amount: Uint128 },
+    /// Transfer to a contract and call its `Receive(Cw20ReceiveMsg)` hook.
+    Send { contract: String, amount: Uint128, msg: Binary },
+    /// Destroy tokens, forfeiting their principal claim; the borrower no longer owes it and gets
+    /// back whatever of it was already repaid. Interest earned so far stays claimable.
+    Burn { amount: Uint128 },
+    IncreaseAllowance { spender: String, amount: Uint128, expires: Option<Expiration> },
+    DecreaseAllowance { spender: String, amount: Uint128, expires: Option<Expiration> },
+    TransferFrom { owner: String, recipient: String, amount: Uint128 },
+    SendFrom { owner: String, contract: String, amount: Uint128, msg: Binary },
+    BurnFrom { owner: String, amount: Uint128 },
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
+
+/// TokenFactory before-send hook calls, made by the chain for every bank send of the bond denom.
+#[cw_serde]
+pub enum SudoMsg {
+    /// Runs before the send; an error aborts it. The series books the transfer here.
+    BlockBeforeSend { from: String, to: String, amount: Coin },
+    /// Runs before the send; errors are ignored by the chain, so nothing is booked here.
+    TrackBeforeSend { from: String, to: String, amount: Coin },
 }
 
 #[cw_serde]
@@ -52,6 +114,34 @@ pub enum QueryMsg {
     PriceStatus {},
     #[returns(ImpactStatusResponse)]
     ImpactStatus {},
+    #[returns(EffectiveAprResponse)]
+    EffectiveApr {},
+    #[returns(SaleCollateralResponse)]
+    SaleCollateral {},
+    /// What `address` would recover from the collateral if the series defaulted now.
+    #[returns(ExpectedRecoveryResponse)]
+    ExpectedRecovery { address: String },
+    /// Coupon dates after `start_after`, with `holder`'s balance on each passed date if given.
+    #[returns(CouponScheduleResponse)]
+    CouponSchedule { holder: Option<String>, start_after: Option<u64>, limit: Option<u32> },
+    /// Funds `CallBonds { amount }` needs at the current block time.
+    #[returns(CallQuoteResponse)]
+    CallQuote { amount: Uint128 },
+    /// Tokens of `address` redeemed by calls and installments and not claimed yet.
+    #[returns(PendingRedemptionResponse)]
+    PendingRedemption { address: String },
+    #[returns(AmortizationResponse)]
+    Amortization {},
+
+    // CW20 queries; `Balance` above is the CW20 balance query.
+    #[returns(cw20::TokenInfoResponse)]
+    TokenInfo {},
+    #[returns(cw20::AllowanceResponse)]
+    Allowance { owner: String, spender: String },
+    #[returns(cw20::AllAllowancesResponse)]
+    AllAllowances { owner: String, start_after: Option<String>, limit: Option<u32> },
+    #[returns(cw20::AllAccountsResponse)]
+    AllAccounts { start_after: Option<String>, limit: Option<u32> },
 }
 
 #[cw_serde]
@@ -60,50 +150,232 @@ pub struct TermsResponse {
     pub admin: String,
     pub protocol_fee_bps: u32,
     pub fee_recipient: String,
+    /// native bond token denom; `None` in CW20 mode
+    pub bond_denom: Option<String>,
 }
 
 #[cw_serde]
 pub struct StateResponse {
+    /// lifecycle status as of the queried block time
+    pub status: SeriesStatus,
     pub sale_open: bool,
     pub paused: bool,
 
-    pub total_principal_sold: String,
-    pub total_principal_outstanding: String,
+    pub total_principal_sold: Uint128,
+    pub total_principal_outstanding: Uint128,
+    pub principal_reserve: Uint128,
+    pub redemption_reserve: Uint128,
+    pub total_supply: Uint128,
+    pub interest_accrued: Uint128,
+    pub interest_funded: Uint128,
 
-    pub collateral_locked: String,
+    pub collateral_locked: Uint128,
 
-    pub global_interest_index: String,
+    pub global_interest_index: Decimal256,
     pub last_accrual_ts: u64,
 
     pub last_price: Option<PriceStatusResponse>,
     pub last_impact: Option<ImpactStatusResponse>,
+    pub defaulted_at: Option<u64>,
 }
 
 #[cw_serde]
 pub struct BalanceResponse {
-    pub balance: String,
+    pub balance: Uint128,
 }
 
 #[cw_serde]
 pub struct AccruedInterestResponse {
-    pub accrued: String,
+    pub accrued: Uint128,
+}
+
+#[cw_serde]
+pub enum CollateralHealth {
+    /// CR at or above `initial_collateral_ratio_bps`
+    Healthy,
+    /// CR between `liquidation_ratio_bps` and `initial_collateral_ratio_bps`
+    AtRisk,
+    /// CR below `liquidation_ratio_bps`
+    Liquidatable,
 }
 
+/// Price-dependent fields are `None` until the first oracle price arrives.
 #[cw_serde]
 pub struct CollateralRatioResponse {
+    /// `collateral_value * 10_000 / max(debt, 1)`, saturating at `u32::MAX`
     pub ratio_bps: Option<u32>,
+    pub health: Option<CollateralHealth>,
+    pub collateral_locked: Uint128,
+    /// collateral value in principal denom at the last price
+    pub collateral_value: Option<Uint128>,
+    pub principal_outstanding: Uint128,
+    /// interest due but not yet funded by the borrower, projected to the current block time
+    pub interest_accrued: Uint128,
+    /// `principal_outstanding + interest_accrued`
+    pub debt: Uint128,
+    pub price: Option<PriceStatusResponse>,
+    /// price below which the series becomes liquidatable
+    pub liquidation_price: Option<Decimal256>,
+    /// principal a single `Liquidate` could repay right now; zero unless liquidatable with a
+    /// fresh price
+    pub max_liquidation_repay: Uint128,
 }
 
 #[cw_serde]
 pub struct PriceStatusResponse {
-    pub price: String,
+    pub price: Decimal256,
     pub ts: u64,
+    /// true when a price exists and is no older than `max_price_age_seconds`
+    pub is_fresh: bool,
+    /// seconds since `ts`; `None` when no price has been received yet
+    pub age_seconds: Option<u64>,
 }
 
 #[cw_serde]
 pub struct ImpactStatusResponse {
     pub checkpoint_ts: u64,
-    pub retired_total: String,
-    pub target_retired: String,
+    pub retired_total: Uint128,
+    pub target_retired: Uint128,
     pub met: bool,
 }
+
+/// Why the current APR applies.
+#[cw_serde]
+pub enum AprReason {
+    /// no impact checkpoint has been evaluated yet
+    NoCheckpoint,
+    /// the last evaluated checkpoint met its target
+    ImpactMet,
+    /// the last evaluated checkpoint missed its target; the penalty APR is added
+    ImpactMissed,
+    /// the series is in default; the penalty APR is added until it settles
+    Defaulted,
+    /// a coupon is unpaid past its grace period; the penalty APR is added until it is paid
+    CouponLate,
+}
+
+#[cw_serde]
+pub struct EffectiveAprResponse {
+    /// APR currently used for accrual
+    pub apr_bps: u32,
+    pub base_rate_apr_bps: u32,
+    pub penalty_rate_apr_bps: u32,
+    pub penalty_active: bool,
+    pub reason: AprReason,
+    /// checkpoint behind `reason`, if any
+    pub checkpoint_ts: Option<u64>,
+}
+
+/// Collateral gate for `OpenSale`. Price-dependent fields are `None` until the first oracle price
+/// arrives; `OpenSale` additionally needs that price to be fresh.
+#[cw_serde]
+pub struct SaleCollateralResponse {
+    pub collateral_locked: Uint128,
+    /// collateral covering `principal_cap` at `initial_collateral_ratio_bps`
+    pub required: Option<Uint128>,
+    /// additional collateral to deposit before the sale can open
+    pub shortfall: Option<Uint128>,
+    pub price: Option<PriceStatusResponse>,
+}
+
+#[cw_serde]
+pub struct ExpectedRecoveryResponse {
+    pub status: SeriesStatus,
+    /// true once the series is `Defaulted` and the collateral belongs to holders
+    pub collateral_claimable: bool,
+    /// earliest time `DeclareDefault` is allowed
+    pub default_after: u64,
+    pub balance: Uint128,
+    /// principal plus interest owed to the holder, projected to the current block time
+    pub claim: Uint128,
+    /// holder's pro-rata share (by balance) of the locked collateral, or of the snapshot once
+    /// recovery has started
+    pub collateral_share: Uint128,
+    /// `collateral_share` at the last price
+    pub collateral_value: Option<Uint128>,
+    /// holder's pro-rata share of the principal reserve
+    pub reserve_share: Uint128,
+    /// `(collateral_value + reserve_share) * 10_000 / claim`, saturating at `u32::MAX`
+    pub recovery_bps: Option<u32>,
+}
+
+#[cw_serde]
+pub enum CouponStatus {
+    /// coupon date not reached yet
+    Scheduled,
+    /// coupon date passed, not fully paid, still within the grace period
+    Due,
+    Paid,
+    /// unpaid past the grace period
+    Late,
+}
+
+#[cw_serde]
+pub struct CouponResponse {
+    pub ts: u64,
+    pub status: CouponStatus,
+    /// end of the grace period
+    pub late_after: u64,
+    /// interest credited over the coupon period; `None` before the coupon date
+    pub amount_due: Option<Uint128>,
+    /// part of `amount_due` the borrower has paid in
+    pub amount_paid: Uint128,
+    /// bond tokens in circulation on the coupon date
+    pub record_supply: Option<Uint128>,
+    /// `holder`'s balance on the coupon date
+    pub holder_balance: Option<Uint128>,
+}
+
+/// Coupon schedule as of the queried block time; empty for series without `coupon_period_seconds`.
+#[cw_serde]
+pub struct CouponScheduleResponse {
+    pub coupons: Vec<CouponResponse>,
+}
+
+#[cw_serde]
+pub struct CallQuoteResponse {
+    /// premium of the current call date; `None` when the series is not callable now
+    pub premium_bps: Option<u32>,
+    pub principal: Uint128,
+    pub premium: Uint128,
+    /// unpaid interest, which a call funds in full
+    pub interest: Uint128,
+    /// `principal + premium + interest`
+    pub total: Uint128,
+}
+
+#[cw_serde]
+pub struct PendingRedemptionResponse {
+    /// tokens taken out of the balance; in TokenFactory mode they go back with `ClaimRedemption`
+    pub redeemed: Uint128,
+    /// principal and premium `ClaimRedemption` pays
+    pub payout: Uint128,
+}
+
+#[cw_serde]
+pub enum InstallmentStatus {
+    /// not fully paid, date not reached yet
+    Scheduled,
+    Paid,
+    /// not fully paid past its date
+    Overdue,
+}
+
+#[cw_serde]
+pub struct InstallmentResponse {
+    pub ts: u64,
+    pub status: InstallmentStatus,
+    /// `principal_bps` of the principal sold
+    pub amount_due: Uint128,
+    pub amount_paid: Uint128,
+}
+
+/// Amortization schedule; empty for bullet series.
+#[cw_serde]
+pub struct AmortizationResponse {
+    pub installments: Vec<InstallmentResponse>,
+    pub total_due: Uint128,
+    pub total_paid: Uint128,
+    /// unpaid principal of installments past their date
+    pub overdue: Uint128,
+}
@@ -0,0 +1,122 @@
+//! BandChain packet types (CW-Band) and the OBI encoding used by the REGEN price script.
+//! See docs/ORACLE_SPEC.md for the request/response flow.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Binary, Coin, Decimal256, StdError, StdResult, Uint64};
+
+/// IBC channel version BandChain's oracle module negotiates.
+pub const BAND_IBC_VERSION: &str = "bandchain-1";
+
+/// Seconds a price request packet may wait for relaying before it times out.
+pub const PRICE_REQUEST_TIMEOUT_SECONDS: u64 = 600;
+
+/// Symbol requested from the price script.
+pub const PRICE_SYMBOL: &str = "REGEN";
+
+/// Band rates are integers scaled by this multiplier.
+pub const PRICE_MULTIPLIER: u64 = 1_000_000_000;
+
+pub const ASK_COUNT: u64 = 4;
+pub const MIN_COUNT: u64 = 3;
+pub const PREPARE_GAS: u64 = 100_000;
+pub const EXECUTE_GAS: u64 = 500_000;
+
+#[cw_serde]
+pub struct OracleRequestPacketData {
+    pub client_id: String,
+    pub oracle_script_id: Uint64,
+    pub calldata: Binary,
+    pub ask_count: Uint64,
+    pub min_count: Uint64,
+    pub fee_limit: Vec<Coin>,
+    pub prepare_gas: Uint64,
+    pub execute_gas: Uint64,
+}
+
+#[cw_serde]
+pub struct OracleResponsePacketData {
+    pub client_id: String,
+    pub request_id: Uint64,
+    pub ans_count: Uint64,
+    pub request_time: Uint64,
+    pub resolve_time: Uint64,
+    pub resolve_status: ResolveStatus,
+    pub result: Binary,
+}
+
+#[derive(
+    serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq, schemars::JsonSchema,
+)]
+pub enum ResolveStatus {
+    #[serde(rename = "RESOLVE_STATUS_OPEN_UNSPECIFIED")]
+    Open,
+    #[serde(rename = "RESOLVE_STATUS_SUCCESS")]
+    Success,
+    #[serde(rename = "RESOLVE_STATUS_FAILURE")]
+    Failure,
+    #[serde(rename = "RESOLVE_STATUS_EXPIRED")]
+    Expired,
+}
+
+/// Payload BandChain acknowledges a request packet with.
+#[cw_serde]
+pub struct BandAcknowledgement {
+    pub request_id: Uint64,
+}
+
+/// ICS-20 style acknowledgement envelope used in both directions.
+#[cw_serde]
+pub enum AcknowledgementMsg {
+    Result(Binary),
+    Error(String),
+}
+
+/// OBI-encoded calldata for the price script: `{ symbols: [string], multiplier: u64 }`.
+pub fn encode_price_calldata(symbol: &str, multiplier: u64) -> Binary {
+    let mut out = Vec::new();
+    out.extend_from_slice(&1u32.to_be_bytes());
+    out.extend_from_slice(&(symbol.len() as u32).to_be_bytes());
+    out.extend_from_slice(symbol.as_bytes());
+    out.extend_from_slice(&multiplier.to_be_bytes());
+    Binary::from(out)
+}
+
+/// Decode the OBI price script result `{ rates: [u64] }` into a price.
+///
+/// Exactly one rate is expected and it must be non-zero; the price is `rate / multiplier`.
+pub fn decode_price_result(result: &[u8], multiplier: u64) -> StdResult<Decimal256> {
+    let mut r = ObiReader(result);
+    let len = r.u32()?;
+    if len != 1 {
+        return Err(StdError::generic_err(format!("expected 1 rate, got {len}")));
+    }
+    let rate = r.u64()?;
+    if !r.0.is_empty() {
+        return Err(StdError::generic_err("trailing bytes in oracle result"));
+    }
+    if rate == 0 {
+        return Err(StdError::generic_err("oracle returned zero price"));
+    }
+    Ok(Decimal256::from_ratio(rate, multiplier))
+}
+
+struct ObiReader<'a>(&'a [u8]);
+
+impl ObiReader<'_> {
+    fn take<const N: usize>(&mut self) -> StdResult<[u8; N]> {
+        if self.0.len() < N {
+            return Err(StdError::generic_err("oracle result too short"));
+        }
+        let (head, rest) = self.0.split_at(N);
+        self.0 = rest;
+        Ok(head.try_into().expect("split_at returns N bytes"))
+    }
+
+    fn u32(&mut self) -> StdResult<u32> {
+        Ok(u32::from_be_bytes(self.take()?))
+    }
+
+    fn u64(&mut self) -> StdResult<u64> {
+        Ok(u64::from_be_bytes(self.take()?))
+    }
+}
@@ -1,6 +1,8 @@
 use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Decimal256, Uint128};
+use cw20::AllowanceResponse;
 use cw_storage_plus::{Item, Map};
-use heb_types::SeriesTerms;
+use heb_types::{SeriesStatus, SeriesTerms};
 
 #[cw_serde]
 pub struct Config {
@@ -12,45 +14,166 @@ pub struct Config {
 
 #[cw_serde]
 pub struct PricePoint {
-    /// price in fixed-point string (implementation decides scale)
-    pub price: String,
+    /// principal_denom base units per 1 collateral_denom base unit (scale S = 1e18)
+    pub price: Decimal256,
     pub ts: u64,
 }
 
 #[cw_serde]
 pub struct ImpactPoint {
     pub checkpoint_ts: u64,
-    pub retired_total: String,
-    pub target_retired: String,
+    pub retired_total: Uint128,
+    pub target_retired: Uint128,
     pub met: bool,
 }
 
 #[cw_serde]
 pub struct SeriesState {
-    pub sale_open: bool,
+    pub status: SeriesStatus,
+    /// admin emergency switch, orthogonal to `status`
     pub paused: bool,
 
-    pub total_principal_sold: String,
-    pub total_principal_outstanding: String,
+    pub total_principal_sold: Uint128,
+    /// principal the borrower still owes
+    pub total_principal_outstanding: Uint128,
+    /// principal repaid by the borrower or liquidators and not yet paid out to holders
+    pub principal_reserve: Uint128,
+    /// principal and premium of pro-rata redemptions, owed to holders until they claim it
+    pub redemption_reserve: Uint128,
+    /// tokens redeemed pro-rata that holders' stored balances still include; they come off as each
+    /// account is synced
+    pub redeemed_unsynced: Uint128,
+    /// principal repaid toward the amortization schedule, oldest installment first
+    pub installments_paid: Uint128,
+    /// bond tokens in circulation (minted on buy, burned on redeem)
+    pub total_supply: Uint128,
+    /// interest credited to holders through the index and not yet claimed (interest due)
+    pub interest_accrued: Uint128,
+    /// part of `interest_accrued` the borrower has already paid in; `ClaimInterest` pays from it
+    pub interest_funded: Uint128,
+    /// interest ever credited to holders through the index; never decreases
+    pub interest_credited: Uint128,
 
-    pub collateral_locked: String,
+    pub collateral_locked: Uint128,
 
-    /// interest index is fixed-point string (implementation decides scale)
-    pub global_interest_index: String,
+    /// interest index (scale S = 1e18), starts at 1.0
+    pub global_interest_index: Decimal256,
     pub last_accrual_ts: u64,
+    /// `global_interest_index` growth weighted by `redemption_factor`: the interest earned by a
+    /// token held since factor 1.0. Equal to the global index until the first redemption
+    pub holder_interest_index: Decimal256,
+    /// share of every balance left by pro-rata redemptions (scale S = 1e18), starts at 1.0.
+    /// Redemptions only move this and `redemption_premium_index`, and each account applies them
+    /// when it is next synced
+    pub redemption_factor: Decimal256,
+    /// call premium per token held since factor 1.0
+    pub redemption_premium_index: Decimal256,
+    /// fractions of a token that accounts kept when their redeemed balances were rounded up, not
+    /// back in circulation yet
+    pub redemption_carry: Decimal256,
 
     pub last_price: Option<PricePoint>,
     pub last_impact: Option<ImpactPoint>,
+
+    /// when `DeclareDefault` ran
+    pub defaulted_at: Option<u64>,
+    /// recovery pools, fixed at the first `ClaimDefaultRecovery`
+    pub recovery: Option<RecoverySnapshot>,
+
+    /// latest coupon date that has passed
+    pub last_coupon_ts: Option<u64>,
+    /// latest coupon date whose grace period has ended
+    pub coupon_due_ts: Option<u64>,
+    /// `interest_credited` as of `coupon_due_ts`; coupons are late while the borrower has paid in
+    /// less than this
+    pub coupon_due_credited: Uint128,
+}
+
+/// What a defaulted series distributes per bond token. Every claim pays
+/// `amount * pool / supply`, so neither transfers nor the order of claims change the rate.
+#[cw_serde]
+pub struct RecoverySnapshot {
+    pub ts: u64,
+    /// bond tokens in circulation when the snapshot was taken
+    pub supply: Uint128,
+    pub collateral: Uint128,
+    pub principal_reserve: Uint128,
+}
+
+/// A date on the coupon schedule. Interest accrues through the index as usual; the coupon is the
+/// interest credited since the previous coupon date, which the borrower funds with `PayCoupon`.
+#[cw_serde]
+pub struct Coupon {
+    pub ts: u64,
+    /// fixed when the coupon date passes
+    pub record: Option<CouponRecord>,
+}
+
+#[cw_serde]
+pub struct CouponRecord {
+    /// interest credited between the previous coupon date and this one
+    pub amount_due: Uint128,
+    /// `SeriesState::interest_credited` as of the coupon date
+    pub interest_credited: Uint128,
+    /// bond tokens in circulation on the coupon date
+    pub supply: Uint128,
+    /// `SeriesState::redemption_factor` on the coupon date
+    pub redemption_factor: Decimal256,
+}
+
+/// The single in-flight Band price request. Cleared when the response, an error ack or a timeout
+/// arrives.
+#[cw_serde]
+pub struct PendingPriceRequest {
+    pub client_id: String,
+    pub channel_id: String,
+    pub sent_at: u64,
+    pub timeout_ts: u64,
+    /// request packet sequence, known once BandChain acknowledges the packet
+    pub sequence: Option<u64>,
+    /// BandChain request id from the acknowledgement
+    pub band_request_id: Option<u64>,
 }
 
 /// CW20-like balances
 #[cw_serde]
 pub struct AccountIndex {
-    pub balance: String,
-    pub index: String,
-    pub accrued: String,
+    pub balance: Uint128,
+    /// `holder_interest_index` at the last sync
+    pub index: Decimal256,
+    pub accrued: Uint128,
+    /// tokens taken out of `balance` by redemptions and not claimed yet
+    #[serde(default)]
+    pub redeemed: Uint128,
+    /// principal and premium owed for `redeemed`
+    #[serde(default)]
+    pub redemption_payout: Uint128,
+    /// `redemption_factor` at the last sync; `balance` is the balance as of then
+    #[serde(default = "Decimal256::one")]
+    pub redemption_factor: Decimal256,
+    /// `redemption_premium_index` at the last sync
+    #[serde(default)]
+    pub redemption_premium_index: Decimal256,
+}
+
+/// A stored balance with the redemption factor it was synced at.
+#[cw_serde]
+pub struct CouponBalance {
+    pub balance: Uint128,
+    pub redemption_factor: Decimal256,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const STATE: Item<SeriesState> = Item::new("state");
 pub const ACCOUNTS: Map<&str, AccountIndex> = Map::new("accounts");
+/// CW20 allowances keyed by (owner, spender).
+pub const ALLOWANCES: Map<(&str, &str), AllowanceResponse> = Map::new("allowances");
+/// Coupon schedule keyed by coupon date, generated at instantiate.
+pub const COUPONS: Map<u64, Coupon> = Map::new("coupons");
+/// Holder balances on coupon dates, keyed by (holder, coupon date). Written the first time an
+/// account is synced after a coupon date; a holder's balance on date `d` is the first entry at or
+/// after `d`, or the stored account if there is none, carried to the redemption factor of `d`.
+pub const COUPON_BALANCES: Map<(&str, u64), CouponBalance> = Map::new("coupon_balances");
+pub const PENDING_PRICE_REQUEST: Item<PendingPriceRequest> = Item::new("pending_price_request");
+/// Monotonic counter used to derive Band `client_id`s.
+pub const PRICE_REQUEST_NONCE: Item<u64> = Item::new("price_request_nonce");
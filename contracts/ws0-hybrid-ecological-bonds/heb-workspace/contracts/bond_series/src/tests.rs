@@ -1,11 +1,4265 @@
-#[cfg(test)]
-mod tests {
-    use super::*;
-    // TODO: add cw-multi-test harness tests for:
-    // - instantiate
-    // - deposit collateral
-    // - open sale + buy
-    // - repay + claim interest
-    // - redeem at maturity
-    // - liquidation path
+mod math {
+    use proptest::prelude::*;
+
+    use cosmwasm_std::{Decimal256, Uint128, Uint256};
+
+    use crate::math::{
+        accrue_index, accrued_interest, factor_share, initial_index, scale_balance, scale_factor,
+        SCALE, SECONDS_PER_YEAR,
+    };
+
+    fn atomics(index: u128) -> Decimal256 {
+        Decimal256::new(Uint256::from(index))
+    }
+
+    fn accrue(index: u128, dt: u64, apr_bps: u32) -> u128 {
+        let next = accrue_index(atomics(index), dt, apr_bps).unwrap();
+        Uint128::try_from(next.atomics()).unwrap().u128()
+    }
+
+    #[test]
+    fn initial_index_is_one() {
+        assert_eq!(initial_index().atomics(), Uint256::from(SCALE));
+    }
+
+    #[test]
+    fn scenario_f_one_year_at_ten_percent() {
+        let index = accrue(SCALE, SECONDS_PER_YEAR, 1_000);
+        assert_eq!(index, 1_100_000_000_000_000_000);
+    }
+
+    #[test]
+    fn scenario_f_accrued_in_many_steps_compounds_slightly_above() {
+        let mut index = SCALE;
+        for _ in 0..365 {
+            index = accrue(index, 86_400, 1_000);
+        }
+        // daily compounding of 10% APR: (1 + 0.1/365)^365 ~= 1.10516
+        assert!(index > 1_105_000_000_000_000_000);
+        assert!(index < 1_106_000_000_000_000_000);
+    }
+
+    #[test]
+    fn zero_rate_or_zero_dt_is_identity() {
+        assert_eq!(accrue(SCALE, 0, 1_000), SCALE);
+        assert_eq!(accrue(SCALE, SECONDS_PER_YEAR, 0), SCALE);
+    }
+
+    #[test]
+    fn rounds_against_borrower() {
+        // growth below one unit of precision is still rounded up
+        assert_eq!(accrue(1, 1, 1), 2);
+        assert_eq!(accrue(SCALE, 1, 1), SCALE + 3_170_980);
+    }
+
+    #[test]
+    fn overflow_is_an_error() {
+        assert!(accrue_index(Decimal256::MAX, SECONDS_PER_YEAR, 10_000).is_err());
+    }
+
+    #[test]
+    fn accrued_interest_rounds_down() {
+        let from = atomics(SCALE);
+        assert_eq!(
+            accrued_interest(
+                Uint128::new(1_000),
+                from,
+                atomics(1_100_000_000_000_000_000)
+            )
+            .unwrap(),
+            Uint128::new(100)
+        );
+        assert_eq!(
+            accrued_interest(
+                Uint128::new(1_000),
+                from,
+                atomics(SCALE + 999_999_999_999_999)
+            )
+            .unwrap(),
+            Uint128::zero()
+        );
+        // an index that went backwards never produces negative interest
+        assert_eq!(
+            accrued_interest(Uint128::new(1_000), from, atomics(1)).unwrap(),
+            Uint128::zero()
+        );
+    }
+
+    #[test]
+    fn redemption_factor_scales_balances_up_to_whole_tokens() {
+        // a third of the supply redeemed
+        let factor = scale_factor(initial_index(), Uint128::new(2), Uint128::new(3)).unwrap();
+        assert_eq!(factor.atomics(), Uint256::from(666_666_666_666_666_666u128));
+        // 300 * 2/3 = 199.9999...: the holder gives up 100 tokens and keeps the fraction
+        let (left, kept) = scale_balance(Uint128::new(300), initial_index(), factor).unwrap();
+        assert_eq!(left, Uint128::new(200));
+        assert_eq!(kept.atomics(), Uint256::from(200u128));
+        let (left, kept) = scale_balance(Uint128::new(1), initial_index(), factor).unwrap();
+        assert_eq!(left, Uint128::new(1));
+        assert_eq!(kept.atomics(), Uint256::from(333_333_333_333_333_334u128));
+        // an account checkpointed at the current factor keeps its balance
+        assert_eq!(
+            scale_balance(Uint128::new(300), factor, factor).unwrap(),
+            (Uint128::new(300), Decimal256::zero())
+        );
+        let gone = scale_factor(factor, Uint128::zero(), Uint128::new(2)).unwrap();
+        assert_eq!(
+            scale_balance(Uint128::new(200), factor, gone).unwrap(),
+            (Uint128::zero(), Decimal256::zero())
+        );
+        assert!(scale_balance(Uint128::new(1), gone, factor).is_err());
+    }
+
+    #[test]
+    fn factor_share_divides_by_the_checkpointed_factor() {
+        let half = atomics(SCALE / 2);
+        // 100 tokens held at factor 0.5 stand for 200 tokens held at 1.0
+        assert_eq!(
+            factor_share(Uint128::new(100), half, atomics(0), atomics(SCALE / 4)).unwrap(),
+            Uint128::new(50)
+        );
+        assert_eq!(
+            factor_share(
+                Uint128::new(1_000),
+                initial_index(),
+                atomics(SCALE),
+                atomics(1_100_000_000_000_000_000)
+            )
+            .unwrap(),
+            accrued_interest(
+                Uint128::new(1_000),
+                atomics(SCALE),
+                atomics(1_100_000_000_000_000_000)
+            )
+            .unwrap()
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn lazy_redemption_only_takes_whole_tokens_and_books_the_fractions(
+            balances in prop::collection::vec(0u128..1_000_000, 1..20),
+            percent in prop::collection::vec(0u128..=100, 1..4),
+        ) {
+            let mut supply: u128 = balances.iter().sum();
+            prop_assume!(supply > 0);
+            let (mut factor, mut premium_index) = (initial_index(), Decimal256::zero());
+            let (mut redeemed, mut premium) = (0u128, 0u128);
+            for percent in percent {
+                if supply == 0 {
+                    break;
+                }
+                let amount = supply * percent / 100;
+                // a 2% premium
+                let supply_u = Uint128::new(supply);
+                premium_index +=
+                    scale_factor(factor, Uint128::new(amount / 50), supply_u).unwrap();
+                factor = scale_factor(factor, Uint128::new(supply - amount), supply_u).unwrap();
+                supply -= amount;
+                redeemed += amount;
+                premium += amount / 50;
+            }
+            let (mut left, mut taken, mut carry) = (0u128, 0u128, Decimal256::zero());
+            let mut premium_paid = 0u128;
+            for b in &balances {
+                let b = Uint128::new(*b);
+                let (balance, kept) = scale_balance(b, initial_index(), factor).unwrap();
+                let taken_here = b - balance;
+                premium_paid += factor_share(
+                    taken_here,
+                    initial_index() - factor,
+                    Decimal256::zero(),
+                    premium_index,
+                )
+                .unwrap()
+                .u128();
+                left += balance.u128();
+                taken += taken_here.u128();
+                carry += kept;
+            }
+            // the fractions kept make up exactly the tokens not taken, give or take rounding
+            prop_assert!(taken <= redeemed);
+            prop_assert_eq!(left + taken, supply + redeemed);
+            let booked = carry.to_uint_floor();
+            prop_assert!(booked >= Uint256::from(redeemed - taken));
+            prop_assert!(booked <= Uint256::from(redeemed - taken + 1));
+            prop_assert!(premium_paid <= premium);
+        }
+
+        #[test]
+        fn index_is_monotonic(
+            index in SCALE..SCALE * 1_000,
+            dt in 0u64..10 * SECONDS_PER_YEAR,
+            apr_bps in 0u32..100_000,
+        ) {
+            let next = accrue(index, dt, apr_bps);
+            prop_assert!(next >= index);
+            if dt > 0 && apr_bps > 0 {
+                prop_assert!(next > index);
+            }
+        }
+
+        #[test]
+        fn index_grows_with_rate_and_time(
+            index in SCALE..SCALE * 1_000,
+            dt in 1u64..10 * SECONDS_PER_YEAR,
+            apr_bps in 1u32..100_000,
+        ) {
+            let base = accrue(index, dt, apr_bps);
+            prop_assert!(accrue(index, dt + 1, apr_bps) >= base);
+            prop_assert!(accrue(index, dt, apr_bps + 1) >= base);
+        }
+
+        #[test]
+        fn splitting_an_interval_never_undercharges(
+            dt_a in 0u64..SECONDS_PER_YEAR,
+            dt_b in 0u64..SECONDS_PER_YEAR,
+            apr_bps in 0u32..50_000,
+        ) {
+            let once = accrue(SCALE, dt_a + dt_b, apr_bps);
+            let split = accrue(accrue(SCALE, dt_a, apr_bps), dt_b, apr_bps);
+            prop_assert!(split >= once);
+        }
+    }
+}
+
+mod helpers {
+    use std::collections::HashMap;
+    use std::marker::PhantomData;
+
+    use cosmwasm_std::testing::{
+        mock_env, mock_ibc_packet_ack, mock_ibc_packet_recv, mock_info, MockApi, MockQuerier,
+        MockStorage,
+    };
+    use cosmwasm_std::{
+        coins, from_json, to_json_binary, Binary, ContractResult, CosmosMsg, Decimal256, Empty,
+        Env, IbcAcknowledgement, IbcMsg, IbcReceiveResponse, OwnedDeps, Querier, QuerierResult,
+        QueryRequest, Response, SystemError, SystemResult, Uint128,
+    };
+    use heb_types::{BandPriceConfig, ImpactConfig, ImpactMode, SeriesTerms};
+
+    use crate::contract::{execute, instantiate, query};
+    use crate::ecocredit::{QuerySupplyResponse, SUPPLY_QUERY_PATH};
+    use crate::error::ContractError;
+    use crate::ibc::{ibc_packet_ack, ibc_packet_receive};
+    use crate::msg::{
+        AccruedInterestResponse, BalanceResponse, ExecuteMsg, InstantiateMsg,
+        PendingRedemptionResponse, QueryMsg, StateResponse,
+    };
+    use crate::oracle::{
+        AcknowledgementMsg, BandAcknowledgement, OracleRequestPacketData, OracleResponsePacketData,
+        ResolveStatus, PRICE_MULTIPLIER,
+    };
+
+    pub const ADMIN: &str = "admin";
+    pub const BORROWER: &str = "borrower";
+    pub const FEE_RECIPIENT: &str = "fees";
+    pub const LENDER: &str = "lender";
+    pub const LENDER2: &str = "lender2";
+    pub const COLLATERAL: &str = "uregen";
+    pub const PRINCIPAL: &str = "ibc/USDC";
+    pub const BAND_CHANNEL: &str = "channel-0";
+    pub const YEAR: u64 = crate::math::SECONDS_PER_YEAR;
+
+    pub type Deps = OwnedDeps<MockStorage, MockApi, TestQuerier>;
+
+    /// `MockQuerier` plus a stand-in for the ecocredit module's `Supply` query.
+    pub struct TestQuerier {
+        pub base: MockQuerier,
+        /// retired amount (decimal credits) per batch denom; unknown batches error
+        pub retired: HashMap<String, String>,
+    }
+
+    impl TestQuerier {
+        fn supply(&self, data: &[u8]) -> QuerierResult {
+            // QuerySupplyRequest { batch_denom = 1 } with a one-byte length
+            let batch = String::from_utf8(data[2..].to_vec()).unwrap();
+            match self.retired.get(&batch) {
+                Some(retired) => SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&QuerySupplyResponse {
+                        tradable_amount: "0".to_string(),
+                        retired_amount: retired.clone(),
+                        cancelled_amount: "0".to_string(),
+                    })
+                    .unwrap(),
+                )),
+                None => SystemResult::Ok(ContractResult::Err(format!("batch {batch} not found"))),
+            }
+        }
+    }
+
+    impl Querier for TestQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            match from_json::<QueryRequest<Empty>>(bin_request) {
+                Ok(QueryRequest::Stargate { path, data }) if path == SUPPLY_QUERY_PATH => {
+                    self.supply(&data)
+                }
+                Ok(_) => self.base.raw_query(bin_request),
+                Err(e) => SystemResult::Err(SystemError::InvalidRequest {
+                    error: e.to_string(),
+                    request: bin_request.into(),
+                }),
+            }
+        }
+    }
+
+    pub fn mock_deps() -> Deps {
+        OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: TestQuerier {
+                base: MockQuerier::new(&[]),
+                retired: HashMap::new(),
+            },
+            custom_query_type: PhantomData,
+        }
+    }
+
+    /// Report `retired` credits for `batch` from the stubbed ecocredit module.
+    pub fn set_retired(deps: &mut Deps, batch: &str, retired: &str) {
+        deps.querier
+            .retired
+            .insert(batch.to_string(), retired.to_string());
+    }
+
+    pub fn terms(env: &Env) -> SeriesTerms {
+        SeriesTerms {
+            borrower: BORROWER.to_string(),
+            collateral_denom: COLLATERAL.to_string(),
+            principal_denom: PRINCIPAL.to_string(),
+            principal_cap: Uint128::new(10_000),
+            maturity_ts: env.block.time.seconds() + 2 * YEAR,
+            base_rate_apr_bps: 1_000,
+            penalty_rate_apr_bps: 500,
+            coupon_period_seconds: None,
+            initial_collateral_ratio_bps: 25_000,
+            liquidation_ratio_bps: 15_000,
+            liquidation_bonus_bps: 500,
+            close_factor_bps: None,
+            token_mode: None,
+            default_grace_period_seconds: None,
+            coupon_grace_period_seconds: None,
+            call_schedule: None,
+            amortization: None,
+            oracle: BandPriceConfig {
+                band_ibc_channel: BAND_CHANNEL.to_string(),
+                regen_price_script_id: 1,
+                max_price_age_seconds: 3_600,
+            },
+            impact: ImpactConfig {
+                mode: ImpactMode::OnChainEcocreditBatches,
+                batch_ids: vec![],
+                band_impact_script_id: None,
+                checkpoints: vec![],
+            },
+        }
+    }
+
+    pub fn setup_with(terms: SeriesTerms, protocol_fee_bps: u32) -> Deps {
+        let mut deps = mock_deps();
+        let msg = InstantiateMsg {
+            terms,
+            admin: ADMIN.to_string(),
+            protocol_fee_bps,
+            fee_recipient: FEE_RECIPIENT.to_string(),
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+        deps
+    }
+
+    /// Default REGEN price: 0.25 principal units per uregen.
+    pub fn default_price() -> Decimal256 {
+        Decimal256::percent(25)
+    }
+
+    /// Collateral covering the default 10_000 cap at 25_000 bps and the default price.
+    pub const SALE_COLLATERAL: u128 = 100_000;
+
+    pub fn deposit(deps: &mut Deps, amount: u128) {
+        exec(
+            deps,
+            &mock_env(),
+            BORROWER,
+            &coins(amount, COLLATERAL),
+            ExecuteMsg::DepositCollateral {},
+        )
+        .unwrap();
+    }
+
+    /// Lock `collateral`, publish the default price and open the sale.
+    pub fn open_sale(deps: &mut Deps, collateral: u128) {
+        deposit(deps, collateral);
+        set_price(deps, &mock_env(), default_price());
+        exec(deps, &mock_env(), BORROWER, &[], ExecuteMsg::OpenSale {}).unwrap();
+    }
+
+    /// Series with default terms, no protocol fee, a fresh price and the sale already open.
+    pub fn setup() -> Deps {
+        let mut deps = setup_with(terms(&mock_env()), 0);
+        open_sale(&mut deps, SALE_COLLATERAL);
+        deps
+    }
+
+    /// 10_000 uregen against 1_000 principal: CR 25_000 bps at the default price.
+    pub fn position(close_factor_bps: Option<u32>) -> Deps {
+        let mut t = terms(&mock_env());
+        t.principal_cap = Uint128::new(1_000);
+        t.close_factor_bps = close_factor_bps;
+        let mut deps = setup_with(t, 0);
+        open_sale(&mut deps, 10_000);
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+        deps
+    }
+
+    pub fn env_at(offset: u64) -> Env {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(offset);
+        env
+    }
+
+    pub fn exec(
+        deps: &mut Deps,
+        env: &Env,
+        sender: &str,
+        funds: &[cosmwasm_std::Coin],
+        msg: ExecuteMsg,
+    ) -> Result<Response, ContractError> {
+        execute(deps.as_mut(), env.clone(), mock_info(sender, funds), msg)
+    }
+
+    pub fn buy(deps: &mut Deps, env: &Env, lender: &str, amount: u128) {
+        exec(
+            deps,
+            env,
+            lender,
+            &coins(amount, PRINCIPAL),
+            ExecuteMsg::Buy { min_tokens: None },
+        )
+        .unwrap();
+    }
+
+    pub fn query_as<T: serde::de::DeserializeOwned>(deps: &Deps, env: &Env, msg: QueryMsg) -> T {
+        from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap()
+    }
+
+    pub fn balance(deps: &Deps, who: &str) -> u128 {
+        let res: BalanceResponse = query_as(
+            deps,
+            &mock_env(),
+            QueryMsg::Balance {
+                address: who.to_string(),
+            },
+        );
+        res.balance.u128()
+    }
+
+    pub fn accrued(deps: &Deps, env: &Env, who: &str) -> u128 {
+        let res: AccruedInterestResponse = query_as(
+            deps,
+            env,
+            QueryMsg::AccruedInterest {
+                address: who.to_string(),
+            },
+        );
+        res.accrued.u128()
+    }
+
+    pub fn pending(deps: &Deps, env: &Env, who: &str) -> (u128, u128) {
+        let res: PendingRedemptionResponse = query_as(
+            deps,
+            env,
+            QueryMsg::PendingRedemption {
+                address: who.to_string(),
+            },
+        );
+        (res.redeemed.u128(), res.payout.u128())
+    }
+
+    pub fn state(deps: &Deps) -> StateResponse {
+        query_as(deps, &mock_env(), QueryMsg::State {})
+    }
+
+    pub fn repay(deps: &mut Deps, offset: u64, amount: u128) -> Response {
+        exec(
+            deps,
+            &env_at(offset),
+            BORROWER,
+            &coins(amount, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap()
+    }
+
+    pub fn attr(res: &Response, key: &str) -> String {
+        res.attributes
+            .iter()
+            .find(|a| a.key == key)
+            .unwrap_or_else(|| panic!("missing attribute {key}"))
+            .value
+            .clone()
+    }
+
+    /// Band response packet answering `req` with a single OBI-encoded rate.
+    pub fn band_response(req: &OracleRequestPacketData, rate: u64) -> OracleResponsePacketData {
+        let mut result = 1u32.to_be_bytes().to_vec();
+        result.extend_from_slice(&rate.to_be_bytes());
+        OracleResponsePacketData {
+            client_id: req.client_id.clone(),
+            request_id: 42u64.into(),
+            ans_count: 4u64.into(),
+            request_time: 0u64.into(),
+            resolve_time: 0u64.into(),
+            resolve_status: ResolveStatus::Success,
+            result: Binary::from(result),
+        }
+    }
+
+    /// Send `UpdateOraclePrice` and return the request packet it emitted.
+    pub fn request_price(deps: &mut Deps, env: &Env) -> OracleRequestPacketData {
+        let res = exec(deps, env, "keeper", &[], ExecuteMsg::UpdateOraclePrice {}).unwrap();
+        match &res.messages[0].msg {
+            CosmosMsg::Ibc(IbcMsg::SendPacket { data, .. }) => from_json(data).unwrap(),
+            other => panic!("unexpected message {other:?}"),
+        }
+    }
+
+    pub fn band_ack(request_id: u64) -> IbcAcknowledgement {
+        let inner = to_json_binary(&BandAcknowledgement {
+            request_id: request_id.into(),
+        })
+        .unwrap();
+        IbcAcknowledgement::new(to_json_binary(&AcknowledgementMsg::Result(inner)).unwrap())
+    }
+
+    /// Acknowledge `req` the way BandChain does, assigning it request id 42.
+    pub fn acknowledge(deps: &mut Deps, req: &OracleRequestPacketData) {
+        let ack = mock_ibc_packet_ack(BAND_CHANNEL, req, band_ack(42)).unwrap();
+        ibc_packet_ack(deps.as_mut(), mock_env(), ack).unwrap();
+    }
+
+    /// Deliver a packet from the mocked BandChain counterparty.
+    pub fn deliver(
+        deps: &mut Deps,
+        env: &Env,
+        resp: &OracleResponsePacketData,
+    ) -> IbcReceiveResponse {
+        let msg = mock_ibc_packet_recv(BAND_CHANNEL, resp).unwrap();
+        ibc_packet_receive(deps.as_mut(), env.clone(), msg).unwrap()
+    }
+
+    /// Run a full request/response round trip; `price` is principal units per uregen.
+    pub fn set_price(deps: &mut Deps, env: &Env, price: Decimal256) {
+        let req = request_price(deps, env);
+        acknowledge(deps, &req);
+        let rate = (price * Decimal256::from_ratio(PRICE_MULTIPLIER, 1u64)).to_uint_floor();
+        let mut resp = band_response(&req, rate.to_string().parse().unwrap());
+        resp.resolve_time = env.block.time.seconds().into();
+        let res = deliver(deps, env, &resp);
+        assert_eq!(
+            from_json::<AcknowledgementMsg>(&res.acknowledgement).unwrap(),
+            AcknowledgementMsg::Result(Binary::from(vec![1]))
+        );
+    }
+}
+
+mod accrual {
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{coins, BankMsg, Coin, CosmosMsg, Decimal256, Uint128};
+
+    use super::helpers::*;
+    use crate::msg::ExecuteMsg;
+    use crate::state::ACCOUNTS;
+
+    #[test]
+    fn scenario_f_lender_accrues_ten_percent() {
+        let mut deps = setup();
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+
+        // query projects the index to block time without persisting it
+        assert_eq!(accrued(&deps, &env_at(YEAR), LENDER), 100);
+        assert_eq!(
+            ACCOUNTS.load(&deps.storage, LENDER).unwrap().accrued.u128(),
+            0
+        );
+
+        // the borrower funds the year's interest
+        exec(
+            &mut deps,
+            &env_at(YEAR),
+            BORROWER,
+            &coins(100, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap();
+        let res = exec(
+            &mut deps,
+            &env_at(YEAR),
+            LENDER,
+            &[],
+            ExecuteMsg::ClaimInterest {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: LENDER.to_string(),
+                amount: vec![Coin::new(100, PRINCIPAL)],
+            })
+        );
+        let acc = ACCOUNTS.load(&deps.storage, LENDER).unwrap();
+        assert_eq!(acc.accrued.u128(), 0);
+        assert_eq!(acc.index, Decimal256::percent(110));
+    }
+
+    #[test]
+    fn buy_settles_interest_before_minting() {
+        let mut deps = setup();
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+        set_price(&mut deps, &env_at(YEAR), default_price());
+        buy(&mut deps, &env_at(YEAR), LENDER, 1_000);
+
+        // the second purchase must not retroactively earn the first year's interest
+        assert_eq!(
+            ACCOUNTS.load(&deps.storage, LENDER).unwrap().accrued.u128(),
+            100
+        );
+        assert_eq!(accrued(&deps, &env_at(YEAR), LENDER), 100);
+        assert_eq!(balance(&deps, LENDER), 2000);
+    }
+
+    #[test]
+    fn transfer_settles_sender_and_recipient() {
+        let mut deps = setup();
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+        buy(&mut deps, &mock_env(), LENDER2, 1_000);
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: LENDER2.to_string(),
+            amount: Uint128::new(500),
+        };
+        exec(&mut deps, &env_at(YEAR), LENDER, &[], msg).unwrap();
+
+        assert_eq!(
+            ACCOUNTS.load(&deps.storage, LENDER).unwrap().accrued.u128(),
+            100
+        );
+        assert_eq!(
+            ACCOUNTS
+                .load(&deps.storage, LENDER2)
+                .unwrap()
+                .accrued
+                .u128(),
+            100
+        );
+        assert_eq!(balance(&deps, LENDER), 500);
+        assert_eq!(balance(&deps, LENDER2), 1500);
+
+        // from here on interest follows the new balances
+        let a1 = accrued(&deps, &env_at(2 * YEAR - 1), LENDER);
+        let a2 = accrued(&deps, &env_at(2 * YEAR - 1), LENDER2);
+        assert!(a2 - 100 > 2 * (a1 - 100));
+    }
+
+    #[test]
+    fn transfer_to_fresh_account_starts_at_current_index() {
+        let mut deps = setup();
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: LENDER2.to_string(),
+            amount: Uint128::new(1_000),
+        };
+        exec(&mut deps, &env_at(YEAR), LENDER, &[], msg).unwrap();
+
+        assert_eq!(accrued(&deps, &env_at(YEAR), LENDER2), 0);
+        assert_eq!(accrued(&deps, &env_at(YEAR), LENDER), 100);
+    }
+
+    #[test]
+    fn self_transfer_does_not_mint() {
+        let mut deps = setup();
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: LENDER.to_string(),
+            amount: Uint128::new(400),
+        };
+        exec(&mut deps, &env_at(YEAR), LENDER, &[], msg).unwrap();
+
+        assert_eq!(balance(&deps, LENDER), 1000);
+        assert_eq!(
+            ACCOUNTS.load(&deps.storage, LENDER).unwrap().accrued.u128(),
+            100
+        );
+    }
+
+    #[test]
+    fn redeem_settles_interest_before_burning() {
+        let mut deps = setup();
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+        let repay = ExecuteMsg::Repay {};
+        exec(
+            &mut deps,
+            &env_at(2 * YEAR),
+            BORROWER,
+            &coins(1_200, PRINCIPAL),
+            repay,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::RedeemAtMaturity {
+            amount: Uint128::new(1_000),
+        };
+        exec(&mut deps, &env_at(2 * YEAR), LENDER, &[], msg).unwrap();
+
+        let acc = ACCOUNTS.load(&deps.storage, LENDER).unwrap();
+        assert_eq!(acc.balance.u128(), 0);
+        // a single two-year accrual step is simple interest: 20% of 1000
+        assert_eq!(acc.accrued.u128(), 200);
+        // nothing further accrues on a zero balance
+        assert_eq!(accrued(&deps, &env_at(3 * YEAR), LENDER), 200);
+    }
+
+    #[test]
+    fn unknown_account_has_no_interest() {
+        let deps = setup();
+        assert_eq!(accrued(&deps, &env_at(YEAR), "nobody"), 0);
+    }
+}
+
+mod migration {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coins, BankMsg, CosmosMsg, Decimal256, Timestamp, Uint128};
+    use heb_types::{BandPriceConfig, ImpactMode};
+
+    use crate::contract::{execute, migrate};
+    use crate::error::ContractError;
+    use crate::migrations::v0_1::*;
+    use crate::msg::{ExecuteMsg, MigrateMsg};
+    use crate::state::{ACCOUNTS, CONFIG, STATE};
+
+    fn legacy_config(principal_cap: &str) -> ConfigV0_1 {
+        ConfigV0_1 {
+            admin: "admin".to_string(),
+            protocol_fee_bps: 50,
+            fee_recipient: "fees".to_string(),
+            terms: SeriesTermsV0_1 {
+                borrower: "borrower".to_string(),
+                collateral_denom: "uregen".to_string(),
+                principal_denom: "ibc/USDC".to_string(),
+                principal_cap: principal_cap.to_string(),
+                maturity_ts: 2_000_000_000,
+                base_rate_apr_bps: 1_000,
+                penalty_rate_apr_bps: 500,
+                coupon_period_seconds: None,
+                initial_collateral_ratio_bps: 25_000,
+                liquidation_ratio_bps: 15_000,
+                liquidation_bonus_bps: 500,
+                oracle: BandPriceConfig {
+                    band_ibc_channel: "channel-0".to_string(),
+                    regen_price_script_id: 1,
+                    max_price_age_seconds: 3_600,
+                },
+                impact: ImpactConfigV0_1 {
+                    mode: ImpactMode::OnChainEcocreditBatches,
+                    batch_ids: vec!["C01-001".to_string()],
+                    band_impact_script_id: None,
+                    checkpoints: vec![ImpactCheckpointV0_1 {
+                        ts: 1_800_000_000,
+                        target_retired: "500".to_string(),
+                    }],
+                },
+            },
+        }
+    }
+
+    fn legacy_state() -> SeriesStateV0_1 {
+        SeriesStateV0_1 {
+            sale_open: true,
+            paused: false,
+            total_principal_sold: "3000".to_string(),
+            total_principal_outstanding: "2500".to_string(),
+            collateral_locked: "1000000".to_string(),
+            global_interest_index: "1".to_string(),
+            last_accrual_ts: 1_700_000_000,
+            last_price: Some(PricePointV0_1 {
+                price: "0.25".to_string(),
+                ts: 1_700_000_000,
+            }),
+            last_impact: Some(ImpactPointV0_1 {
+                checkpoint_ts: 1_800_000_000,
+                retired_total: "0".to_string(),
+                target_retired: "500".to_string(),
+                met: false,
+            }),
+        }
+    }
+
+    #[test]
+    fn rewrites_v0_1_string_state() {
+        let mut deps = mock_dependencies();
+        cw2::set_contract_version(&mut deps.storage, "heb-bond-series", "0.1.0").unwrap();
+        CONFIG_V0_1
+            .save(&mut deps.storage, &legacy_config("10000"))
+            .unwrap();
+        STATE_V0_1.save(&mut deps.storage, &legacy_state()).unwrap();
+        let acc = AccountIndexV0_1 {
+            balance: "3000".to_string(),
+            index: "1".to_string(),
+            accrued: "7".to_string(),
+        };
+        ACCOUNTS_V0_1
+            .save(&mut deps.storage, "lender", &acc)
+            .unwrap();
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        let cfg = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(cfg.terms.principal_cap, Uint128::new(10_000));
+        assert_eq!(
+            cfg.terms.impact.checkpoints[0].target_retired,
+            Uint128::new(500)
+        );
+
+        let st = STATE.load(&deps.storage).unwrap();
+        assert_eq!(st.total_principal_sold, Uint128::new(3_000));
+        assert_eq!(st.total_principal_outstanding, Uint128::new(2_500));
+        assert_eq!(st.collateral_locked, Uint128::new(1_000_000));
+        assert_eq!(st.total_supply, Uint128::new(3_000));
+        assert_eq!(st.interest_accrued, Uint128::new(7));
+        assert_eq!(st.global_interest_index, Decimal256::one());
+        assert_eq!(st.last_price.unwrap().price, Decimal256::percent(25));
+        assert_eq!(st.last_impact.unwrap().target_retired, Uint128::new(500));
+
+        let acc = ACCOUNTS.load(&deps.storage, "lender").unwrap();
+        assert_eq!(acc.balance, Uint128::new(3_000));
+        assert_eq!(acc.index, Decimal256::one());
+        assert_eq!(acc.accrued, Uint128::new(7));
+
+        let version = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.version, "0.2.0");
+    }
+
+    #[test]
+    fn principal_repaid_under_v0_1_stays_redeemable() {
+        let mut deps = mock_dependencies();
+        cw2::set_contract_version(&mut deps.storage, "heb-bond-series", "0.1.0").unwrap();
+        CONFIG_V0_1
+            .save(&mut deps.storage, &legacy_config("10000"))
+            .unwrap();
+        // v0.1 repay cut the outstanding principal and kept the 2_000 repaid in the contract
+        let mut state = legacy_state();
+        state.sale_open = false;
+        state.total_principal_outstanding = "1000".to_string();
+        STATE_V0_1.save(&mut deps.storage, &state).unwrap();
+        let acc = AccountIndexV0_1 {
+            balance: "3000".to_string(),
+            index: "1".to_string(),
+            accrued: "0".to_string(),
+        };
+        ACCOUNTS_V0_1
+            .save(&mut deps.storage, "lender", &acc)
+            .unwrap();
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        let st = STATE.load(&deps.storage).unwrap();
+        assert_eq!(st.principal_reserve, Uint128::new(2_000));
+
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(2_000_000_001);
+        let msg = ExecuteMsg::RedeemAtMaturity {
+            amount: Uint128::new(2_000),
+        };
+        let res = execute(deps.as_mut(), env, mock_info("lender", &[]), msg).unwrap();
+        assert!(res.messages.iter().any(|m| matches!(
+            &m.msg,
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount })
+                if to_address == "lender" && amount == &coins(2_000, "ibc/USDC")
+        )));
+    }
+
+    #[test]
+    fn malformed_v0_1_amount_aborts_migration() {
+        let mut deps = mock_dependencies();
+        cw2::set_contract_version(&mut deps.storage, "heb-bond-series", "0.1.0").unwrap();
+        CONFIG_V0_1
+            .save(&mut deps.storage, &legacy_config("ten thousand"))
+            .unwrap();
+        STATE_V0_1.save(&mut deps.storage, &legacy_state()).unwrap();
+
+        assert!(migrate(deps.as_mut(), mock_env(), MigrateMsg {}).is_err());
+    }
+
+    #[test]
+    fn rejects_foreign_contract() {
+        let mut deps = mock_dependencies();
+        cw2::set_contract_version(&mut deps.storage, "heb-bond-factory", "0.1.0").unwrap();
+        assert!(migrate(deps.as_mut(), mock_env(), MigrateMsg {}).is_err());
+    }
+
+    #[test]
+    fn refuses_downgrade_and_reruns_nothing_on_the_same_version() {
+        let mut deps = mock_dependencies();
+        cw2::set_contract_version(&mut deps.storage, "heb-bond-series", "9.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::CannotDowngrade {
+                from: "9.0.0".to_string(),
+                to: "0.2.0".to_string()
+            }
+        );
+
+        // no v0.1 state to rewrite: the step must not run
+        cw2::set_contract_version(&mut deps.storage, "heb-bond-series", "0.2.0").unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    }
+}
+
+mod oracle {
+    use cosmwasm_std::testing::{
+        mock_env, mock_ibc_channel_connect_ack, mock_ibc_channel_open_init,
+        mock_ibc_channel_open_try, mock_ibc_packet_ack, mock_ibc_packet_timeout,
+    };
+    use cosmwasm_std::{
+        from_json, to_json_binary, Binary, CosmosMsg, Decimal256, IbcAcknowledgement, IbcMsg,
+        IbcOrder, IbcTimeout,
+    };
+
+    use super::helpers::*;
+    use crate::error::ContractError;
+    use crate::ibc::{ibc_channel_connect, ibc_channel_open, ibc_packet_ack, ibc_packet_timeout};
+    use crate::msg::{ExecuteMsg, PriceStatusResponse, QueryMsg};
+    use crate::oracle::{
+        decode_price_result, encode_price_calldata, AcknowledgementMsg, ResolveStatus,
+        BAND_IBC_VERSION, PRICE_MULTIPLIER, PRICE_REQUEST_TIMEOUT_SECONDS,
+    };
+    use crate::state::{PENDING_PRICE_REQUEST, STATE};
+
+    fn ack_of(res: &cosmwasm_std::IbcReceiveResponse) -> AcknowledgementMsg {
+        from_json(&res.acknowledgement).unwrap()
+    }
+
+    #[test]
+    fn channel_handshake_requires_band_version_and_unordered() {
+        let mut deps = setup();
+        let ok = mock_ibc_channel_open_init(BAND_CHANNEL, IbcOrder::Unordered, BAND_IBC_VERSION);
+        ibc_channel_open(deps.as_mut(), mock_env(), ok).unwrap();
+
+        let ordered = mock_ibc_channel_open_init(BAND_CHANNEL, IbcOrder::Ordered, BAND_IBC_VERSION);
+        assert!(matches!(
+            ibc_channel_open(deps.as_mut(), mock_env(), ordered),
+            Err(ContractError::InvalidIbcChannel(_))
+        ));
+        let wrong = mock_ibc_channel_open_try(BAND_CHANNEL, IbcOrder::Unordered, "ics20-1");
+        assert!(ibc_channel_open(deps.as_mut(), mock_env(), wrong).is_err());
+
+        let connect =
+            mock_ibc_channel_connect_ack(BAND_CHANNEL, IbcOrder::Unordered, BAND_IBC_VERSION);
+        ibc_channel_connect(deps.as_mut(), mock_env(), connect).unwrap();
+    }
+
+    #[test]
+    fn update_sends_request_packet_on_configured_channel() {
+        let mut deps = setup();
+        let env = mock_env();
+        let res = exec(
+            &mut deps,
+            &env,
+            "keeper",
+            &[],
+            ExecuteMsg::UpdateOraclePrice {},
+        )
+        .unwrap();
+
+        let CosmosMsg::Ibc(IbcMsg::SendPacket {
+            channel_id,
+            data,
+            timeout,
+        }) = &res.messages[0].msg
+        else {
+            panic!("expected SendPacket");
+        };
+        assert_eq!(channel_id, BAND_CHANNEL);
+        assert_eq!(
+            timeout,
+            &IbcTimeout::with_timestamp(env.block.time.plus_seconds(PRICE_REQUEST_TIMEOUT_SECONDS))
+        );
+        let req: crate::oracle::OracleRequestPacketData = from_json(data).unwrap();
+        assert_eq!(req.oracle_script_id.u64(), 1);
+        assert_eq!(
+            req.calldata,
+            encode_price_calldata("REGEN", PRICE_MULTIPLIER)
+        );
+
+        let pending = PENDING_PRICE_REQUEST.load(&deps.storage).unwrap();
+        assert_eq!(pending.client_id, req.client_id);
+        assert_eq!(pending.sequence, None);
+    }
+
+    #[test]
+    fn full_round_trip_stores_price() {
+        let mut deps = setup();
+        let env = env_at(60);
+        let req = request_price(&mut deps, &mock_env());
+
+        let ack = mock_ibc_packet_ack(BAND_CHANNEL, &req, band_ack(42)).unwrap();
+        ibc_packet_ack(deps.as_mut(), mock_env(), ack).unwrap();
+        let pending = PENDING_PRICE_REQUEST.load(&deps.storage).unwrap();
+        assert_eq!(pending.sequence, Some(29));
+        assert_eq!(pending.band_request_id, Some(42));
+
+        // 0.25 principal units per uregen, resolved on BandChain before it was relayed
+        let mut resp = band_response(&req, 250_000_000);
+        resp.resolve_time = (env.block.time.seconds() - 30).into();
+        let res = deliver(&mut deps, &env, &resp);
+        assert_eq!(
+            ack_of(&res),
+            AcknowledgementMsg::Result(Binary::from(vec![1]))
+        );
+
+        let price: PriceStatusResponse = query_as(&deps, &env, QueryMsg::PriceStatus {});
+        assert_eq!(price.price, Decimal256::percent(25));
+        assert_eq!(price.ts, env.block.time.seconds() - 30);
+        assert!(PENDING_PRICE_REQUEST
+            .may_load(&deps.storage)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn only_one_request_in_flight() {
+        let mut deps = setup();
+        request_price(&mut deps, &mock_env());
+        let err = exec(
+            &mut deps,
+            &env_at(10),
+            "keeper",
+            &[],
+            ExecuteMsg::UpdateOraclePrice {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::OracleRequestPending);
+
+        // once the packet timeout has passed a new request is allowed even without a callback
+        request_price(&mut deps, &env_at(PRICE_REQUEST_TIMEOUT_SECONDS));
+    }
+
+    #[test]
+    fn timeout_and_error_ack_release_the_slot() {
+        let mut deps = setup();
+        let req = request_price(&mut deps, &mock_env());
+        let timeout = mock_ibc_packet_timeout(BAND_CHANNEL, &req).unwrap();
+        ibc_packet_timeout(deps.as_mut(), mock_env(), timeout).unwrap();
+        assert!(PENDING_PRICE_REQUEST
+            .may_load(&deps.storage)
+            .unwrap()
+            .is_none());
+
+        let req = request_price(&mut deps, &mock_env());
+        let err_ack = IbcAcknowledgement::new(
+            to_json_binary(&AcknowledgementMsg::Error("out of gas".to_string())).unwrap(),
+        );
+        let ack = mock_ibc_packet_ack(BAND_CHANNEL, &req, err_ack).unwrap();
+        ibc_packet_ack(deps.as_mut(), mock_env(), ack).unwrap();
+        assert!(PENDING_PRICE_REQUEST
+            .may_load(&deps.storage)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn uncorrelated_responses_get_error_ack() {
+        let mut deps = setup();
+        let req = request_price(&mut deps, &mock_env());
+
+        // before the acknowledgement only the client id correlates
+        let mut early = band_response(&req, 1);
+        early.client_id = "someone-else".to_string();
+        assert!(matches!(
+            ack_of(&deliver(&mut deps, &mock_env(), &early)),
+            AcknowledgementMsg::Error(_)
+        ));
+        acknowledge(&mut deps, &req);
+
+        let mut wrong_client = band_response(&req, 1);
+        wrong_client.client_id = "someone-else".to_string();
+        assert!(matches!(
+            ack_of(&deliver(&mut deps, &mock_env(), &wrong_client)),
+            AcknowledgementMsg::Error(_)
+        ));
+
+        let mut wrong_request = band_response(&req, 1);
+        wrong_request.request_id = 7u64.into();
+        assert!(matches!(
+            ack_of(&deliver(&mut deps, &mock_env(), &wrong_request)),
+            AcknowledgementMsg::Error(_)
+        ));
+
+        // neither touched the in-flight request or the price
+        assert!(PENDING_PRICE_REQUEST
+            .may_load(&deps.storage)
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            STATE.load(&deps.storage).unwrap().last_price.unwrap().price,
+            default_price()
+        );
+    }
+
+    #[test]
+    fn responses_relayed_before_the_ack_match_on_client_id() {
+        let mut deps = setup();
+        let env = env_at(60);
+        let req = request_price(&mut deps, &env);
+
+        let mut resp = band_response(&req, 2 * PRICE_MULTIPLIER);
+        resp.resolve_time = (env.block.time.seconds() - 10).into();
+        assert_eq!(
+            ack_of(&deliver(&mut deps, &env, &resp)),
+            AcknowledgementMsg::Result(Binary::from(vec![1]))
+        );
+        let price = STATE.load(&deps.storage).unwrap().last_price.unwrap();
+        assert_eq!(price.price, Decimal256::from_ratio(2u64, 1u64));
+        assert_eq!(price.ts, env.block.time.seconds() - 10);
+        assert!(PENDING_PRICE_REQUEST
+            .may_load(&deps.storage)
+            .unwrap()
+            .is_none());
+
+        // the acknowledgement arriving afterwards is stale and leaves the price alone
+        let ack = mock_ibc_packet_ack(BAND_CHANNEL, &req, band_ack(42)).unwrap();
+        let res = ibc_packet_ack(deps.as_mut(), mock_env(), ack).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "stale"));
+        assert_eq!(
+            STATE.load(&deps.storage).unwrap().last_price.unwrap().price,
+            Decimal256::from_ratio(2u64, 1u64)
+        );
+    }
+
+    #[test]
+    fn callbacks_match_the_acknowledged_packet_sequence() {
+        let mut deps = setup();
+        let req = request_price(&mut deps, &mock_env());
+        acknowledge(&mut deps, &req);
+
+        // same client id, different packet: not ours
+        let mut timeout = mock_ibc_packet_timeout(BAND_CHANNEL, &req).unwrap();
+        timeout.packet.sequence += 1;
+        ibc_packet_timeout(deps.as_mut(), mock_env(), timeout).unwrap();
+        let pending = PENDING_PRICE_REQUEST.load(&deps.storage).unwrap();
+        assert_eq!(pending.band_request_id, Some(42));
+
+        let timeout = mock_ibc_packet_timeout(BAND_CHANNEL, &req).unwrap();
+        ibc_packet_timeout(deps.as_mut(), mock_env(), timeout).unwrap();
+        assert!(PENDING_PRICE_REQUEST
+            .may_load(&deps.storage)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn malformed_or_failed_results_get_error_ack() {
+        let mut deps = setup();
+        let req = request_price(&mut deps, &mock_env());
+        acknowledge(&mut deps, &req);
+        let mut resp = band_response(&req, 1);
+        resp.result = Binary::from(vec![0, 0, 0, 1, 0xff]);
+        assert!(matches!(
+            ack_of(&deliver(&mut deps, &mock_env(), &resp)),
+            AcknowledgementMsg::Error(_)
+        ));
+        assert_eq!(
+            STATE.load(&deps.storage).unwrap().last_price.unwrap().price,
+            default_price()
+        );
+        // the answered request no longer blocks a retry
+        let req = request_price(&mut deps, &mock_env());
+        acknowledge(&mut deps, &req);
+
+        let mut resp = band_response(&req, 1);
+        resp.resolve_status = ResolveStatus::Failure;
+        assert!(matches!(
+            ack_of(&deliver(&mut deps, &mock_env(), &resp)),
+            AcknowledgementMsg::Error(_)
+        ));
+        assert_eq!(
+            STATE.load(&deps.storage).unwrap().last_price.unwrap().price,
+            default_price()
+        );
+
+        // garbage that is not a response packet at all
+        let msg = cosmwasm_std::testing::mock_ibc_packet_recv(BAND_CHANNEL, &"garbage").unwrap();
+        let res = crate::ibc::ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(matches!(ack_of(&res), AcknowledgementMsg::Error(_)));
+    }
+
+    #[test]
+    fn rejects_packets_on_other_channels() {
+        let mut deps = setup();
+        let req = request_price(&mut deps, &mock_env());
+        let msg = cosmwasm_std::testing::mock_ibc_packet_recv("channel-9", &band_response(&req, 1))
+            .unwrap();
+        let res = crate::ibc::ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(matches!(ack_of(&res), AcknowledgementMsg::Error(_)));
+    }
+
+    #[test]
+    fn paused_series_does_not_request() {
+        let mut deps = setup();
+        exec(&mut deps, &mock_env(), ADMIN, &[], ExecuteMsg::Pause {}).unwrap();
+        let err = exec(
+            &mut deps,
+            &mock_env(),
+            "keeper",
+            &[],
+            ExecuteMsg::UpdateOraclePrice {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::Paused);
+    }
+
+    #[test]
+    fn decodes_obi_rates() {
+        let mut result = 1u32.to_be_bytes().to_vec();
+        result.extend_from_slice(&1_500_000_000u64.to_be_bytes());
+        assert_eq!(
+            decode_price_result(&result, PRICE_MULTIPLIER).unwrap(),
+            Decimal256::percent(150)
+        );
+        let mut two = 2u32.to_be_bytes().to_vec();
+        two.extend_from_slice(&[0; 16]);
+        assert!(decode_price_result(&two, PRICE_MULTIPLIER).is_err());
+        let mut zero = 1u32.to_be_bytes().to_vec();
+        zero.extend_from_slice(&0u64.to_be_bytes());
+        assert!(decode_price_result(&zero, PRICE_MULTIPLIER).is_err());
+        assert!(decode_price_result(&[], PRICE_MULTIPLIER).is_err());
+    }
+
+    #[test]
+    fn set_price_helper_round_trips() {
+        let mut deps = setup();
+        set_price(&mut deps, &mock_env(), Decimal256::permille(125));
+        let price: PriceStatusResponse = query_as(&deps, &mock_env(), QueryMsg::PriceStatus {});
+        assert_eq!(price.price, Decimal256::permille(125));
+    }
+}
+
+mod freshness {
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{coins, Decimal256, Uint128};
+
+    use super::helpers::*;
+    use crate::error::ContractError;
+    use crate::msg::{ExecuteMsg, PriceStatusResponse, QueryMsg, StateResponse};
+
+    const MAX_AGE: u64 = 3_600;
+
+    #[test]
+    fn open_sale_requires_a_price() {
+        let mut deps = setup_with(terms(&mock_env()), 0);
+        deposit(&mut deps, SALE_COLLATERAL);
+        let err = exec(
+            &mut deps,
+            &mock_env(),
+            BORROWER,
+            &[],
+            ExecuteMsg::OpenSale {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::OracleStale);
+
+        set_price(&mut deps, &mock_env(), default_price());
+        let err = exec(
+            &mut deps,
+            &env_at(MAX_AGE + 1),
+            BORROWER,
+            &[],
+            ExecuteMsg::OpenSale {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::OracleStale);
+        exec(
+            &mut deps,
+            &env_at(MAX_AGE),
+            BORROWER,
+            &[],
+            ExecuteMsg::OpenSale {},
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn buy_rejects_stale_price() {
+        let mut deps = setup();
+        let msg = ExecuteMsg::Buy { min_tokens: None };
+        let err = exec(
+            &mut deps,
+            &env_at(MAX_AGE + 1),
+            LENDER,
+            &coins(100, PRINCIPAL),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::OracleStale);
+
+        set_price(&mut deps, &env_at(MAX_AGE + 1), default_price());
+        exec(
+            &mut deps,
+            &env_at(MAX_AGE + 1),
+            LENDER,
+            &coins(100, PRINCIPAL),
+            msg,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn scenario_j_liquidate_rejects_stale_price() {
+        let mut deps = setup();
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+        let msg = ExecuteMsg::Liquidate {
+            max_repay: Uint128::new(100),
+        };
+        let err = exec(
+            &mut deps,
+            &env_at(MAX_AGE + 1),
+            "liquidator",
+            &coins(100, PRINCIPAL),
+            msg,
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::OracleStale);
+    }
+
+    #[test]
+    fn repay_and_redeem_ignore_the_oracle() {
+        let mut deps = setup();
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+
+        // long after the last price update
+        let late = env_at(3 * YEAR);
+        exec(
+            &mut deps,
+            &late,
+            BORROWER,
+            &coins(1_300, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap();
+        let msg = ExecuteMsg::RedeemAtMaturity {
+            amount: Uint128::new(1_000),
+        };
+        exec(&mut deps, &late, LENDER, &[], msg).unwrap();
+    }
+
+    #[test]
+    fn price_status_reports_freshness() {
+        let mut deps = setup_with(terms(&mock_env()), 0);
+        let status: PriceStatusResponse = query_as(&deps, &mock_env(), QueryMsg::PriceStatus {});
+        assert!(!status.is_fresh);
+        assert_eq!(status.age_seconds, None);
+
+        set_price(&mut deps, &mock_env(), Decimal256::percent(30));
+        let status: PriceStatusResponse =
+            query_as(&deps, &env_at(MAX_AGE), QueryMsg::PriceStatus {});
+        assert!(status.is_fresh);
+        assert_eq!(status.age_seconds, Some(MAX_AGE));
+
+        let status: PriceStatusResponse =
+            query_as(&deps, &env_at(MAX_AGE + 1), QueryMsg::PriceStatus {});
+        assert!(!status.is_fresh);
+        assert_eq!(status.age_seconds, Some(MAX_AGE + 1));
+
+        let state: StateResponse = query_as(&deps, &env_at(MAX_AGE + 1), QueryMsg::State {});
+        assert!(!state.last_price.unwrap().is_fresh);
+    }
+}
+
+mod liquidation {
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::{coins, BankMsg, CosmosMsg, Decimal256, Response, Uint128};
+
+    use super::helpers::*;
+    use crate::contract::instantiate;
+    use crate::error::ContractError;
+    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, StateResponse};
+
+    const LIQUIDATOR: &str = "liquidator";
+
+    fn liquidate(deps: &mut Deps, paid: u128, max_repay: u128) -> Result<Response, ContractError> {
+        exec(
+            deps,
+            &mock_env(),
+            LIQUIDATOR,
+            &coins(paid, PRINCIPAL),
+            ExecuteMsg::Liquidate {
+                max_repay: Uint128::new(max_repay),
+            },
+        )
+    }
+
+    fn sends(res: &Response) -> Vec<BankMsg> {
+        res.messages
+            .iter()
+            .map(|m| match &m.msg {
+                CosmosMsg::Bank(msg) => msg.clone(),
+                other => panic!("unexpected message {other:?}"),
+            })
+            .collect()
+    }
+
+    fn send(amount: u128, denom: &str) -> BankMsg {
+        BankMsg::Send {
+            to_address: LIQUIDATOR.to_string(),
+            amount: coins(amount, denom),
+        }
+    }
+
+    #[test]
+    fn healthy_position_is_not_liquidatable() {
+        let mut deps = position(None);
+        let err = liquidate(&mut deps, 500, 500).unwrap_err();
+        assert_eq!(err, ContractError::NotLiquidatable);
+
+        // exactly at the threshold is still healthy: V = 1_500, CR = 15_000
+        set_price(&mut deps, &mock_env(), Decimal256::percent(15));
+        let err = liquidate(&mut deps, 500, 500).unwrap_err();
+        assert_eq!(err, ContractError::NotLiquidatable);
+    }
+
+    #[test]
+    fn scenario_k_collateral_out_uses_price_and_bonus() {
+        let mut deps = position(None);
+        // V = 1_000, CR = 10_000 bps
+        set_price(&mut deps, &mock_env(), Decimal256::percent(10));
+
+        let res = liquidate(&mut deps, 500, 500).unwrap();
+        // 500 * 1.05 / 0.10
+        assert_eq!(sends(&res), vec![send(5_250, COLLATERAL)]);
+        assert_eq!(attr(&res, "price"), "0.1");
+        assert_eq!(attr(&res, "cr_before_bps"), "10000");
+        assert_eq!(attr(&res, "cr_after_bps"), "9500");
+        assert_eq!(attr(&res, "repaid"), "500");
+        assert_eq!(attr(&res, "collateral_out"), "5250");
+        assert_eq!(attr(&res, "bonus_paid"), "250");
+        assert_eq!(attr(&res, "refund"), "0");
+
+        let state: StateResponse = query_as(&deps, &mock_env(), QueryMsg::State {});
+        assert_eq!(state.total_principal_outstanding, Uint128::new(500));
+        assert_eq!(state.collateral_locked, Uint128::new(4_750));
+    }
+
+    #[test]
+    fn close_factor_limits_repayment_and_refunds_excess() {
+        let mut deps = position(None);
+        set_price(&mut deps, &mock_env(), Decimal256::percent(10));
+
+        // default close factor: at most half of the 1_000 debt per call
+        let res = liquidate(&mut deps, 1_000, 1_000).unwrap();
+        assert_eq!(
+            sends(&res),
+            vec![send(5_250, COLLATERAL), send(500, PRINCIPAL)]
+        );
+        assert_eq!(attr(&res, "close_factor_bps"), "5000");
+        assert_eq!(attr(&res, "refund"), "500");
+
+        // max_repay below the close factor also refunds the rest of the payment
+        let res = liquidate(&mut deps, 300, 100).unwrap();
+        assert_eq!(attr(&res, "repaid"), "100");
+        assert_eq!(attr(&res, "refund"), "200");
+    }
+
+    #[test]
+    fn full_close_factor_allows_repaying_all_debt() {
+        let mut deps = position(Some(10_000));
+        set_price(&mut deps, &mock_env(), Decimal256::percent(12));
+
+        let res = liquidate(&mut deps, 1_000, 1_000).unwrap();
+        // 1_000 * 1.05 / 0.12
+        assert_eq!(attr(&res, "collateral_out"), "8750");
+        let state: StateResponse = query_as(&deps, &mock_env(), QueryMsg::State {});
+        assert!(state.total_principal_outstanding.is_zero());
+        assert_eq!(state.collateral_locked, Uint128::new(1_250));
+
+        let err = liquidate(&mut deps, 100, 100).unwrap_err();
+        assert_eq!(err, ContractError::NotLiquidatable);
+    }
+
+    #[test]
+    fn collateral_out_is_capped_to_locked_collateral() {
+        let mut deps = position(Some(10_000));
+        // 1_000 repaid would need 21_000 uregen; only 10_000 are locked
+        set_price(&mut deps, &mock_env(), Decimal256::percent(5));
+
+        let res = liquidate(&mut deps, 1_000, 1_000).unwrap();
+        // 10_000 * 0.05 / 1.05 = 476.19, which buys 476 * 1.05 / 0.05 = 9_996.
+        assert_eq!(
+            sends(&res),
+            vec![send(9_996, COLLATERAL), send(524, PRINCIPAL)]
+        );
+        assert_eq!(attr(&res, "repaid"), "476");
+
+        let state: StateResponse = query_as(&deps, &mock_env(), QueryMsg::State {});
+        assert_eq!(state.total_principal_outstanding, Uint128::new(524));
+        assert_eq!(state.collateral_locked, Uint128::new(4));
+    }
+
+    #[test]
+    fn paused_series_rejects_liquidation() {
+        let mut deps = position(None);
+        set_price(&mut deps, &mock_env(), Decimal256::percent(10));
+        exec(&mut deps, &mock_env(), ADMIN, &[], ExecuteMsg::Pause {}).unwrap();
+        let err = liquidate(&mut deps, 500, 500).unwrap_err();
+        assert_eq!(err, ContractError::Paused);
+    }
+
+    #[test]
+    fn close_factor_must_be_a_valid_share() {
+        for cf in [0, 10_001] {
+            let mut t = terms(&mock_env());
+            t.close_factor_bps = Some(cf);
+            let msg = InstantiateMsg {
+                terms: t,
+                admin: ADMIN.to_string(),
+                protocol_fee_bps: 0,
+                fee_recipient: FEE_RECIPIENT.to_string(),
+            };
+            let mut deps = cosmwasm_std::testing::mock_dependencies();
+            let err =
+                instantiate(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidConfig(_)));
+        }
+    }
+}
+
+mod collateral_ratio {
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{coins, Decimal256, Uint128};
+
+    use super::helpers::*;
+    use crate::msg::{CollateralHealth, CollateralRatioResponse, ExecuteMsg, QueryMsg};
+
+    fn ratio(deps: &Deps, offset: u64) -> CollateralRatioResponse {
+        query_as(deps, &env_at(offset), QueryMsg::CollateralRatio {})
+    }
+
+    #[test]
+    fn without_price_only_amounts_are_reported() {
+        let mut deps = setup_with(terms(&mock_env()), 0);
+        exec(
+            &mut deps,
+            &mock_env(),
+            BORROWER,
+            &coins(10_000, COLLATERAL),
+            ExecuteMsg::DepositCollateral {},
+        )
+        .unwrap();
+
+        let res = ratio(&deps, 0);
+        assert_eq!(res.ratio_bps, None);
+        assert_eq!(res.health, None);
+        assert_eq!(res.collateral_locked, Uint128::new(10_000));
+        assert_eq!(res.collateral_value, None);
+        assert!(res.debt.is_zero());
+        assert_eq!(res.liquidation_price, None);
+    }
+
+    #[test]
+    fn healthy_position() {
+        let deps = position(None);
+        let res = ratio(&deps, 0);
+        assert_eq!(res.ratio_bps, Some(25_000));
+        assert_eq!(res.health, Some(CollateralHealth::Healthy));
+        assert_eq!(res.collateral_value, Some(Uint128::new(2_500)));
+        assert_eq!(res.debt, Uint128::new(1_000));
+        // 15_000 * 1_000 / (10_000 * 10_000)
+        assert_eq!(res.liquidation_price, Some(Decimal256::permille(150)));
+        assert!(res.max_liquidation_repay.is_zero());
+    }
+
+    #[test]
+    fn debt_includes_projected_interest() {
+        let deps = position(None);
+        // 10% APR on 1_000 for a year
+        let res = ratio(&deps, YEAR);
+        assert_eq!(res.principal_outstanding, Uint128::new(1_000));
+        assert_eq!(res.interest_accrued, Uint128::new(100));
+        assert_eq!(res.debt, Uint128::new(1_100));
+        // 2_500 * 10_000 / 1_100
+        assert_eq!(res.ratio_bps, Some(22_727));
+        assert_eq!(res.health, Some(CollateralHealth::AtRisk));
+    }
+
+    #[test]
+    fn liquidatable_position_reports_max_repay() {
+        let mut deps = position(None);
+        set_price(&mut deps, &mock_env(), Decimal256::percent(10));
+        let res = ratio(&deps, 0);
+        assert_eq!(res.ratio_bps, Some(10_000));
+        assert_eq!(res.health, Some(CollateralHealth::Liquidatable));
+        // close factor: half of the debt
+        assert_eq!(res.max_liquidation_repay, Uint128::new(500));
+
+        // a stale price blocks liquidation
+        let res = ratio(&deps, 3_601);
+        assert_eq!(res.health, Some(CollateralHealth::Liquidatable));
+        assert!(res.max_liquidation_repay.is_zero());
+    }
+
+    #[test]
+    fn max_repay_is_bounded_by_collateral() {
+        let mut deps = position(Some(10_000));
+        set_price(&mut deps, &mock_env(), Decimal256::percent(5));
+        let res = ratio(&deps, 0);
+        assert_eq!(res.max_liquidation_repay, Uint128::new(476));
+    }
+}
+
+mod impact {
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{Decimal256, Uint128};
+    use heb_types::{ImpactCheckpoint, ImpactMode, SeriesTerms};
+
+    use super::helpers::*;
+    use crate::ecocredit::{encode_supply_request, parse_credit_amount};
+    use crate::error::ContractError;
+    use crate::math::{accrue_index, accrued_interest};
+    use crate::msg::{AprReason, EffectiveAprResponse, ExecuteMsg, ImpactStatusResponse, QueryMsg};
+
+    const BATCH_A: &str = "C01-001-20200101-20201231-001";
+    const BATCH_B: &str = "C01-001-20210101-20211231-002";
+
+    /// Checkpoints at +1y (target 500 credits) and +1.5y (target 1_000 credits).
+    fn impact_terms() -> SeriesTerms {
+        let mut t = terms(&mock_env());
+        let now = mock_env().block.time.seconds();
+        t.impact.batch_ids = vec![BATCH_A.to_string(), BATCH_B.to_string()];
+        t.impact.checkpoints = vec![
+            ImpactCheckpoint {
+                ts: now + YEAR,
+                target_retired: Uint128::new(500_000_000),
+            },
+            ImpactCheckpoint {
+                ts: now + YEAR * 3 / 2,
+                target_retired: Uint128::new(1_000_000_000),
+            },
+        ];
+        t
+    }
+
+    fn checkpoint(deps: &mut Deps, offset: u64) -> Result<ImpactStatusResponse, ContractError> {
+        let env = env_at(offset);
+        exec(deps, &env, "keeper", &[], ExecuteMsg::CheckpointImpact {})?;
+        Ok(query_as(deps, &env, QueryMsg::ImpactStatus {}))
+    }
+
+    #[test]
+    fn supply_request_encoding() {
+        assert_eq!(
+            encode_supply_request("C01").as_slice(),
+            &[0x0a, 3, b'C', b'0', b'1']
+        );
+        // length varint spills into a second byte past 127
+        let long = "x".repeat(200);
+        assert_eq!(
+            &encode_supply_request(&long).as_slice()[..3],
+            &[0x0a, 0xc8, 0x01]
+        );
+    }
+
+    #[test]
+    fn credit_amounts_in_micro_units() {
+        assert_eq!(
+            parse_credit_amount("12.5").unwrap(),
+            Uint128::new(12_500_000)
+        );
+        assert_eq!(parse_credit_amount("0.0000019").unwrap(), Uint128::new(1));
+        assert_eq!(parse_credit_amount("").unwrap(), Uint128::zero());
+        assert!(parse_credit_amount("many").is_err());
+    }
+
+    #[test]
+    fn no_checkpoint_due_before_first_ts() {
+        let mut deps = setup_with(impact_terms(), 0);
+        let err = checkpoint(&mut deps, YEAR - 1).unwrap_err();
+        assert_eq!(err, ContractError::NoCheckpointDue);
+    }
+
+    #[test]
+    fn sums_retired_supply_across_batches() {
+        let mut deps = setup_with(impact_terms(), 0);
+        set_retired(&mut deps, BATCH_A, "0");
+        set_retired(&mut deps, BATCH_B, "0");
+
+        let status = checkpoint(&mut deps, YEAR).unwrap();
+        assert_eq!(status.checkpoint_ts, mock_env().block.time.seconds() + YEAR);
+        assert!(status.retired_total.is_zero());
+        assert_eq!(status.target_retired, Uint128::new(500_000_000));
+        assert!(!status.met);
+
+        set_retired(&mut deps, BATCH_A, "200.25");
+        set_retired(&mut deps, BATCH_B, "299.75");
+        let status = checkpoint(&mut deps, YEAR + 10).unwrap();
+        assert_eq!(status.retired_total, Uint128::new(500_000_000));
+        assert!(status.met);
+    }
+
+    #[test]
+    fn uses_latest_due_checkpoint() {
+        let mut deps = setup_with(impact_terms(), 0);
+        set_retired(&mut deps, BATCH_A, "600");
+        set_retired(&mut deps, BATCH_B, "0");
+
+        let status = checkpoint(&mut deps, 2 * YEAR).unwrap();
+        assert_eq!(
+            status.checkpoint_ts,
+            mock_env().block.time.seconds() + YEAR * 3 / 2
+        );
+        assert_eq!(status.target_retired, Uint128::new(1_000_000_000));
+        assert!(!status.met);
+    }
+
+    #[test]
+    fn unknown_batch_fails_the_checkpoint() {
+        let mut deps = setup_with(impact_terms(), 0);
+        set_retired(&mut deps, BATCH_A, "600");
+        assert!(checkpoint(&mut deps, YEAR).is_err());
+    }
+
+    #[test]
+    fn band_impact_mode_is_not_supported_yet() {
+        let mut t = impact_terms();
+        t.impact.mode = ImpactMode::BandOracleScript;
+        let mut deps = setup_with(t, 0);
+        let err = checkpoint(&mut deps, YEAR).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig(_)));
+    }
+
+    /// Impact series with the sale open and 1_000 sold to `LENDER` at `mock_env()`.
+    fn funded() -> Deps {
+        let mut deps = setup_with(impact_terms(), 0);
+        open_sale(&mut deps, SALE_COLLATERAL);
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+        set_retired(&mut deps, BATCH_A, "0");
+        set_retired(&mut deps, BATCH_B, "0");
+        deps
+    }
+
+    fn apr(deps: &Deps) -> EffectiveAprResponse {
+        query_as(deps, &mock_env(), QueryMsg::EffectiveApr {})
+    }
+
+    #[test]
+    fn scenario_l_missed_checkpoint_adds_penalty_apr() {
+        let mut deps = funded();
+        let res = apr(&deps);
+        assert_eq!(res.apr_bps, 1_000);
+        assert_eq!(res.reason, AprReason::NoCheckpoint);
+        assert!(!res.penalty_active);
+
+        // the keeper runs late: time before the run is still charged at base APR
+        let missed_at = YEAR + YEAR / 10;
+        checkpoint(&mut deps, missed_at).unwrap();
+        let index = accrue_index(Decimal256::one(), missed_at, 1_000).unwrap();
+        let expected = accrued_interest(Uint128::new(1_000), Decimal256::one(), index).unwrap();
+        assert_eq!(accrued(&deps, &env_at(missed_at), LENDER), expected.u128());
+
+        let res = apr(&deps);
+        assert_eq!(res.apr_bps, 1_500);
+        assert_eq!(res.reason, AprReason::ImpactMissed);
+        assert_eq!(
+            res.checkpoint_ts,
+            Some(mock_env().block.time.seconds() + YEAR)
+        );
+        assert!(res.penalty_active);
+
+        // penalty APR from the status change until the target is met
+        let met_at = YEAR + YEAR / 4;
+        set_retired(&mut deps, BATCH_A, "500");
+        let index = accrue_index(index, met_at - missed_at, 1_500).unwrap();
+        checkpoint(&mut deps, met_at).unwrap();
+        let expected = accrued_interest(Uint128::new(1_000), Decimal256::one(), index).unwrap();
+        assert_eq!(accrued(&deps, &env_at(met_at), LENDER), expected.u128());
+
+        let res = apr(&deps);
+        assert_eq!(res.apr_bps, 1_000);
+        assert_eq!(res.reason, AprReason::ImpactMet);
+        assert!(!res.penalty_active);
+
+        // back to base APR afterwards
+        let later = met_at + YEAR / 10;
+        let index = accrue_index(index, later - met_at, 1_000).unwrap();
+        let expected = accrued_interest(Uint128::new(1_000), Decimal256::one(), index).unwrap();
+        assert_eq!(accrued(&deps, &env_at(later), LENDER), expected.u128());
+    }
+
+    #[test]
+    fn penalty_applies_to_unaccrued_time_only_after_change() {
+        let mut deps = funded();
+        let base_only = accrued(&deps, &env_at(YEAR), LENDER);
+        checkpoint(&mut deps, YEAR).unwrap();
+        // the checkpoint itself does not move already-elapsed interest
+        assert_eq!(accrued(&deps, &env_at(YEAR), LENDER), base_only);
+        assert_eq!(base_only, 100);
+    }
+}
+
+mod sale {
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{coins, BankMsg, CosmosMsg, Response, Uint128};
+
+    use super::helpers::*;
+    use crate::error::ContractError;
+    use crate::msg::{BalanceResponse, ExecuteMsg, QueryMsg};
+
+    fn try_buy(
+        deps: &mut Deps,
+        amount: u128,
+        min_tokens: Option<u128>,
+    ) -> Result<Response, ContractError> {
+        exec(
+            deps,
+            &mock_env(),
+            LENDER2,
+            &coins(amount, PRINCIPAL),
+            ExecuteMsg::Buy {
+                min_tokens: min_tokens.map(Uint128::new),
+            },
+        )
+    }
+
+    #[test]
+    fn scenario_e_cap_enforcement() {
+        let mut deps = setup();
+        buy(&mut deps, &mock_env(), LENDER, 9_900);
+
+        let err = try_buy(&mut deps, 200, None).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::PrincipalCapExceeded {
+                remaining: Uint128::new(100)
+            }
+        );
+
+        let res = try_buy(&mut deps, 100, None).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "sale_closed" && a.value == "true"));
+        let st = state(&deps);
+        assert_eq!(st.total_principal_sold, Uint128::new(10_000));
+        assert!(!st.sale_open);
+
+        let err = try_buy(&mut deps, 1, None).unwrap_err();
+        assert_eq!(err, ContractError::SaleNotOpen);
+    }
+
+    #[test]
+    fn min_tokens_allows_partial_fill_with_refund() {
+        let mut deps = setup();
+        buy(&mut deps, &mock_env(), LENDER, 9_900);
+
+        let res = try_buy(&mut deps, 250, Some(100)).unwrap();
+        assert_eq!(
+            res.messages.last().unwrap().msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: LENDER2.to_string(),
+                amount: coins(150, PRINCIPAL),
+            })
+        );
+        let bal: BalanceResponse = query_as(
+            &deps,
+            &mock_env(),
+            QueryMsg::Balance {
+                address: LENDER2.to_string(),
+            },
+        );
+        assert_eq!(bal.balance, Uint128::new(100));
+        assert!(!state(&deps).sale_open);
+    }
+
+    #[test]
+    fn min_tokens_is_a_slippage_floor() {
+        let mut deps = setup();
+        buy(&mut deps, &mock_env(), LENDER, 9_900);
+
+        let err = try_buy(&mut deps, 250, Some(101)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MinTokensNotMet {
+                filled: Uint128::new(100),
+                min_tokens: Uint128::new(101)
+            }
+        );
+        // a full fill above the floor is unaffected
+        try_buy(&mut deps, 50, Some(50)).unwrap();
+        assert_eq!(state(&deps).total_principal_sold, Uint128::new(9_950));
+    }
+
+    #[test]
+    fn fee_is_taken_on_the_filled_amount() {
+        let mut deps = setup_with(terms(&mock_env()), 100);
+        open_sale(&mut deps, SALE_COLLATERAL);
+        buy(&mut deps, &mock_env(), LENDER, 9_000);
+
+        let res = try_buy(&mut deps, 2_000, Some(0)).unwrap();
+        let sends: Vec<_> = res.messages.iter().map(|m| m.msg.clone()).collect();
+        assert_eq!(
+            sends,
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: FEE_RECIPIENT.to_string(),
+                    amount: coins(10, PRINCIPAL),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: BORROWER.to_string(),
+                    amount: coins(990, PRINCIPAL),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: LENDER2.to_string(),
+                    amount: coins(1_000, PRINCIPAL),
+                }),
+            ]
+        );
+    }
+}
+
+mod sale_collateral {
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{Decimal256, Uint128};
+
+    use super::helpers::*;
+    use crate::error::ContractError;
+    use crate::msg::{ExecuteMsg, QueryMsg, SaleCollateralResponse, StateResponse};
+
+    fn sale_collateral(deps: &Deps) -> SaleCollateralResponse {
+        query_as(deps, &mock_env(), QueryMsg::SaleCollateral {})
+    }
+
+    fn try_open(deps: &mut Deps) -> Result<(), ContractError> {
+        exec(deps, &mock_env(), BORROWER, &[], ExecuteMsg::OpenSale {}).map(|_| ())
+    }
+
+    #[test]
+    fn scenario_c_open_sale_requires_collateral() {
+        let mut deps = setup_with(terms(&mock_env()), 0);
+        set_price(&mut deps, &mock_env(), default_price());
+        deposit(&mut deps, SALE_COLLATERAL - 1);
+
+        let err = try_open(&mut deps).unwrap_err();
+        assert_eq!(err, ContractError::CollateralTooLow);
+
+        deposit(&mut deps, 1);
+        try_open(&mut deps).unwrap();
+        let st: StateResponse = query_as(&deps, &mock_env(), QueryMsg::State {});
+        assert!(st.sale_open);
+    }
+
+    #[test]
+    fn query_reports_shortfall() {
+        let mut deps = setup_with(terms(&mock_env()), 0);
+        let res = sale_collateral(&deps);
+        assert_eq!(res.required, None);
+        assert_eq!(res.shortfall, None);
+
+        set_price(&mut deps, &mock_env(), default_price());
+        deposit(&mut deps, 40_000);
+        let res = sale_collateral(&deps);
+        // 10_000 * 2.5 / 0.25
+        assert_eq!(res.required, Some(Uint128::new(SALE_COLLATERAL)));
+        assert_eq!(res.shortfall, Some(Uint128::new(60_000)));
+        assert_eq!(res.collateral_locked, Uint128::new(40_000));
+
+        deposit(&mut deps, 70_000);
+        assert_eq!(sale_collateral(&deps).shortfall, Some(Uint128::zero()));
+    }
+
+    #[test]
+    fn requirement_follows_the_price_and_rounds_up() {
+        let mut deps = setup_with(terms(&mock_env()), 0);
+        // 25_000 / 0.3 = 83_333.33
+        set_price(&mut deps, &mock_env(), Decimal256::percent(30));
+        assert_eq!(sale_collateral(&deps).required, Some(Uint128::new(83_334)));
+        deposit(&mut deps, 83_334);
+        try_open(&mut deps).unwrap();
+    }
+}
+
+mod repayment {
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{coins, BankMsg, CosmosMsg, Response, Uint128};
+
+    use super::helpers::*;
+    use crate::error::ContractError;
+    use crate::msg::ExecuteMsg;
+
+    fn claim(deps: &mut Deps, offset: u64, who: &str) -> Result<Response, ContractError> {
+        exec(
+            deps,
+            &env_at(offset),
+            who,
+            &[],
+            ExecuteMsg::ClaimInterest {},
+        )
+    }
+
+    #[test]
+    fn scenario_g_repay_reduces_outstanding() {
+        let mut deps = setup();
+        buy(&mut deps, &mock_env(), LENDER, 10_000);
+
+        repay(&mut deps, 0, 2_500);
+        assert_eq!(
+            state(&deps).total_principal_outstanding,
+            Uint128::new(7_500)
+        );
+
+        // clipped to outstanding; the excess goes back to the borrower
+        let res = repay(&mut deps, 0, 8_000);
+        assert_eq!(attr(&res, "principal_repaid"), "7500");
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: BORROWER.to_string(),
+                amount: coins(500, PRINCIPAL),
+            })
+        );
+        assert!(state(&deps).total_principal_outstanding.is_zero());
+    }
+
+    #[test]
+    fn repayment_funds_interest_before_principal() {
+        let mut deps = setup();
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+
+        // 100 interest due after a year
+        let res = repay(&mut deps, YEAR, 60);
+        assert_eq!(attr(&res, "interest_funded"), "60");
+        assert_eq!(attr(&res, "principal_repaid"), "0");
+
+        let res = repay(&mut deps, YEAR, 540);
+        assert_eq!(attr(&res, "interest_funded"), "40");
+        assert_eq!(attr(&res, "principal_repaid"), "500");
+
+        let st = state(&deps);
+        assert_eq!(st.interest_accrued, Uint128::new(100));
+        assert_eq!(st.interest_funded, Uint128::new(100));
+        assert_eq!(st.total_principal_outstanding, Uint128::new(500));
+    }
+
+    #[test]
+    fn scenario_h_claim_fails_without_funded_interest() {
+        let mut deps = setup();
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+        buy(&mut deps, &mock_env(), LENDER2, 1_000);
+
+        let err = claim(&mut deps, YEAR, LENDER).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InterestNotFunded {
+                funded: Uint128::zero(),
+                requested: Uint128::new(100)
+            }
+        );
+
+        // funding one lender's share lets exactly one claim through
+        repay(&mut deps, YEAR, 100);
+        claim(&mut deps, YEAR, LENDER).unwrap();
+        assert!(claim(&mut deps, YEAR, LENDER2).is_err());
+
+        repay(&mut deps, YEAR, 100);
+        claim(&mut deps, YEAR, LENDER2).unwrap();
+        let st = state(&deps);
+        assert!(st.interest_accrued.is_zero());
+        assert!(st.interest_funded.is_zero());
+    }
+
+    #[test]
+    fn redemption_pays_from_the_principal_reserve() {
+        let mut deps = position(None);
+        let maturity = 2 * YEAR;
+        let redeem = |deps: &mut Deps, amount: u128| {
+            let msg = ExecuteMsg::RedeemAtMaturity {
+                amount: Uint128::new(amount),
+            };
+            exec(deps, &env_at(maturity), LENDER, &[], msg)
+        };
+        let err = redeem(&mut deps, 1).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::PrincipalNotRepaid {
+                available: Uint128::zero(),
+                requested: Uint128::new(1),
+            }
+        );
+
+        // 200 funds interest, 500 goes to the reserve
+        repay(&mut deps, maturity, 700);
+        assert_eq!(state(&deps).principal_reserve, Uint128::new(500));
+        assert!(redeem(&mut deps, 600).is_err());
+        let res = redeem(&mut deps, 500).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: LENDER.to_string(),
+                amount: coins(500, PRINCIPAL),
+            })
+        );
+
+        repay(&mut deps, maturity, 500);
+        redeem(&mut deps, 500).unwrap();
+        let st = state(&deps);
+        assert!(st.principal_reserve.is_zero());
+        assert!(st.total_supply.is_zero());
+        assert!(st.total_principal_outstanding.is_zero());
+    }
+}
+
+mod withdrawal {
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{coins, BankMsg, CosmosMsg, Env, Response, Uint128};
+
+    use super::helpers::*;
+    use crate::error::ContractError;
+    use crate::msg::{ExecuteMsg, QueryMsg, StateResponse};
+
+    fn withdraw(
+        deps: &mut Deps,
+        env: &Env,
+        sender: &str,
+        amount: u128,
+    ) -> Result<Response, ContractError> {
+        exec(
+            deps,
+            env,
+            sender,
+            &[],
+            ExecuteMsg::WithdrawCollateral {
+                amount: Uint128::new(amount),
+            },
+        )
+    }
+
+    fn locked(deps: &Deps) -> Uint128 {
+        let st: StateResponse = query_as(deps, &mock_env(), QueryMsg::State {});
+        st.collateral_locked
+    }
+
+    #[test]
+    fn only_borrower_withdraws() {
+        let mut deps = position(None);
+        let err = withdraw(&mut deps, &mock_env(), LENDER, 1).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+        let err = withdraw(&mut deps, &mock_env(), BORROWER, 10_001).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientFunds);
+    }
+
+    #[test]
+    fn excess_collateral_can_be_withdrawn() {
+        // 10_000 uregen back 1_000 principal at exactly 25_000 bps
+        let mut deps = position(None);
+        deposit(&mut deps, 2_000);
+
+        let res = withdraw(&mut deps, &mock_env(), BORROWER, 2_000).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: BORROWER.to_string(),
+                amount: coins(2_000, COLLATERAL),
+            })
+        );
+        assert_eq!(locked(&deps), Uint128::new(10_000));
+
+        let err = withdraw(&mut deps, &mock_env(), BORROWER, 1).unwrap_err();
+        assert_eq!(err, ContractError::CollateralTooLow);
+    }
+
+    #[test]
+    fn open_sale_keeps_cap_collateralized() {
+        let mut deps = setup();
+        // nothing sold yet, but the sale can still mint the full cap
+        let err = withdraw(&mut deps, &mock_env(), BORROWER, 1).unwrap_err();
+        assert_eq!(err, ContractError::CollateralTooLow);
+    }
+
+    #[test]
+    fn health_check_needs_a_fresh_price() {
+        let mut deps = position(None);
+        deposit(&mut deps, 2_000);
+        let err = withdraw(&mut deps, &env_at(3_601), BORROWER, 1_000).unwrap_err();
+        assert_eq!(err, ContractError::OracleStale);
+    }
+
+    #[test]
+    fn everything_is_released_after_full_repayment_at_maturity() {
+        let mut deps = position(None);
+        let maturity = env_at(2 * YEAR);
+
+        // the price is long stale and the debt is still open
+        let err = withdraw(&mut deps, &maturity, BORROWER, 10_000).unwrap_err();
+        assert_eq!(err, ContractError::OracleStale);
+
+        // overpay; the excess is refunded
+        exec(
+            &mut deps,
+            &maturity,
+            BORROWER,
+            &coins(2_000, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap();
+        let res = withdraw(&mut deps, &maturity, BORROWER, 10_000).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "settled" && a.value == "true"));
+        assert!(locked(&deps).is_zero());
+    }
+}
+
+mod cw20_interface {
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{
+        coins, to_json_binary, BankMsg, Binary, CosmosMsg, Env, Response, Uint128, WasmMsg,
+    };
+    use cw20::{
+        AllAccountsResponse, AllAllowancesResponse, AllowanceResponse, Cw20ReceiveMsg, Expiration,
+        TokenInfoResponse,
+    };
+
+    use heb_types::SeriesStatus;
+
+    use super::helpers::*;
+    use crate::error::ContractError;
+    use crate::msg::{ExecuteMsg, QueryMsg};
+
+    const SPENDER: &str = "spender";
+    const DEX: &str = "dex";
+
+    fn run(
+        deps: &mut Deps,
+        env: &Env,
+        sender: &str,
+        msg: ExecuteMsg,
+    ) -> Result<Response, ContractError> {
+        exec(deps, env, sender, &[], msg)
+    }
+
+    fn allowance(deps: &Deps) -> AllowanceResponse {
+        query_as(
+            deps,
+            &mock_env(),
+            QueryMsg::Allowance {
+                owner: LENDER.to_string(),
+                spender: SPENDER.to_string(),
+            },
+        )
+    }
+
+    fn approve(deps: &mut Deps, amount: u128, expires: Option<Expiration>) {
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: SPENDER.to_string(),
+            amount: Uint128::new(amount),
+            expires,
+        };
+        run(deps, &mock_env(), LENDER, msg).unwrap();
+    }
+
+    fn funded() -> Deps {
+        let mut deps = setup();
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+        deps
+    }
+
+    #[test]
+    fn token_info_tracks_supply() {
+        let mut deps = funded();
+        let info: TokenInfoResponse = query_as(&deps, &mock_env(), QueryMsg::TokenInfo {});
+        assert_eq!(info.symbol, "HEB");
+        assert_eq!(info.decimals, 6);
+        assert_eq!(info.total_supply, Uint128::new(1_000));
+
+        let msg = ExecuteMsg::Burn {
+            amount: Uint128::new(400),
+        };
+        run(&mut deps, &mock_env(), LENDER, msg).unwrap();
+        let info: TokenInfoResponse = query_as(&deps, &mock_env(), QueryMsg::TokenInfo {});
+        assert_eq!(info.total_supply, Uint128::new(600));
+        assert_eq!(balance(&deps, LENDER), 600);
+    }
+
+    #[test]
+    fn send_calls_receiver_hook() {
+        let mut deps = funded();
+        let payload = Binary::from(b"{\"swap\":{}}".to_vec());
+        let msg = ExecuteMsg::Send {
+            contract: DEX.to_string(),
+            amount: Uint128::new(300),
+            msg: payload.clone(),
+        };
+        let res = run(&mut deps, &mock_env(), LENDER, msg).unwrap();
+
+        let hook = Cw20ReceiveMsg {
+            sender: LENDER.to_string(),
+            amount: Uint128::new(300),
+            msg: payload,
+        };
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: DEX.to_string(),
+                msg: hook.into_binary().unwrap(),
+                funds: vec![],
+            })
+        );
+        assert_eq!(balance(&deps, DEX), 300);
+        assert_eq!(balance(&deps, LENDER), 700);
+    }
+
+    #[test]
+    fn allowances_increase_decrease_and_expire() {
+        let mut deps = funded();
+        approve(&mut deps, 500, None);
+        approve(&mut deps, 100, Some(Expiration::AtHeight(20_000)));
+        assert_eq!(
+            allowance(&deps),
+            AllowanceResponse {
+                allowance: Uint128::new(600),
+                expires: Expiration::AtHeight(20_000),
+            }
+        );
+
+        let msg = ExecuteMsg::DecreaseAllowance {
+            spender: SPENDER.to_string(),
+            amount: Uint128::new(250),
+            expires: None,
+        };
+        run(&mut deps, &mock_env(), LENDER, msg).unwrap();
+        assert_eq!(allowance(&deps).allowance, Uint128::new(350));
+
+        // decreasing below zero removes the allowance
+        let msg = ExecuteMsg::DecreaseAllowance {
+            spender: SPENDER.to_string(),
+            amount: Uint128::new(1_000),
+            expires: None,
+        };
+        run(&mut deps, &mock_env(), LENDER, msg).unwrap();
+        assert_eq!(allowance(&deps), AllowanceResponse::default());
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: LENDER.to_string(),
+            amount: Uint128::new(1),
+            expires: None,
+        };
+        let err = run(&mut deps, &mock_env(), LENDER, msg).unwrap_err();
+        assert_eq!(err, ContractError::CannotSetOwnAccount);
+    }
+
+    #[test]
+    fn transfer_from_spends_allowance() {
+        let mut deps = funded();
+        approve(&mut deps, 300, None);
+
+        let msg = ExecuteMsg::TransferFrom {
+            owner: LENDER.to_string(),
+            recipient: LENDER2.to_string(),
+            amount: Uint128::new(200),
+        };
+        run(&mut deps, &mock_env(), SPENDER, msg.clone()).unwrap();
+        assert_eq!(balance(&deps, LENDER2), 200);
+        assert_eq!(allowance(&deps).allowance, Uint128::new(100));
+
+        let err = run(&mut deps, &mock_env(), SPENDER, msg).unwrap_err();
+        assert_eq!(err, ContractError::NoAllowance);
+
+        let msg = ExecuteMsg::TransferFrom {
+            owner: LENDER.to_string(),
+            recipient: LENDER2.to_string(),
+            amount: Uint128::new(1),
+        };
+        let err = run(&mut deps, &mock_env(), "stranger", msg).unwrap_err();
+        assert_eq!(err, ContractError::NoAllowance);
+    }
+
+    #[test]
+    fn expired_allowance_is_rejected() {
+        let mut deps = funded();
+        let height = mock_env().block.height;
+        approve(&mut deps, 300, Some(Expiration::AtHeight(height + 1)));
+
+        let mut later = mock_env();
+        later.block.height += 1;
+        let msg = ExecuteMsg::TransferFrom {
+            owner: LENDER.to_string(),
+            recipient: LENDER2.to_string(),
+            amount: Uint128::new(1),
+        };
+        let err = run(&mut deps, &later, SPENDER, msg).unwrap_err();
+        assert_eq!(err, ContractError::AllowanceExpired);
+    }
+
+    #[test]
+    fn send_from_and_burn_from() {
+        let mut deps = funded();
+        approve(&mut deps, 500, None);
+
+        let msg = ExecuteMsg::SendFrom {
+            owner: LENDER.to_string(),
+            contract: DEX.to_string(),
+            amount: Uint128::new(200),
+            msg: to_json_binary(&"hook").unwrap(),
+        };
+        let res = run(&mut deps, &mock_env(), SPENDER, msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(balance(&deps, DEX), 200);
+
+        let msg = ExecuteMsg::BurnFrom {
+            owner: LENDER.to_string(),
+            amount: Uint128::new(300),
+        };
+        run(&mut deps, &mock_env(), SPENDER, msg).unwrap();
+        assert_eq!(balance(&deps, LENDER), 500);
+        assert!(allowance(&deps).allowance.is_zero());
+        let info: TokenInfoResponse = query_as(&deps, &mock_env(), QueryMsg::TokenInfo {});
+        assert_eq!(info.total_supply, Uint128::new(700));
+    }
+
+    #[test]
+    fn delegated_movements_settle_interest() {
+        let mut deps = funded();
+        approve(&mut deps, 1_000, None);
+
+        // a year at 10% on 1_000 belongs to the owner, not to the recipient
+        let env = env_at(YEAR);
+        let msg = ExecuteMsg::TransferFrom {
+            owner: LENDER.to_string(),
+            recipient: LENDER2.to_string(),
+            amount: Uint128::new(1_000),
+        };
+        run(&mut deps, &env, SPENDER, msg).unwrap();
+        assert_eq!(accrued(&deps, &env, LENDER), 100);
+        assert_eq!(accrued(&deps, &env, LENDER2), 0);
+
+        // burnt tokens stop earning, interest already earned stays
+        let msg = ExecuteMsg::Burn {
+            amount: Uint128::new(1_000),
+        };
+        run(&mut deps, &env, LENDER2, msg).unwrap();
+        assert_eq!(accrued(&deps, &env_at(2 * YEAR), LENDER2), 0);
+        assert_eq!(accrued(&deps, &env_at(2 * YEAR), LENDER), 100);
+    }
+
+    #[test]
+    fn burnt_principal_is_not_owed_and_the_series_settles() {
+        let mut deps = funded();
+        buy(&mut deps, &mock_env(), LENDER2, 500);
+
+        let burn = |amount| ExecuteMsg::Burn {
+            amount: Uint128::new(amount),
+        };
+        let res = run(&mut deps, &mock_env(), LENDER, burn(300)).unwrap();
+        assert!(res.messages.is_empty());
+        let st = state(&deps);
+        assert_eq!(st.total_supply, Uint128::new(1_200));
+        assert_eq!(st.total_principal_outstanding, Uint128::new(1_200));
+
+        // repaid in full at maturity; what is repaid for tokens burnt after that goes back
+        let res = repay(&mut deps, 2 * YEAR, 10_000);
+        assert_eq!(attr(&res, "principal_repaid"), "1200");
+        assert_eq!(attr(&res, "interest_funded"), "240");
+        let env = env_at(2 * YEAR);
+        let res = run(&mut deps, &env, LENDER2, burn(100)).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: BORROWER.to_string(),
+                amount: coins(100, PRINCIPAL),
+            })
+        );
+
+        for (who, amount) in [(LENDER, 700), (LENDER2, 400)] {
+            let msg = ExecuteMsg::RedeemAtMaturity {
+                amount: Uint128::new(amount),
+            };
+            run(&mut deps, &env, who, msg).unwrap();
+            run(&mut deps, &env, who, ExecuteMsg::ClaimInterest {}).unwrap();
+        }
+        let st = state(&deps);
+        assert_eq!(st.status, SeriesStatus::Settled);
+        assert!(st.total_supply.is_zero());
+        assert!(st.total_principal_outstanding.is_zero());
+        assert!(st.principal_reserve.is_zero());
+        assert!(st.interest_funded.is_zero());
+    }
+
+    #[test]
+    fn enumerates_accounts_and_allowances() {
+        let mut deps = funded();
+        buy(&mut deps, &mock_env(), LENDER2, 100);
+        approve(&mut deps, 10, None);
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: DEX.to_string(),
+            amount: Uint128::new(20),
+            expires: None,
+        };
+        run(&mut deps, &mock_env(), LENDER, msg).unwrap();
+
+        let res: AllAccountsResponse = query_as(
+            &deps,
+            &mock_env(),
+            QueryMsg::AllAccounts {
+                start_after: None,
+                limit: None,
+            },
+        );
+        assert_eq!(res.accounts, vec![LENDER.to_string(), LENDER2.to_string()]);
+        let res: AllAccountsResponse = query_as(
+            &deps,
+            &mock_env(),
+            QueryMsg::AllAccounts {
+                start_after: Some(LENDER.to_string()),
+                limit: Some(1),
+            },
+        );
+        assert_eq!(res.accounts, vec![LENDER2.to_string()]);
+
+        let res: AllAllowancesResponse = query_as(
+            &deps,
+            &mock_env(),
+            QueryMsg::AllAllowances {
+                owner: LENDER.to_string(),
+                start_after: None,
+                limit: None,
+            },
+        );
+        let spenders: Vec<_> = res.allowances.iter().map(|a| a.spender.as_str()).collect();
+        assert_eq!(spenders, vec![DEX, SPENDER]);
+    }
+}
+
+mod token_factory {
+    use std::collections::HashMap;
+
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{coins, BankMsg, Coin, CosmosMsg, Env, Response, Uint128};
+    use heb_types::{CallDate, TokenMode};
+
+    use super::helpers::*;
+    use crate::contract::sudo;
+    use crate::error::ContractError;
+    use crate::msg::{BalanceResponse, ExecuteMsg, QueryMsg, SudoMsg, TermsResponse};
+    use crate::tokenfactory::{MSG_BURN, MSG_CREATE_DENOM, MSG_MINT, MSG_SET_BEFORE_SEND_HOOK};
+
+    const MODULE: &str = "tokenfactory";
+    const HOLDER: &str = "holder";
+
+    fn series() -> String {
+        mock_env().contract.address.to_string()
+    }
+
+    fn denom() -> String {
+        format!("factory/{}/heb", series())
+    }
+
+    /// Length-delimited protobuf fields, in order.
+    fn fields(mut bytes: &[u8]) -> Vec<(u8, Vec<u8>)> {
+        let mut out = vec![];
+        while !bytes.is_empty() {
+            let tag = bytes[0] >> 3;
+            let (mut len, mut shift, mut i) = (0usize, 0, 1);
+            loop {
+                len |= ((bytes[i] & 0x7f) as usize) << shift;
+                shift += 7;
+                i += 1;
+                if bytes[i - 1] < 0x80 {
+                    break;
+                }
+            }
+            out.push((tag, bytes[i..i + len].to_vec()));
+            bytes = &bytes[i + len..];
+        }
+        out
+    }
+
+    fn text(bytes: &[u8]) -> String {
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    /// Decode `amount = 2` of a `MsgMint` / `MsgBurn`.
+    fn coin_of(value: &[u8]) -> Coin {
+        let coin = fields(&fields(value)[1].1);
+        Coin::new(text(&coin[1].1).parse().unwrap(), text(&coin[0].1))
+    }
+
+    /// Mocked TokenFactory + bank for the bond denom: executes the messages a response emits and
+    /// calls the series' before-send hook for every movement, as the chain would.
+    #[derive(Default)]
+    struct MockTokenFactory {
+        balances: HashMap<String, u128>,
+    }
+
+    impl MockTokenFactory {
+        fn balance(&self, who: &str) -> u128 {
+            self.balances.get(who).copied().unwrap_or_default()
+        }
+
+        fn supply(&self) -> u128 {
+            self.balances
+                .iter()
+                .filter(|(who, _)| who.as_str() != MODULE)
+                .map(|(_, b)| b)
+                .sum()
+        }
+
+        fn send(
+            &mut self,
+            deps: &mut Deps,
+            env: &Env,
+            from: &str,
+            to: &str,
+            amount: u128,
+        ) -> Result<(), ContractError> {
+            let msg = SudoMsg::BlockBeforeSend {
+                from: from.to_string(),
+                to: to.to_string(),
+                amount: Coin::new(amount, denom()),
+            };
+            sudo(deps.as_mut(), env.clone(), msg)?;
+            if from != MODULE {
+                let bal = self.balances.entry(from.to_string()).or_default();
+                assert!(*bal >= amount, "bank balance of {from} too low");
+                *bal -= amount;
+            }
+            *self.balances.entry(to.to_string()).or_default() += amount;
+            Ok(())
+        }
+
+        fn apply(&mut self, deps: &mut Deps, env: &Env, res: &Response) {
+            for sub in &res.messages {
+                match &sub.msg {
+                    CosmosMsg::Stargate { type_url, value } if type_url == MSG_MINT => {
+                        assert_eq!(text(&fields(value)[0].1), series());
+                        let coin = coin_of(value);
+                        assert_eq!(coin.denom, denom());
+                        self.send(deps, env, MODULE, &series(), coin.amount.u128())
+                            .unwrap();
+                    }
+                    CosmosMsg::Stargate { type_url, value } if type_url == MSG_BURN => {
+                        let coin = coin_of(value);
+                        self.send(deps, env, &series(), MODULE, coin.amount.u128())
+                            .unwrap();
+                        *self.balances.get_mut(MODULE).unwrap() -= coin.amount.u128();
+                    }
+                    CosmosMsg::Bank(BankMsg::Send { to_address, amount })
+                        if amount[0].denom == denom() =>
+                    {
+                        self.send(deps, env, &series(), to_address, amount[0].amount.u128())
+                            .unwrap();
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        /// Execute `msg` with `bond_tokens` attached, moving them into the series first.
+        fn exec(
+            &mut self,
+            deps: &mut Deps,
+            env: &Env,
+            sender: &str,
+            funds: &[Coin],
+            msg: ExecuteMsg,
+        ) -> Result<Response, ContractError> {
+            for coin in funds.iter().filter(|c| c.denom == denom()) {
+                self.send(deps, env, sender, &series(), coin.amount.u128())?;
+            }
+            let res = exec(deps, env, sender, funds, msg)?;
+            self.apply(deps, env, &res);
+            Ok(res)
+        }
+    }
+
+    fn tf_setup() -> (Deps, MockTokenFactory) {
+        let mut t = terms(&mock_env());
+        t.token_mode = Some(TokenMode::TokenFactory);
+        let mut deps = setup_with(t, 0);
+        open_sale(&mut deps, SALE_COLLATERAL);
+        let mut tf = MockTokenFactory::default();
+        let msg = ExecuteMsg::Buy { min_tokens: None };
+        tf.exec(
+            &mut deps,
+            &mock_env(),
+            LENDER,
+            &coins(1_000, PRINCIPAL),
+            msg,
+        )
+        .unwrap();
+        (deps, tf)
+    }
+
+    fn tracked(deps: &Deps, who: &str) -> u128 {
+        let res: BalanceResponse = query_as(
+            deps,
+            &mock_env(),
+            QueryMsg::Balance {
+                address: who.to_string(),
+            },
+        );
+        res.balance.u128()
+    }
+
+    #[test]
+    fn instantiate_creates_denom_and_registers_hook() {
+        let mut t = terms(&mock_env());
+        t.token_mode = Some(TokenMode::TokenFactory);
+        let mut deps = mock_deps();
+        let msg = crate::msg::InstantiateMsg {
+            terms: t,
+            admin: ADMIN.to_string(),
+            protocol_fee_bps: 0,
+            fee_recipient: FEE_RECIPIENT.to_string(),
+        };
+        let res = crate::contract::instantiate(
+            deps.as_mut(),
+            mock_env(),
+            cosmwasm_std::testing::mock_info(ADMIN, &[]),
+            msg,
+        )
+        .unwrap();
+
+        let stargate: Vec<_> = res
+            .messages
+            .iter()
+            .map(|m| match &m.msg {
+                CosmosMsg::Stargate { type_url, value } => (type_url.clone(), fields(value)),
+                other => panic!("unexpected message {other:?}"),
+            })
+            .collect();
+        assert_eq!(stargate[0].0, MSG_CREATE_DENOM);
+        assert_eq!(
+            stargate[0].1,
+            vec![(1, series().into_bytes()), (2, b"heb".to_vec())]
+        );
+        assert_eq!(stargate[1].0, MSG_SET_BEFORE_SEND_HOOK);
+        assert_eq!(
+            stargate[1].1,
+            vec![
+                (1, series().into_bytes()),
+                (2, denom().into_bytes()),
+                (3, series().into_bytes())
+            ]
+        );
+
+        let terms: TermsResponse = query_as(&deps, &mock_env(), QueryMsg::Terms {});
+        assert_eq!(terms.bond_denom, Some(denom()));
+    }
+
+    #[test]
+    fn cw20_mode_has_no_native_denom() {
+        let deps = setup();
+        let terms: TermsResponse = query_as(&deps, &mock_env(), QueryMsg::Terms {});
+        assert_eq!(terms.bond_denom, None);
+    }
+
+    #[test]
+    fn buy_mints_native_tokens_to_the_buyer() {
+        let (deps, tf) = tf_setup();
+        assert_eq!(tf.balance(LENDER), 1_000);
+        assert_eq!(tf.balance(&series()), 0);
+        assert_eq!(tracked(&deps, LENDER), 1_000);
+    }
+
+    #[test]
+    fn bank_sends_move_interest_accrual_with_the_tokens() {
+        let (mut deps, mut tf) = tf_setup();
+        tf.send(&mut deps, &env_at(YEAR / 2), LENDER, HOLDER, 500)
+            .unwrap();
+        assert_eq!(tracked(&deps, LENDER), 500);
+        assert_eq!(tracked(&deps, HOLDER), 500);
+
+        // 10% APR compounding at the transfer: 1_000 for half a year, then 500 each on an index of 1.05
+        let end = env_at(YEAR);
+        assert_eq!(accrued(&deps, &end, LENDER), 50 + 26);
+        assert_eq!(accrued(&deps, &end, HOLDER), 26);
+    }
+
+    #[test]
+    fn hook_blocks_sends_beyond_tracked_balance() {
+        let (mut deps, mut tf) = tf_setup();
+        let err = tf
+            .send(&mut deps, &mock_env(), LENDER, HOLDER, 1_001)
+            .unwrap_err();
+        assert_eq!(err, ContractError::InsufficientFunds);
+    }
+
+    #[test]
+    fn hook_rejects_other_denoms() {
+        let (mut deps, _) = tf_setup();
+        let msg = SudoMsg::BlockBeforeSend {
+            from: LENDER.to_string(),
+            to: HOLDER.to_string(),
+            amount: Coin::new(1, "uother"),
+        };
+        assert_eq!(
+            sudo(deps.as_mut(), mock_env(), msg).unwrap_err(),
+            ContractError::InvalidDenom
+        );
+    }
+
+    #[test]
+    fn track_hook_books_nothing() {
+        let (mut deps, _) = tf_setup();
+        let msg = SudoMsg::TrackBeforeSend {
+            from: LENDER.to_string(),
+            to: HOLDER.to_string(),
+            amount: Coin::new(100, denom()),
+        };
+        sudo(deps.as_mut(), mock_env(), msg).unwrap();
+        assert_eq!(tracked(&deps, LENDER), 1_000);
+    }
+
+    #[test]
+    fn cw20_messages_are_disabled() {
+        let (mut deps, _) = tf_setup();
+        let msg = ExecuteMsg::Transfer {
+            recipient: HOLDER.to_string(),
+            amount: Uint128::new(1),
+        };
+        assert_eq!(
+            exec(&mut deps, &mock_env(), LENDER, &[], msg).unwrap_err(),
+            ContractError::NativeBondToken
+        );
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: HOLDER.to_string(),
+            amount: Uint128::new(1),
+            expires: None,
+        };
+        assert_eq!(
+            exec(&mut deps, &mock_env(), LENDER, &[], msg).unwrap_err(),
+            ContractError::NativeBondToken
+        );
+    }
+
+    #[test]
+    fn redeem_burns_attached_tokens() {
+        let (mut deps, mut tf) = tf_setup();
+        let env = env_at(3 * YEAR);
+        tf.send(&mut deps, &mock_env(), LENDER, HOLDER, 400)
+            .unwrap();
+        exec(
+            &mut deps,
+            &env,
+            BORROWER,
+            &coins(1_200, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::RedeemAtMaturity {
+            amount: Uint128::new(400),
+        };
+        let res = tf
+            .exec(&mut deps, &env, HOLDER, &coins(400, denom()), msg)
+            .unwrap();
+        assert!(res.messages.iter().any(|m| matches!(
+            &m.msg,
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount })
+                if to_address == HOLDER && amount == &coins(400, PRINCIPAL)
+        )));
+        assert_eq!(tf.balance(HOLDER), 0);
+        assert_eq!(tf.supply(), 600);
+        assert_eq!(tracked(&deps, HOLDER), 0);
+        assert_eq!(tracked(&deps, LENDER), 600);
+    }
+
+    #[test]
+    fn redeem_requires_the_tokens_as_funds() {
+        let (mut deps, mut tf) = tf_setup();
+        let env = env_at(3 * YEAR);
+        exec(
+            &mut deps,
+            &env,
+            BORROWER,
+            &coins(1_200, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap();
+        let msg = ExecuteMsg::RedeemAtMaturity {
+            amount: Uint128::new(400),
+        };
+        let err = exec(&mut deps, &env, LENDER, &[], msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Payment(_)));
+
+        let err = tf
+            .exec(&mut deps, &env, LENDER, &coins(300, denom()), msg)
+            .unwrap_err();
+        assert_eq!(err, ContractError::InsufficientFunds);
+    }
+
+    #[test]
+    fn called_tokens_go_back_with_the_claim() {
+        let mut t = terms(&mock_env());
+        t.token_mode = Some(TokenMode::TokenFactory);
+        t.call_schedule = Some(vec![CallDate {
+            ts: mock_env().block.time.seconds() + YEAR,
+            premium_bps: 0,
+        }]);
+        let mut deps = setup_with(t, 0);
+        open_sale(&mut deps, SALE_COLLATERAL);
+        let mut tf = MockTokenFactory::default();
+        let msg = ExecuteMsg::Buy { min_tokens: None };
+        tf.exec(
+            &mut deps,
+            &mock_env(),
+            LENDER,
+            &coins(10_000, PRINCIPAL),
+            msg,
+        )
+        .unwrap();
+
+        let env = env_at(YEAR);
+        let msg = ExecuteMsg::CallBonds {
+            amount: Uint128::new(4_000),
+        };
+        exec(&mut deps, &env, BORROWER, &coins(5_000, PRINCIPAL), msg).unwrap();
+        // the ledger balance drops at once, so only the uncalled tokens can move
+        assert_eq!(tracked(&deps, LENDER), 6_000);
+        let err = tf.send(&mut deps, &env, LENDER, HOLDER, 6_001).unwrap_err();
+        assert_eq!(err, ContractError::InsufficientFunds);
+
+        let err = exec(&mut deps, &env, LENDER, &[], ExecuteMsg::ClaimRedemption {}).unwrap_err();
+        assert!(matches!(err, ContractError::Payment(_)));
+        let res = tf
+            .exec(
+                &mut deps,
+                &env,
+                LENDER,
+                &coins(4_000, denom()),
+                ExecuteMsg::ClaimRedemption {},
+            )
+            .unwrap();
+        assert!(res.messages.iter().any(|m| matches!(
+            &m.msg,
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount })
+                if to_address == LENDER && amount == &coins(4_000, PRINCIPAL)
+        )));
+        assert_eq!(tf.balance(LENDER), 6_000);
+        assert_eq!(tf.supply(), 6_000);
+    }
+}
+
+mod lifecycle {
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{coins, Decimal256, Env, Response, Uint128};
+    use heb_types::SeriesStatus;
+
+    use super::helpers::*;
+    use crate::error::ContractError;
+    use crate::msg::{ExecuteMsg, QueryMsg, StateResponse};
+
+    fn status(deps: &Deps, env: &Env) -> SeriesStatus {
+        let st: StateResponse = query_as(deps, env, QueryMsg::State {});
+        st.status
+    }
+
+    /// `(from, to)` of every `series_status` event in `res`.
+    fn transitions(res: &Response) -> Vec<(String, String)> {
+        res.events
+            .iter()
+            .filter(|e| e.ty == "series_status")
+            .map(|e| (e.attributes[0].value.clone(), e.attributes[1].value.clone()))
+            .collect()
+    }
+
+    fn moved(from: &str, to: &str) -> Vec<(String, String)> {
+        vec![(from.to_string(), to.to_string())]
+    }
+
+    #[test]
+    fn transition_table() {
+        use SeriesStatus::*;
+        assert!(Created.can_transition_to(Collateralized));
+        assert!(SaleOpen.can_transition_to(Active));
+        assert!(Matured.can_transition_to(Settled));
+        assert!(!Active.can_transition_to(SaleOpen));
+        assert!(!Matured.can_transition_to(Active));
+        assert!(!Created.can_transition_to(SaleOpen));
+        let all = [
+            Created,
+            Collateralized,
+            SaleOpen,
+            SaleClosed,
+            Active,
+            Matured,
+            Defaulted,
+            Liquidating,
+            Settled,
+            Cancelled,
+        ];
+        for next in all {
+            assert!(!Settled.can_transition_to(next));
+            assert!(!Cancelled.can_transition_to(next));
+        }
+    }
+
+    #[test]
+    fn primary_issuance_path() {
+        let env = mock_env();
+        let mut deps = setup_with(terms(&env), 0);
+        assert_eq!(status(&deps, &env), SeriesStatus::Created);
+
+        let res = exec(
+            &mut deps,
+            &env,
+            BORROWER,
+            &coins(SALE_COLLATERAL, COLLATERAL),
+            ExecuteMsg::DepositCollateral {},
+        )
+        .unwrap();
+        assert_eq!(transitions(&res), moved("created", "collateralized"));
+
+        set_price(&mut deps, &env, default_price());
+        let res = exec(&mut deps, &env, BORROWER, &[], ExecuteMsg::OpenSale {}).unwrap();
+        assert_eq!(transitions(&res), moved("collateralized", "sale_open"));
+
+        let buy = |amount| ExecuteMsg::Buy {
+            min_tokens: Some(Uint128::new(amount)),
+        };
+        let res = exec(
+            &mut deps,
+            &env,
+            LENDER,
+            &coins(4_000, PRINCIPAL),
+            buy(4_000),
+        )
+        .unwrap();
+        assert!(transitions(&res).is_empty());
+        let res = exec(
+            &mut deps,
+            &env,
+            LENDER2,
+            &coins(6_000, PRINCIPAL),
+            buy(6_000),
+        )
+        .unwrap();
+        assert_eq!(transitions(&res), moved("sale_open", "active"));
+        assert_eq!(status(&deps, &env), SeriesStatus::Active);
+    }
+
+    #[test]
+    fn closing_an_empty_sale_allows_reopening_or_cancelling() {
+        let env = mock_env();
+        let mut deps = setup();
+        let res = exec(&mut deps, &env, BORROWER, &[], ExecuteMsg::CloseSale {}).unwrap();
+        assert_eq!(transitions(&res), moved("sale_open", "sale_closed"));
+        let err = exec(
+            &mut deps,
+            &env,
+            LENDER,
+            &coins(100, PRINCIPAL),
+            ExecuteMsg::Buy { min_tokens: None },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::SaleNotOpen);
+
+        let res = exec(&mut deps, &env, BORROWER, &[], ExecuteMsg::OpenSale {}).unwrap();
+        assert_eq!(transitions(&res), moved("sale_closed", "sale_open"));
+        exec(&mut deps, &env, BORROWER, &[], ExecuteMsg::CloseSale {}).unwrap();
+
+        let err = exec(&mut deps, &env, LENDER, &[], ExecuteMsg::Cancel {}).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized);
+        let res = exec(&mut deps, &env, ADMIN, &[], ExecuteMsg::Cancel {}).unwrap();
+        assert_eq!(transitions(&res), moved("sale_closed", "cancelled"));
+
+        // cancelled series release collateral without a price check
+        let later = env_at(7 * 24 * 3_600);
+        let msg = ExecuteMsg::WithdrawCollateral {
+            amount: Uint128::new(SALE_COLLATERAL),
+        };
+        exec(&mut deps, &later, BORROWER, &[], msg).unwrap();
+        let err = exec(&mut deps, &later, BORROWER, &[], ExecuteMsg::OpenSale {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidStatus {
+                status: SeriesStatus::Cancelled
+            }
+        );
+    }
+
+    #[test]
+    fn closing_a_sale_with_bonds_sold_activates_the_series() {
+        let env = mock_env();
+        let mut deps = setup();
+        buy(&mut deps, &env, LENDER, 1_000);
+        let res = exec(&mut deps, &env, BORROWER, &[], ExecuteMsg::CloseSale {}).unwrap();
+        assert_eq!(transitions(&res), moved("sale_open", "active"));
+
+        let err = exec(&mut deps, &env, BORROWER, &[], ExecuteMsg::Cancel {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidStatus {
+                status: SeriesStatus::Active
+            }
+        );
+        let err = exec(&mut deps, &env, BORROWER, &[], ExecuteMsg::OpenSale {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidStatus {
+                status: SeriesStatus::Active
+            }
+        );
+    }
+
+    #[test]
+    fn handlers_reject_statuses_outside_their_table() {
+        let env = mock_env();
+        let mut deps = setup_with(terms(&env), 0);
+        let err = exec(
+            &mut deps,
+            &env,
+            BORROWER,
+            &coins(100, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidStatus {
+                status: SeriesStatus::Created
+            }
+        );
+        let msg = ExecuteMsg::Liquidate {
+            max_repay: Uint128::new(100),
+        };
+        let err = exec(&mut deps, &env, LENDER, &coins(100, PRINCIPAL), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidStatus {
+                status: SeriesStatus::Created
+            }
+        );
+    }
+
+    #[test]
+    fn maturity_and_full_repayment_settle_the_series() {
+        let mut deps = position(None);
+        let maturity = env_at(2 * YEAR);
+        assert_eq!(status(&deps, &env_at(2 * YEAR - 1)), SeriesStatus::Active);
+        assert_eq!(status(&deps, &maturity), SeriesStatus::Matured);
+
+        // 1_000 at 10% for two years, compounded once a year at most; unpaid debt keeps accruing
+        let due = accrued(&deps, &maturity, LENDER);
+        assert_eq!(due, 200);
+        assert_eq!(accrued(&deps, &env_at(3 * YEAR), LENDER), 300);
+
+        let res = exec(
+            &mut deps,
+            &maturity,
+            BORROWER,
+            &coins(600, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap();
+        assert_eq!(transitions(&res), moved("active", "matured"));
+        let res = exec(
+            &mut deps,
+            &maturity,
+            BORROWER,
+            &coins(600, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap();
+        assert_eq!(transitions(&res), moved("matured", "settled"));
+        assert_eq!(status(&deps, &env_at(3 * YEAR)), SeriesStatus::Settled);
+        // interest stops once settled
+        assert_eq!(accrued(&deps, &env_at(3 * YEAR), LENDER), due);
+
+        let err = exec(
+            &mut deps,
+            &maturity,
+            BORROWER,
+            &coins(1, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidStatus {
+                status: SeriesStatus::Settled
+            }
+        );
+    }
+
+    #[test]
+    fn liquidation_holds_the_series_until_it_is_healthy_again() {
+        let env = mock_env();
+        let mut deps = position(None);
+        // V = 1_000, CR = 10_000: half the debt is repaid and CR is still below 15_000
+        set_price(&mut deps, &env, Decimal256::percent(10));
+        let msg = ExecuteMsg::Liquidate {
+            max_repay: Uint128::new(500),
+        };
+        let res = exec(&mut deps, &env, "liquidator", &coins(500, PRINCIPAL), msg).unwrap();
+        assert_eq!(transitions(&res), moved("active", "liquidating"));
+
+        let err = exec(
+            &mut deps,
+            &env,
+            BORROWER,
+            &[],
+            ExecuteMsg::WithdrawCollateral {
+                amount: Uint128::new(1),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidStatus {
+                status: SeriesStatus::Liquidating
+            }
+        );
+
+        let res = exec(
+            &mut deps,
+            &env,
+            BORROWER,
+            &coins(10_000, COLLATERAL),
+            ExecuteMsg::DepositCollateral {},
+        )
+        .unwrap();
+        assert_eq!(transitions(&res), moved("liquidating", "active"));
+    }
+}
+
+mod default {
+    use cosmwasm_std::testing::mock_env;
+    use cosmwasm_std::{coins, BankMsg, Coin, CosmosMsg, Env, Response, Uint128};
+    use heb_types::SeriesStatus;
+
+    use super::helpers::*;
+    use crate::contract::DEFAULT_GRACE_PERIOD_SECONDS;
+    use crate::error::ContractError;
+    use crate::msg::{
+        AprReason, EffectiveAprResponse, ExecuteMsg, ExpectedRecoveryResponse, QueryMsg,
+        StateResponse,
+    };
+
+    const KEEPER: &str = "keeper";
+
+    /// `position(None)` with a custom grace period.
+    fn position_with_grace(grace: Option<u64>) -> Deps {
+        let mut t = terms(&mock_env());
+        t.principal_cap = Uint128::new(1_000);
+        t.default_grace_period_seconds = grace;
+        let mut deps = setup_with(t, 0);
+        open_sale(&mut deps, 10_000);
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+        deps
+    }
+
+    fn declare(deps: &mut Deps, env: &Env) -> Result<Response, ContractError> {
+        exec(deps, env, KEEPER, &[], ExecuteMsg::DeclareDefault {})
+    }
+
+    fn recovery(deps: &Deps, env: &Env, who: &str) -> ExpectedRecoveryResponse {
+        query_as(
+            deps,
+            env,
+            QueryMsg::ExpectedRecovery {
+                address: who.to_string(),
+            },
+        )
+    }
+
+    #[test]
+    fn default_waits_for_the_grace_period() {
+        let mut deps = position(None);
+        let maturity = mock_env().block.time.seconds() + 2 * YEAR;
+        let err = declare(&mut deps, &env_at(YEAR)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidStatus {
+                status: SeriesStatus::Active
+            }
+        );
+
+        let ends_at = maturity + DEFAULT_GRACE_PERIOD_SECONDS;
+        let err = declare(
+            &mut deps,
+            &env_at(2 * YEAR + DEFAULT_GRACE_PERIOD_SECONDS - 1),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::GracePeriodActive { ends_at });
+
+        let env = env_at(2 * YEAR + DEFAULT_GRACE_PERIOD_SECONDS);
+        let res = declare(&mut deps, &env).unwrap();
+        let event = res.events.iter().find(|e| e.ty == "series_status").unwrap();
+        assert_eq!(event.attributes[1].value, "defaulted");
+        let st: StateResponse = query_as(&deps, &env, QueryMsg::State {});
+        assert_eq!(st.status, SeriesStatus::Defaulted);
+        assert_eq!(st.defaulted_at, Some(ends_at));
+    }
+
+    #[test]
+    fn repaid_series_cannot_default() {
+        let mut deps = position(None);
+        let maturity = env_at(2 * YEAR);
+        exec(
+            &mut deps,
+            &maturity,
+            BORROWER,
+            &coins(1_200, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap();
+        let err = declare(&mut deps, &env_at(3 * YEAR)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidStatus {
+                status: SeriesStatus::Settled
+            }
+        );
+    }
+
+    #[test]
+    fn penalty_interest_accrues_once_defaulted() {
+        let mut deps = position_with_grace(Some(0));
+        declare(&mut deps, &env_at(2 * YEAR)).unwrap();
+
+        let later = env_at(3 * YEAR);
+        let apr: EffectiveAprResponse = query_as(&deps, &later, QueryMsg::EffectiveApr {});
+        assert_eq!(apr.reason, AprReason::Defaulted);
+        assert_eq!(apr.apr_bps, 1_500);
+        assert!(apr.penalty_active);
+
+        // index 1.2 at maturity, then a year at 15%: 1.2 * 1.15 = 1.38
+        assert_eq!(accrued(&deps, &later, LENDER), 380);
+    }
+
+    #[test]
+    fn grace_period_accrues_at_the_base_rate() {
+        let mut deps = position_with_grace(Some(YEAR));
+        let apr: EffectiveAprResponse = query_as(
+            &deps,
+            &env_at(2 * YEAR + YEAR / 2),
+            QueryMsg::EffectiveApr {},
+        );
+        assert_eq!(apr.apr_bps, 1_000);
+        assert!(!apr.penalty_active);
+
+        declare(&mut deps, &env_at(3 * YEAR)).unwrap();
+        // 10% through maturity and the grace year: 1.3, then a year at 15%: 1.3 * 1.15 = 1.495
+        assert_eq!(accrued(&deps, &env_at(4 * YEAR), LENDER), 495);
+    }
+
+    #[test]
+    fn repaying_inside_the_grace_period_pays_base_interest() {
+        let mut deps = position_with_grace(Some(YEAR));
+        let env = env_at(2 * YEAR + YEAR / 2);
+        assert_eq!(accrued(&deps, &env, LENDER), 250);
+        exec(
+            &mut deps,
+            &env,
+            BORROWER,
+            &coins(1_250, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap();
+        let st: StateResponse = query_as(&deps, &env, QueryMsg::State {});
+        assert_eq!(st.status, SeriesStatus::Settled);
+
+        // settled: nothing more accrues and the series can no longer default
+        assert_eq!(accrued(&deps, &env_at(4 * YEAR), LENDER), 250);
+        let err = declare(&mut deps, &env_at(4 * YEAR)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidStatus {
+                status: SeriesStatus::Settled
+            }
+        );
+    }
+
+    #[test]
+    fn defaulted_collateral_is_locked_until_repaid() {
+        let mut deps = position_with_grace(Some(0));
+        let env = env_at(2 * YEAR);
+        declare(&mut deps, &env).unwrap();
+
+        let msg = ExecuteMsg::WithdrawCollateral {
+            amount: Uint128::new(1),
+        };
+        let err = exec(&mut deps, &env, BORROWER, &[], msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidStatus {
+                status: SeriesStatus::Defaulted
+            }
+        );
+        let msg = ExecuteMsg::RedeemAtMaturity {
+            amount: Uint128::new(1),
+        };
+        let err = exec(&mut deps, &env, LENDER, &[], msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidStatus {
+                status: SeriesStatus::Defaulted
+            }
+        );
+
+        // curing the default settles the series
+        let res = exec(
+            &mut deps,
+            &env,
+            BORROWER,
+            &coins(1_300, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap();
+        let event = res.events.iter().find(|e| e.ty == "series_status").unwrap();
+        assert_eq!(event.attributes[1].value, "settled");
+    }
+
+    #[test]
+    fn expected_recovery_is_pro_rata_by_balance() {
+        let mut deps = position(None);
+        let msg = ExecuteMsg::Transfer {
+            recipient: LENDER2.to_string(),
+            amount: Uint128::new(250),
+        };
+        exec(&mut deps, &mock_env(), LENDER, &[], msg).unwrap();
+
+        let maturity = env_at(2 * YEAR);
+        let r = recovery(&deps, &maturity, LENDER);
+        assert_eq!(r.status, SeriesStatus::Matured);
+        assert!(!r.collateral_claimable);
+        assert_eq!(r.balance, Uint128::new(750));
+        assert_eq!(r.claim, Uint128::new(900));
+        assert_eq!(r.collateral_share, Uint128::new(7_500));
+        // 7_500 uregen at 0.25
+        assert_eq!(r.collateral_value, Some(Uint128::new(1_875)));
+        assert_eq!(r.recovery_bps, Some(20_833));
+
+        let r = recovery(&deps, &maturity, LENDER2);
+        assert_eq!(r.collateral_share, Uint128::new(2_500));
+        assert_eq!(r.claim, Uint128::new(300));
+
+        let env = env_at(2 * YEAR + DEFAULT_GRACE_PERIOD_SECONDS);
+        declare(&mut deps, &env).unwrap();
+        assert!(recovery(&deps, &env, LENDER).collateral_claimable);
+
+        let r = recovery(&deps, &env, "nobody");
+        assert_eq!(r.collateral_share, Uint128::zero());
+        assert_eq!(r.recovery_bps, None);
+    }
+
+    #[test]
+    fn recovery_is_closed_until_default() {
+        let mut deps = position(None);
+        let msg = ExecuteMsg::ClaimDefaultRecovery {
+            amount: Uint128::new(1),
+        };
+        let err = exec(&mut deps, &env_at(2 * YEAR), LENDER, &[], msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidStatus {
+                status: SeriesStatus::Matured
+            }
+        );
+    }
+
+    #[test]
+    fn recovery_pays_pro_rata_at_the_snapshot_rate() {
+        let mut deps = position_with_grace(Some(0));
+        let transfer = |deps: &mut Deps, from: &str, to: &str, amount: u128| {
+            let msg = ExecuteMsg::Transfer {
+                recipient: to.to_string(),
+                amount: Uint128::new(amount),
+            };
+            exec(deps, &mock_env(), from, &[], msg).unwrap();
+        };
+        transfer(&mut deps, LENDER, LENDER2, 250);
+
+        // 200 funds interest, 400 goes to the principal reserve
+        let env = env_at(2 * YEAR);
+        exec(
+            &mut deps,
+            &env,
+            BORROWER,
+            &coins(600, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap();
+        declare(&mut deps, &env).unwrap();
+        let r = recovery(&deps, &env, LENDER);
+        assert_eq!(
+            (r.collateral_share.u128(), r.reserve_share.u128()),
+            (7_500, 300)
+        );
+
+        let claim = |deps: &mut Deps, who: &str, amount: u128| {
+            let msg = ExecuteMsg::ClaimDefaultRecovery {
+                amount: Uint128::new(amount),
+            };
+            exec(deps, &env, who, &[], msg).unwrap()
+        };
+        let res = claim(&mut deps, LENDER, 750);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: LENDER.to_string(),
+                amount: vec![Coin::new(7_500, COLLATERAL), Coin::new(300, PRINCIPAL)],
+            })
+        );
+        let err = exec(
+            &mut deps,
+            &env,
+            BORROWER,
+            &coins(100, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::RecoveryStarted);
+
+        // splitting a position after the snapshot does not change what it recovers
+        transfer(&mut deps, LENDER2, "third", 100);
+        let r = recovery(&deps, &env, "third");
+        assert_eq!(
+            (r.collateral_share.u128(), r.reserve_share.u128()),
+            (1_000, 40)
+        );
+        let res = claim(&mut deps, "third", 100);
+        assert_eq!(attr(&res, "collateral_out"), "1000");
+        assert_eq!(attr(&res, "principal_out"), "40");
+        let res = claim(&mut deps, LENDER2, 150);
+        assert_eq!(attr(&res, "collateral_out"), "1500");
+        assert_eq!(attr(&res, "principal_out"), "60");
+        let event = res.events.iter().find(|e| e.ty == "series_status").unwrap();
+        assert_eq!(event.attributes[1].value, "settled");
+
+        let st: StateResponse = query_as(&deps, &env, QueryMsg::State {});
+        assert!(st.collateral_locked.is_zero());
+        assert!(st.principal_reserve.is_zero());
+    }
+}
+
+mod coupons {
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::{coins, BankMsg, Coin, CosmosMsg, Env, Response, Uint128};
+
+    use super::helpers::*;
+    use crate::contract::{instantiate, DEFAULT_COUPON_GRACE_PERIOD_SECONDS};
+    use crate::error::ContractError;
+    use crate::msg::{
+        AprReason, CouponResponse, CouponScheduleResponse, CouponStatus, EffectiveAprResponse,
+        ExecuteMsg, InstantiateMsg, QueryMsg, StateResponse,
+    };
+
+    const HALF_YEAR: u64 = YEAR / 2;
+
+    /// `position(None)` paying a coupon every half year until maturity at two years.
+    fn coupon_position() -> Deps {
+        let mut t = terms(&mock_env());
+        t.principal_cap = Uint128::new(1_000);
+        t.coupon_period_seconds = Some(HALF_YEAR);
+        let mut deps = setup_with(t, 0);
+        open_sale(&mut deps, 10_000);
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+        deps
+    }
+
+    fn schedule(deps: &Deps, env: &Env, holder: Option<&str>) -> Vec<CouponResponse> {
+        let res: CouponScheduleResponse = query_as(
+            deps,
+            env,
+            QueryMsg::CouponSchedule {
+                holder: holder.map(str::to_string),
+                start_after: None,
+                limit: None,
+            },
+        );
+        res.coupons
+    }
+
+    fn pay(deps: &mut Deps, env: &Env, amount: u128) -> Result<Response, ContractError> {
+        exec(
+            deps,
+            env,
+            BORROWER,
+            &coins(amount, PRINCIPAL),
+            ExecuteMsg::PayCoupon {},
+        )
+    }
+
+    fn transfer(deps: &mut Deps, env: &Env, from: &str, to: &str, amount: u128) {
+        let msg = ExecuteMsg::Transfer {
+            recipient: to.to_string(),
+            amount: Uint128::new(amount),
+        };
+        exec(deps, env, from, &[], msg).unwrap();
+    }
+
+    #[test]
+    fn schedule_runs_from_instantiate_to_maturity() {
+        let deps = coupon_position();
+        let start = mock_env().block.time.seconds();
+        let coupons = schedule(&deps, &mock_env(), None);
+        let dates: Vec<u64> = coupons.iter().map(|c| c.ts).collect();
+        assert_eq!(
+            dates,
+            vec![
+                start + HALF_YEAR,
+                start + 2 * HALF_YEAR,
+                start + 3 * HALF_YEAR,
+                start + 2 * YEAR
+            ]
+        );
+        assert!(coupons
+            .iter()
+            .all(|c| c.status == CouponStatus::Scheduled && c.amount_due.is_none()));
+        assert_eq!(
+            coupons[0].late_after,
+            start + HALF_YEAR + DEFAULT_COUPON_GRACE_PERIOD_SECONDS
+        );
+
+        let reject = |period: u64| {
+            let mut t = terms(&mock_env());
+            t.coupon_period_seconds = Some(period);
+            let msg = InstantiateMsg {
+                terms: t,
+                admin: ADMIN.to_string(),
+                protocol_fee_bps: 0,
+                fee_recipient: FEE_RECIPIENT.to_string(),
+            };
+            let info = mock_info(ADMIN, &[]);
+            instantiate(mock_deps().as_mut(), mock_env(), info, msg).unwrap_err()
+        };
+        assert!(matches!(reject(0), ContractError::InvalidConfig(_)));
+        // an hourly coupon over two years is longer than MAX_COUPONS
+        assert!(matches!(reject(3_600), ContractError::InvalidConfig(_)));
+    }
+
+    #[test]
+    fn coupon_is_fixed_on_its_date_and_funded_by_pay_coupon() {
+        let mut deps = coupon_position();
+        let err = pay(&mut deps, &mock_env(), 10).unwrap_err();
+        assert_eq!(err, ContractError::NoInterestDue);
+
+        let env = env_at(HALF_YEAR + 1);
+        let coupon = &schedule(&deps, &env, None)[0];
+        assert_eq!(coupon.status, CouponStatus::Due);
+        assert_eq!(coupon.amount_due, Some(Uint128::new(50)));
+        assert_eq!(coupon.record_supply, Some(Uint128::new(1_000)));
+
+        // only accrued interest is taken; the rest comes back
+        let res = pay(&mut deps, &env, 80).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: BORROWER.to_string(),
+                amount: vec![Coin::new(30, PRINCIPAL)],
+            })
+        );
+        let coupons = schedule(&deps, &env, None);
+        assert_eq!(coupons[0].status, CouponStatus::Paid);
+        assert_eq!(coupons[0].amount_paid, Uint128::new(50));
+        assert_eq!(coupons[1].status, CouponStatus::Scheduled);
+
+        exec(&mut deps, &env, LENDER, &[], ExecuteMsg::ClaimInterest {}).unwrap();
+        assert_eq!(schedule(&deps, &env, None)[0].status, CouponStatus::Paid);
+    }
+
+    #[test]
+    fn repay_funds_coupons_too() {
+        let mut deps = coupon_position();
+        let env = env_at(HALF_YEAR + 1);
+        exec(
+            &mut deps,
+            &env,
+            BORROWER,
+            &coins(20, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap();
+        let coupon = &schedule(&deps, &env, None)[0];
+        assert_eq!(coupon.status, CouponStatus::Due);
+        assert_eq!(coupon.amount_paid, Uint128::new(20));
+
+        exec(
+            &mut deps,
+            &env,
+            BORROWER,
+            &coins(30, PRINCIPAL),
+            ExecuteMsg::Repay {},
+        )
+        .unwrap();
+        assert_eq!(schedule(&deps, &env, None)[0].status, CouponStatus::Paid);
+    }
+
+    #[test]
+    fn late_coupon_switches_to_the_penalty_rate_until_paid() {
+        let mut deps = coupon_position();
+        let apr = |deps: &Deps, env: &Env| -> EffectiveAprResponse {
+            query_as(deps, env, QueryMsg::EffectiveApr {})
+        };
+        let deadline = HALF_YEAR + DEFAULT_COUPON_GRACE_PERIOD_SECONDS;
+        assert_eq!(apr(&deps, &env_at(deadline - 1)).apr_bps, 1_000);
+
+        let env = env_at(deadline);
+        let res = apr(&deps, &env);
+        assert_eq!(res.reason, AprReason::CouponLate);
+        assert_eq!(res.apr_bps, 1_500);
+        assert!(res.penalty_active);
+        assert_eq!(schedule(&deps, &env, None)[0].status, CouponStatus::Late);
+
+        // a quarter year late at 15%: 1.05 -> 1.0509 -> 1.0903, where 10% would reach 1.0771
+        let env = env_at(deadline + YEAR / 4);
+        let res = pay(&mut deps, &env, 50).unwrap();
+        assert_eq!(
+            res.attributes
+                .iter()
+                .find(|a| a.key == "coupon_late")
+                .unwrap()
+                .value,
+            "false"
+        );
+        assert_eq!(apr(&deps, &env).reason, AprReason::NoCheckpoint);
+        let st: StateResponse = query_as(&deps, &env, QueryMsg::State {});
+        assert_eq!(st.interest_accrued, Uint128::new(89));
+        assert_eq!(st.interest_funded, Uint128::new(50));
+        assert_eq!(schedule(&deps, &env, None)[0].status, CouponStatus::Paid);
+    }
+
+    #[test]
+    fn holders_are_recorded_as_of_each_coupon_date() {
+        let mut deps = coupon_position();
+        transfer(&mut deps, &mock_env(), LENDER, LENDER2, 400);
+        // moves after the first coupon date do not change its record
+        let env = env_at(HALF_YEAR + 10);
+        transfer(&mut deps, &env, LENDER, LENDER2, 100);
+        transfer(&mut deps, &env, LENDER2, "third", 50);
+
+        let balances = |who: &str| -> Vec<Option<u128>> {
+            schedule(&deps, &env_at(YEAR), Some(who))
+                .iter()
+                .map(|c| c.holder_balance.map(|b| b.u128()))
+                .collect()
+        };
+        assert_eq!(balances(LENDER), vec![Some(600), Some(500), None, None]);
+        assert_eq!(balances(LENDER2), vec![Some(400), Some(450), None, None]);
+        assert_eq!(balances("third"), vec![Some(0), Some(50), None, None]);
+        assert_eq!(balances("nobody"), vec![Some(0), Some(0), None, None]);
+    }
+
+    #[test]
+    fn pay_coupon_needs_a_schedule() {
+        let mut deps = position(None);
+        let err = pay(&mut deps, &env_at(YEAR), 10).unwrap_err();
+        assert_eq!(err, ContractError::NoCouponSchedule);
+        assert!(schedule(&deps, &env_at(YEAR), None).is_empty());
+    }
+}
+
+mod calls {
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::{coins, BankMsg, Coin, CosmosMsg, Env, Order, Response, StdResult, Uint128};
+    use heb_types::{CallDate, SeriesStatus};
+
+    use super::helpers::*;
+    use crate::contract::instantiate;
+    use crate::error::ContractError;
+    use crate::msg::{
+        AccruedInterestResponse, CallQuoteResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+        StateResponse,
+    };
+    use crate::state::ACCOUNTS;
+
+    /// Callable from one year at 2%, from eighteen months at 1%; 1_000 sold, 250 of it moved to
+    /// `LENDER2`.
+    fn callable_position() -> Deps {
+        let start = mock_env().block.time.seconds();
+        let mut t = terms(&mock_env());
+        t.principal_cap = Uint128::new(1_000);
+        t.call_schedule = Some(vec![
+            CallDate {
+                ts: start + YEAR,
+                premium_bps: 200,
+            },
+            CallDate {
+                ts: start + YEAR + YEAR / 2,
+                premium_bps: 100,
+            },
+        ]);
+        let mut deps = setup_with(t, 0);
+        open_sale(&mut deps, 10_000);
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+        let msg = ExecuteMsg::Transfer {
+            recipient: LENDER2.to_string(),
+            amount: Uint128::new(250),
+        };
+        exec(&mut deps, &mock_env(), LENDER, &[], msg).unwrap();
+        deps
+    }
+
+    fn call(
+        deps: &mut Deps,
+        env: &Env,
+        amount: u128,
+        funds: u128,
+    ) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::CallBonds {
+            amount: Uint128::new(amount),
+        };
+        exec(deps, env, BORROWER, &coins(funds, PRINCIPAL), msg)
+    }
+
+    fn quote(deps: &Deps, env: &Env, amount: u128) -> CallQuoteResponse {
+        query_as(
+            deps,
+            env,
+            QueryMsg::CallQuote {
+                amount: Uint128::new(amount),
+            },
+        )
+    }
+
+    fn claim(deps: &mut Deps, env: &Env, who: &str) -> Response {
+        exec(deps, env, who, &[], ExecuteMsg::ClaimRedemption {}).unwrap()
+    }
+
+    fn paid_to(res: &Response, who: &str, amount: u128) {
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: who.to_string(),
+                amount: vec![Coin::new(amount, PRINCIPAL)],
+            })
+        );
+    }
+
+    #[test]
+    fn call_premium_follows_the_schedule() {
+        let mut deps = callable_position();
+        assert_eq!(quote(&deps, &env_at(YEAR - 1), 100).premium_bps, None);
+
+        let q = quote(&deps, &env_at(YEAR), 400);
+        assert_eq!(q.premium_bps, Some(200));
+        assert_eq!(
+            (q.principal.u128(), q.premium.u128(), q.interest.u128()),
+            (400, 8, 100)
+        );
+        assert_eq!(q.total, Uint128::new(508));
+        assert_eq!(
+            quote(&deps, &env_at(YEAR + YEAR / 2), 400).premium_bps,
+            Some(100)
+        );
+
+        let err = call(&mut deps, &env_at(YEAR - 1), 100, 1_000).unwrap_err();
+        assert_eq!(err, ContractError::NotCallable);
+        let err = call(&mut deps, &env_at(YEAR), 1_001, 2_000).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::CallTooLarge {
+                max: Uint128::new(1_000)
+            }
+        );
+        // the failed calls above accrued in two steps, so ask for the exact amount again
+        let required = quote(&deps, &env_at(YEAR), 400).total;
+        let err = call(&mut deps, &env_at(YEAR), 400, 500).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::CallNotFunded {
+                required,
+                paid: Uint128::new(500)
+            }
+        );
+
+        let mut t = terms(&mock_env());
+        t.call_schedule = Some(vec![CallDate {
+            ts: t.maturity_ts,
+            premium_bps: 0,
+        }]);
+        let msg = InstantiateMsg {
+            terms: t,
+            admin: ADMIN.to_string(),
+            protocol_fee_bps: 0,
+            fee_recipient: FEE_RECIPIENT.to_string(),
+        };
+        let err =
+            instantiate(mock_deps().as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig(_)));
+    }
+
+    #[test]
+    fn partial_call_redeems_every_holder_pro_rata() {
+        let mut deps = callable_position();
+        let env = env_at(YEAR);
+        let res = call(&mut deps, &env, 400, 520).unwrap();
+        paid_to(&res, BORROWER, 12);
+
+        assert_eq!(balance(&deps, LENDER), 450);
+        assert_eq!(balance(&deps, LENDER2), 150);
+        assert_eq!(pending(&deps, &env, LENDER), (300, 306));
+        assert_eq!(pending(&deps, &env, LENDER2), (100, 102));
+        let st: StateResponse = query_as(&deps, &env, QueryMsg::State {});
+        assert_eq!(st.total_supply, Uint128::new(600));
+        assert_eq!(st.total_principal_outstanding, Uint128::new(600));
+        assert_eq!(st.redemption_reserve, Uint128::new(408));
+        assert_eq!(st.interest_funded, Uint128::new(100));
+
+        paid_to(&claim(&mut deps, &env, LENDER), LENDER, 306);
+        let err = exec(&mut deps, &env, LENDER, &[], ExecuteMsg::ClaimRedemption {}).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim);
+        // a transfer after the call does not carry the redemption with it
+        let msg = ExecuteMsg::Transfer {
+            recipient: "third".to_string(),
+            amount: Uint128::new(150),
+        };
+        exec(&mut deps, &env, LENDER2, &[], msg).unwrap();
+        paid_to(&claim(&mut deps, &env, LENDER2), LENDER2, 102);
+        assert_eq!(pending(&deps, &env, "third"), (0, 0));
+
+        // interest up to the call is earned on the full balance, then on what is left:
+        // 750 * 0.1 + 450 * (1.21 - 1.1)
+        let accrued: AccruedInterestResponse = query_as(
+            &deps,
+            &env_at(2 * YEAR),
+            QueryMsg::AccruedInterest {
+                address: LENDER.to_string(),
+            },
+        );
+        assert_eq!(accrued.accrued, Uint128::new(124));
+    }
+
+    #[test]
+    fn small_holders_are_not_redeemed_without_payout() {
+        let start = mock_env().block.time.seconds();
+        let mut t = terms(&mock_env());
+        t.principal_cap = Uint128::new(3);
+        t.call_schedule = Some(vec![CallDate {
+            ts: start + YEAR,
+            premium_bps: 200,
+        }]);
+        let mut deps = setup_with(t, 0);
+        open_sale(&mut deps, 10_000);
+        let holders = [LENDER, LENDER2, "third"];
+        for who in holders {
+            buy(&mut deps, &mock_env(), who, 1);
+        }
+
+        let env = env_at(YEAR);
+        let total = quote(&deps, &env, 1).total.u128();
+        call(&mut deps, &env, 1, total).unwrap();
+
+        // a third of a token each: nobody gives up a token they would not be paid for
+        for who in holders {
+            assert_eq!(balance(&deps, who), 1);
+            assert_eq!(pending(&deps, &env, who), (0, 0));
+        }
+        // once every holder has synced, the fractions they kept add up to the called token, whose
+        // principal goes back to the reserve
+        for (from, to) in [(LENDER, LENDER2), (LENDER2, "third"), ("third", LENDER)] {
+            let msg = ExecuteMsg::Transfer {
+                recipient: to.to_string(),
+                amount: Uint128::one(),
+            };
+            exec(&mut deps, &env, from, &[], msg).unwrap();
+        }
+        let st: StateResponse = query_as(&deps, &env, QueryMsg::State {});
+        assert_eq!(st.total_supply, Uint128::new(3));
+        assert_eq!(st.total_principal_outstanding, Uint128::new(2));
+        assert_eq!(st.principal_reserve, Uint128::new(1));
+        // the premium paid on it stays behind
+        assert_eq!(st.redemption_reserve, Uint128::new(1));
+        for who in holders {
+            assert_eq!(balance(&deps, who), 1);
+            assert_eq!(pending(&deps, &env, who), (0, 0));
+        }
+    }
+
+    #[test]
+    fn a_call_does_not_walk_the_holders() {
+        let start = mock_env().block.time.seconds();
+        let mut t = terms(&mock_env());
+        t.principal_cap = Uint128::new(65_550);
+        t.call_schedule = Some(vec![CallDate {
+            ts: start + YEAR,
+            premium_bps: 200,
+        }]);
+        let mut deps = setup_with(t, 0);
+        open_sale(&mut deps, 1_000_000);
+        let holders: Vec<String> = (0..60).map(|i| format!("holder{i}")).collect();
+        for (i, who) in holders.iter().enumerate() {
+            buy(&mut deps, &mock_env(), who, 1 + 37 * i as u128);
+        }
+        // every fifth holder moves everything on and is left with an empty account
+        for who in holders.iter().step_by(5) {
+            let msg = ExecuteMsg::Transfer {
+                recipient: LENDER.to_string(),
+                amount: Uint128::new(balance(&deps, who)),
+            };
+            exec(&mut deps, &mock_env(), who, &[], msg).unwrap();
+        }
+        let before: StateResponse = query_as(&deps, &mock_env(), QueryMsg::State {});
+        let accounts = |deps: &Deps| {
+            ACCOUNTS
+                .range(&deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()
+                .unwrap()
+        };
+        let stored = accounts(&deps);
+
+        let env = env_at(YEAR);
+        let amount = before.total_supply.u128() / 3;
+        let total = quote(&deps, &env, amount).total.u128();
+        call(&mut deps, &env, amount, total).unwrap();
+        assert_eq!(accounts(&deps), stored);
+
+        // each holder syncs by claiming, or with a transfer to themselves if they have nothing
+        // to claim
+        let mut redeemed = 0;
+        for who in holders.iter().chain([LENDER.to_string()].iter()) {
+            let (tokens, payout) = pending(&deps, &env, who);
+            assert!(payout >= tokens);
+            if tokens > 0 {
+                paid_to(&claim(&mut deps, &env, who), who, payout);
+                redeemed += tokens;
+            } else if balance(&deps, who) > 0 {
+                let msg = ExecuteMsg::Transfer {
+                    recipient: who.to_string(),
+                    amount: Uint128::one(),
+                };
+                exec(&mut deps, &env, who, &[], msg).unwrap();
+            } else {
+                assert_eq!(payout, 0);
+            }
+        }
+        for who in holders.iter().step_by(5) {
+            assert_eq!(pending(&deps, &env, who), (0, 0));
+        }
+        // only whole tokens were taken, and the fractions held back went back into the supply
+        let st: StateResponse = query_as(&deps, &env, QueryMsg::State {});
+        assert!(redeemed <= amount);
+        assert_eq!(
+            st.total_supply.u128(),
+            before.total_supply.u128() - redeemed
+        );
+        assert_eq!(
+            st.total_principal_outstanding + st.principal_reserve,
+            st.total_supply
+        );
+    }
+
+    #[test]
+    fn calling_every_bond_settles_the_series() {
+        let mut deps = callable_position();
+        let env = env_at(YEAR);
+        let res = call(&mut deps, &env, 1_000, 1_120).unwrap();
+        let event = res.events.iter().find(|e| e.ty == "series_status").unwrap();
+        assert_eq!(event.attributes[1].value, "settled");
+
+        paid_to(&claim(&mut deps, &env, LENDER), LENDER, 765);
+        paid_to(&claim(&mut deps, &env, LENDER2), LENDER2, 255);
+        let st: StateResponse = query_as(&deps, &env, QueryMsg::State {});
+        assert_eq!(st.status, SeriesStatus::Settled);
+        assert!(st.redemption_reserve.is_zero());
+
+        // interest funded by the call stays claimable
+        let res = exec(&mut deps, &env, LENDER, &[], ExecuteMsg::ClaimInterest {}).unwrap();
+        paid_to(&res, LENDER, 75);
+        let msg = ExecuteMsg::WithdrawCollateral {
+            amount: Uint128::new(10_000),
+        };
+        exec(&mut deps, &env, BORROWER, &[], msg).unwrap();
+    }
+}
+
+mod amortization {
+    use cosmwasm_std::testing::{mock_env, mock_info};
+    use cosmwasm_std::{BankMsg, Coin, CosmosMsg, Uint128};
+    use heb_types::{Installment, SeriesStatus};
+
+    use super::helpers::*;
+    use crate::contract::instantiate;
+    use crate::error::ContractError;
+    use crate::msg::{
+        AmortizationResponse, ExecuteMsg, InstallmentStatus, InstantiateMsg, QueryMsg,
+        StateResponse,
+    };
+
+    /// 30% due after a year, 50% at maturity and the last 20% as a bullet; 1_000 sold, 250 of it
+    /// moved to `LENDER2`.
+    fn amortizing_position() -> Deps {
+        let start = mock_env().block.time.seconds();
+        let mut t = terms(&mock_env());
+        t.principal_cap = Uint128::new(1_000);
+        t.amortization = Some(vec![
+            Installment {
+                ts: start + YEAR,
+                principal_bps: 3_000,
+            },
+            Installment {
+                ts: start + 2 * YEAR,
+                principal_bps: 5_000,
+            },
+        ]);
+        let mut deps = setup_with(t, 0);
+        open_sale(&mut deps, 10_000);
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+        let msg = ExecuteMsg::Transfer {
+            recipient: LENDER2.to_string(),
+            amount: Uint128::new(250),
+        };
+        exec(&mut deps, &mock_env(), LENDER, &[], msg).unwrap();
+        deps
+    }
+
+    fn schedule(deps: &Deps, offset: u64) -> AmortizationResponse {
+        query_as(deps, &env_at(offset), QueryMsg::Amortization {})
+    }
+
+    fn installment(res: &AmortizationResponse, i: usize) -> (InstallmentStatus, u128, u128) {
+        let inst = &res.installments[i];
+        (
+            inst.status.clone(),
+            inst.amount_due.u128(),
+            inst.amount_paid.u128(),
+        )
+    }
+
+    #[test]
+    fn repay_pays_the_current_installment_and_redeems_pro_rata() {
+        let mut deps = amortizing_position();
+        let res = schedule(&deps, 0);
+        assert_eq!(installment(&res, 0), (InstallmentStatus::Scheduled, 300, 0));
+        assert_eq!(installment(&res, 1), (InstallmentStatus::Scheduled, 500, 0));
+        assert_eq!(res.total_due, Uint128::new(800));
+
+        // a year of interest first, then the installment
+        let res = repay(&mut deps, YEAR, 400);
+        assert_eq!(attr(&res, "interest_funded"), "100");
+        assert_eq!(attr(&res, "installments_paid"), "300");
+        let res = schedule(&deps, YEAR);
+        assert_eq!(installment(&res, 0), (InstallmentStatus::Paid, 300, 300));
+        assert_eq!(res.total_paid, Uint128::new(300));
+        assert!(res.overdue.is_zero());
+
+        assert_eq!(balance(&deps, LENDER), 525);
+        assert_eq!(balance(&deps, LENDER2), 175);
+        assert_eq!(pending(&deps, &mock_env(), LENDER), (225, 225));
+        assert_eq!(pending(&deps, &mock_env(), LENDER2), (75, 75));
+        let st: StateResponse = query_as(&deps, &env_at(YEAR), QueryMsg::State {});
+        assert_eq!(st.total_supply, Uint128::new(700));
+        assert_eq!(st.total_principal_outstanding, Uint128::new(700));
+        assert_eq!(st.redemption_reserve, Uint128::new(300));
+        assert!(st.principal_reserve.is_zero());
+
+        let res = exec(
+            &mut deps,
+            &env_at(YEAR),
+            LENDER2,
+            &[],
+            ExecuteMsg::ClaimRedemption {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: LENDER2.to_string(),
+                amount: vec![Coin::new(75, PRINCIPAL)],
+            })
+        );
+
+        let mut t = terms(&mock_env());
+        t.amortization = Some(vec![Installment {
+            ts: t.maturity_ts,
+            principal_bps: 10_001,
+        }]);
+        let msg = InstantiateMsg {
+            terms: t,
+            admin: ADMIN.to_string(),
+            protocol_fee_bps: 0,
+            fee_recipient: FEE_RECIPIENT.to_string(),
+        };
+        let err =
+            instantiate(mock_deps().as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConfig(_)));
+    }
+
+    #[test]
+    fn missed_installment_is_overdue_until_paid() {
+        let mut deps = amortizing_position();
+        let res = schedule(&deps, YEAR);
+        assert_eq!(installment(&res, 0), (InstallmentStatus::Overdue, 300, 0));
+        assert_eq!(res.overdue, Uint128::new(300));
+
+        // a late partial payment still goes to the oldest installment
+        let res = repay(&mut deps, YEAR + 1, 250);
+        let paid: u128 = attr(&res, "installments_paid").parse().unwrap();
+        let res = schedule(&deps, YEAR + 1);
+        assert_eq!(
+            installment(&res, 0),
+            (InstallmentStatus::Overdue, 300, paid)
+        );
+        assert_eq!(res.overdue.u128(), 300 - paid);
+    }
+
+    #[test]
+    fn principal_past_the_installments_waits_for_maturity() {
+        let mut deps = amortizing_position();
+        let res = repay(&mut deps, 2 * YEAR, 5_000);
+        assert_eq!(attr(&res, "installments_paid"), "800");
+        let res = schedule(&deps, 2 * YEAR);
+        assert_eq!(installment(&res, 1), (InstallmentStatus::Paid, 500, 500));
+        let st: StateResponse = query_as(&deps, &env_at(2 * YEAR), QueryMsg::State {});
+        assert_eq!(st.status, SeriesStatus::Settled);
+        assert_eq!(st.total_supply, Uint128::new(200));
+        assert_eq!(st.principal_reserve, Uint128::new(200));
+
+        // the bullet share is redeemed at maturity, the rest claimed as redemptions
+        assert_eq!(balance(&deps, LENDER), 150);
+        assert_eq!(pending(&deps, &mock_env(), LENDER), (600, 600));
+        let msg = ExecuteMsg::RedeemAtMaturity {
+            amount: Uint128::new(150),
+        };
+        exec(&mut deps, &env_at(2 * YEAR), LENDER, &[], msg).unwrap();
+    }
+
+    #[test]
+    fn early_repay_stops_at_the_current_installment() {
+        let mut deps = amortizing_position();
+        // before the first date only the first installment is payable; the rest waits for maturity
+        let res = repay(&mut deps, YEAR / 2, 900);
+        let principal: u128 = attr(&res, "principal_repaid").parse().unwrap();
+        assert_eq!(attr(&res, "installments_paid"), "300");
+        let res = schedule(&deps, YEAR / 2);
+        assert_eq!(installment(&res, 0), (InstallmentStatus::Paid, 300, 300));
+        assert_eq!(installment(&res, 1), (InstallmentStatus::Scheduled, 500, 0));
+        let st: StateResponse = query_as(&deps, &env_at(YEAR / 2), QueryMsg::State {});
+        assert_eq!(st.total_supply, Uint128::new(700));
+        assert_eq!(st.principal_reserve.u128(), principal - 300);
+
+        // after the first date the second installment is the current one
+        let res = repay(&mut deps, YEAR + 1, 400);
+        assert_ne!(attr(&res, "installments_paid"), "0");
+    }
+
+    #[test]
+    fn installments_can_retire_every_bond_early() {
+        let start = mock_env().block.time.seconds();
+        let mut t = terms(&mock_env());
+        t.principal_cap = Uint128::new(1_000);
+        t.amortization = Some(vec![Installment {
+            ts: start + YEAR,
+            principal_bps: 10_000,
+        }]);
+        let mut deps = setup_with(t, 0);
+        open_sale(&mut deps, 10_000);
+        buy(&mut deps, &mock_env(), LENDER, 1_000);
+
+        let res = repay(&mut deps, YEAR / 2, 2_000);
+        let event = res.events.iter().find(|e| e.ty == "series_status").unwrap();
+        assert_eq!(event.attributes[1].value, "settled");
+        assert_eq!(pending(&deps, &mock_env(), LENDER), (1_000, 1_000));
+    }
 }
@@ -0,0 +1,406 @@
+//! CW20 interface over the bond token balances in `ACCOUNTS`.
+//!
+//! Every balance movement settles interest for the accounts involved (`accrue` + `sync_account`)
+//! before their balances change, so interest is always earned by whoever held the tokens.
+//!
+//! In TokenFactory mode the execute messages are disabled and `ACCOUNTS` mirrors bank balances of
+//! the native bond denom instead, kept in step by the before-send hook.
+
+use cosmwasm_std::{
+    Addr, BankMsg, Binary, BlockInfo, Coin, Deps, DepsMut, Env, MessageInfo, Order, Response,
+    StdResult, Storage, Uint128,
+};
+use cw20::{
+    AllAccountsResponse, AllAllowancesResponse, AllowanceInfo, AllowanceResponse, Cw20ReceiveMsg,
+    Expiration, TokenInfoResponse,
+};
+use cw_storage_plus::Bound;
+
+use crate::contract::{accrue, retire_tokens, sync_account};
+use crate::error::ContractError;
+use crate::state::{ACCOUNTS, ALLOWANCES, CONFIG, STATE};
+use crate::tokenfactory::{bond_denom, is_token_factory};
+
+pub const TOKEN_NAME: &str = "Hybrid Ecological Bond";
+pub const TOKEN_SYMBOL: &str = "HEB";
+/// Bond tokens are minted 1:1 with principal base units; principal denoms are 6-decimal stables.
+pub const TOKEN_DECIMALS: u8 = 6;
+
+pub(crate) const DEFAULT_LIMIT: u32 = 10;
+pub(crate) const MAX_LIMIT: u32 = 30;
+
+fn require_cw20_mode(storage: &dyn Storage) -> Result<(), ContractError> {
+    if is_token_factory(&CONFIG.load(storage)?) {
+        return Err(ContractError::NativeBondToken);
+    }
+    Ok(())
+}
+
+/// Move `amount` tokens from `from` to `to`, settling both accounts first.
+fn move_tokens(
+    mut deps: DepsMut,
+    env: &Env,
+    from: &Addr,
+    to: &Addr,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    accrue(deps.branch(), env)?;
+    sync_account(deps.branch(), from)?;
+    sync_account(deps.branch(), to)?;
+
+    let mut from_acc = ACCOUNTS.load(deps.storage, from.as_str())?;
+    if amount.is_zero() || amount > from_acc.balance {
+        return Err(ContractError::InsufficientFunds);
+    }
+    from_acc.balance -= amount;
+    ACCOUNTS.save(deps.storage, from.as_str(), &from_acc)?;
+
+    // reload after saving the sender so a self-transfer nets to zero
+    let mut to_acc = ACCOUNTS.load(deps.storage, to.as_str())?;
+    to_acc.balance = to_acc.balance.checked_add(amount)?;
+    ACCOUNTS.save(deps.storage, to.as_str(), &to_acc)?;
+    Ok(())
+}
+
+/// Destroy `amount` of `owner`'s tokens. The owner keeps interest earned up to now, but the burnt
+/// tokens stop earning and give up their principal claim, which the borrower no longer owes. Any
+/// of it already repaid into the reserve goes back to the borrower, unless a default recovery has
+/// fixed the pools.
+fn burn_tokens(
+    mut deps: DepsMut,
+    env: &Env,
+    owner: &Addr,
+    amount: Uint128,
+) -> Result<Option<BankMsg>, ContractError> {
+    accrue(deps.branch(), env)?;
+    sync_account(deps.branch(), owner)?;
+
+    let mut acc = ACCOUNTS.load(deps.storage, owner.as_str())?;
+    if amount.is_zero() || amount > acc.balance {
+        return Err(ContractError::InsufficientFunds);
+    }
+    acc.balance -= amount;
+    ACCOUNTS.save(deps.storage, owner.as_str(), &acc)?;
+
+    let mut st = STATE.load(deps.storage)?;
+    let repaid = retire_tokens(&mut st, amount)?;
+    let refund = match st.recovery {
+        Some(_) => Uint128::zero(),
+        None => repaid,
+    };
+    st.principal_reserve = st.principal_reserve.checked_sub(refund)?;
+    STATE.save(deps.storage, &st)?;
+    if refund.is_zero() {
+        return Ok(None);
+    }
+    let cfg = CONFIG.load(deps.storage)?;
+    Ok(Some(BankMsg::Send {
+        to_address: cfg.terms.borrower,
+        amount: vec![Coin::new(refund.u128(), cfg.terms.principal_denom)],
+    }))
+}
+
+/// Spend `amount` of the allowance `owner` granted `spender`.
+fn deduct_allowance(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    spender: &Addr,
+    block: &BlockInfo,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let key = (owner.as_str(), spender.as_str());
+    let mut allowance = ALLOWANCES
+        .may_load(storage, key)?
+        .ok_or(ContractError::NoAllowance)?;
+    if allowance.expires.is_expired(block) {
+        return Err(ContractError::AllowanceExpired);
+    }
+    allowance.allowance = allowance
+        .allowance
+        .checked_sub(amount)
+        .map_err(|_| ContractError::NoAllowance)?;
+    ALLOWANCES.save(storage, key, &allowance)?;
+    Ok(())
+}
+
+pub fn execute_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    require_cw20_mode(deps.storage)?;
+    let rcpt = deps.api.addr_validate(&recipient)?;
+    move_tokens(deps, &env, &info.sender, &rcpt, amount)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "transfer")
+        .add_attribute("from", info.sender)
+        .add_attribute("to", recipient)
+        .add_attribute("amount", amount))
+}
+
+pub fn execute_send(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    amount: Uint128,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    require_cw20_mode(deps.storage)?;
+    let rcpt = deps.api.addr_validate(&contract)?;
+    move_tokens(deps, &env, &info.sender, &rcpt, amount)?;
+
+    let hook = Cw20ReceiveMsg {
+        sender: info.sender.to_string(),
+        amount,
+        msg,
+    }
+    .into_cosmos_msg(contract.clone())?;
+    Ok(Response::new()
+        .add_message(hook)
+        .add_attribute("action", "send")
+        .add_attribute("from", info.sender)
+        .add_attribute("to", contract)
+        .add_attribute("amount", amount))
+}
+
+pub fn execute_burn(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    require_cw20_mode(deps.storage)?;
+    let refund = burn_tokens(deps, &env, &info.sender, amount)?;
+
+    Ok(Response::new()
+        .add_messages(refund)
+        .add_attribute("action", "burn")
+        .add_attribute("from", info.sender)
+        .add_attribute("amount", amount))
+}
+
+pub fn execute_increase_allowance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    require_cw20_mode(deps.storage)?;
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if spender_addr == info.sender {
+        return Err(ContractError::CannotSetOwnAccount);
+    }
+    let key = (info.sender.as_str(), spender_addr.as_str());
+    let mut allowance = ALLOWANCES.may_load(deps.storage, key)?.unwrap_or_default();
+    if let Some(exp) = expires {
+        if exp.is_expired(&env.block) {
+            return Err(ContractError::AllowanceExpired);
+        }
+        allowance.expires = exp;
+    }
+    allowance.allowance = allowance.allowance.checked_add(amount)?;
+    ALLOWANCES.save(deps.storage, key, &allowance)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "increase_allowance")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("amount", amount))
+}
+
+pub fn execute_decrease_allowance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    require_cw20_mode(deps.storage)?;
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if spender_addr == info.sender {
+        return Err(ContractError::CannotSetOwnAccount);
+    }
+    let key = (info.sender.as_str(), spender_addr.as_str());
+    let mut allowance = ALLOWANCES.may_load(deps.storage, key)?.unwrap_or_default();
+    if amount < allowance.allowance {
+        allowance.allowance -= amount;
+        if let Some(exp) = expires {
+            if exp.is_expired(&env.block) {
+                return Err(ContractError::AllowanceExpired);
+            }
+            allowance.expires = exp;
+        }
+        ALLOWANCES.save(deps.storage, key, &allowance)?;
+    } else {
+        ALLOWANCES.remove(deps.storage, key);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "decrease_allowance")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("amount", amount))
+}
+
+pub fn execute_transfer_from(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    recipient: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    require_cw20_mode(deps.storage)?;
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let rcpt = deps.api.addr_validate(&recipient)?;
+    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    move_tokens(deps, &env, &owner_addr, &rcpt, amount)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "transfer_from")
+        .add_attribute("from", owner)
+        .add_attribute("to", recipient)
+        .add_attribute("by", info.sender)
+        .add_attribute("amount", amount))
+}
+
+pub fn execute_send_from(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    contract: String,
+    amount: Uint128,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    require_cw20_mode(deps.storage)?;
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let rcpt = deps.api.addr_validate(&contract)?;
+    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    move_tokens(deps, &env, &owner_addr, &rcpt, amount)?;
+
+    let hook = Cw20ReceiveMsg {
+        sender: info.sender.to_string(),
+        amount,
+        msg,
+    }
+    .into_cosmos_msg(contract.clone())?;
+    Ok(Response::new()
+        .add_message(hook)
+        .add_attribute("action", "send_from")
+        .add_attribute("from", owner)
+        .add_attribute("to", contract)
+        .add_attribute("by", info.sender)
+        .add_attribute("amount", amount))
+}
+
+pub fn execute_burn_from(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    require_cw20_mode(deps.storage)?;
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    let refund = burn_tokens(deps, &env, &owner_addr, amount)?;
+
+    Ok(Response::new()
+        .add_messages(refund)
+        .add_attribute("action", "burn_from")
+        .add_attribute("from", owner)
+        .add_attribute("by", info.sender)
+        .add_attribute("amount", amount))
+}
+
+/// Book a bank send of the bond denom between two holders. Sends to or from the series itself
+/// are mints, burns and redemption deposits, which `Buy` and `RedeemAtMaturity` already book.
+pub fn sudo_block_before_send(
+    deps: DepsMut,
+    env: Env,
+    from: String,
+    to: String,
+    amount: Coin,
+) -> Result<Response, ContractError> {
+    let series = &env.contract.address;
+    if !is_token_factory(&CONFIG.load(deps.storage)?) || amount.denom != bond_denom(series) {
+        return Err(ContractError::InvalidDenom);
+    }
+    if from == series.as_str() || to == series.as_str() || amount.amount.is_zero() {
+        return Ok(Response::new());
+    }
+    let from_addr = deps.api.addr_validate(&from)?;
+    let to_addr = deps.api.addr_validate(&to)?;
+    move_tokens(deps, &env, &from_addr, &to_addr, amount.amount)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "bond_transfer")
+        .add_attribute("from", from)
+        .add_attribute("to", to)
+        .add_attribute("amount", amount.amount))
+}
+
+pub fn query_token_info(deps: Deps) -> StdResult<TokenInfoResponse> {
+    let st = STATE.load(deps.storage)?;
+    Ok(TokenInfoResponse {
+        name: TOKEN_NAME.to_string(),
+        symbol: TOKEN_SYMBOL.to_string(),
+        decimals: TOKEN_DECIMALS,
+        total_supply: st.total_supply,
+    })
+}
+
+pub fn query_allowance(deps: Deps, owner: String, spender: String) -> StdResult<AllowanceResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let spender = deps.api.addr_validate(&spender)?;
+    Ok(ALLOWANCES
+        .may_load(deps.storage, (owner.as_str(), spender.as_str()))?
+        .unwrap_or_default())
+}
+
+pub fn query_all_allowances(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllAllowancesResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let allowances = ALLOWANCES
+        .prefix(owner.as_str())
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (spender, a) = item?;
+            Ok(AllowanceInfo {
+                spender,
+                allowance: a.allowance,
+                expires: a.expires,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(AllAllowancesResponse { allowances })
+}
+
+pub fn query_all_accounts(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllAccountsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let accounts = ACCOUNTS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+    Ok(AllAccountsResponse { accounts })
+}
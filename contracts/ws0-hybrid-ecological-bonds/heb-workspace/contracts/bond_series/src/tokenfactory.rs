@@ -0,0 +1,90 @@
+//! TokenFactory messages for `TokenMode::TokenFactory`.
+//!
+//! The series owns `factory/<series>/heb`: it mints into its own balance and forwards to the buyer,
+//! and burns redeemed tokens after holders send them in. Messages are protobuf-encoded Stargate
+//! messages using the Osmosis `tokenfactory.v1beta1` type URLs.
+
+use cosmwasm_std::{Addr, Binary, Coin, CosmosMsg};
+use heb_types::TokenMode;
+
+use crate::state::Config;
+
+pub const BOND_SUBDENOM: &str = "heb";
+
+pub const MSG_CREATE_DENOM: &str = "/osmosis.tokenfactory.v1beta1.MsgCreateDenom";
+pub const MSG_MINT: &str = "/osmosis.tokenfactory.v1beta1.MsgMint";
+pub const MSG_BURN: &str = "/osmosis.tokenfactory.v1beta1.MsgBurn";
+pub const MSG_SET_BEFORE_SEND_HOOK: &str = "/osmosis.tokenfactory.v1beta1.MsgSetBeforeSendHook";
+
+pub fn is_token_factory(cfg: &Config) -> bool {
+    matches!(cfg.terms.token_mode, Some(TokenMode::TokenFactory))
+}
+
+/// Native denom of the series' bond token.
+pub fn bond_denom(series: &Addr) -> String {
+    format!("factory/{series}/{BOND_SUBDENOM}")
+}
+
+fn push_varint(out: &mut Vec<u8>, mut v: usize) {
+    while v >= 0x80 {
+        out.push((v as u8 & 0x7f) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+/// Length-delimited field `field` (wire type 2).
+fn push_bytes(out: &mut Vec<u8>, field: u8, bytes: &[u8]) {
+    out.push((field << 3) | 2);
+    push_varint(out, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+/// `cosmos.base.v1beta1.Coin { denom = 1, amount = 2 }`
+fn encode_coin(coin: &Coin) -> Vec<u8> {
+    let mut out = vec![];
+    push_bytes(&mut out, 1, coin.denom.as_bytes());
+    push_bytes(&mut out, 2, coin.amount.to_string().as_bytes());
+    out
+}
+
+fn stargate(type_url: &str, value: Vec<u8>) -> CosmosMsg {
+    CosmosMsg::Stargate {
+        type_url: type_url.to_string(),
+        value: Binary::from(value),
+    }
+}
+
+/// `MsgCreateDenom { sender = 1, subdenom = 2 }`
+pub fn create_denom_msg(sender: &Addr) -> CosmosMsg {
+    let mut value = vec![];
+    push_bytes(&mut value, 1, sender.as_bytes());
+    push_bytes(&mut value, 2, BOND_SUBDENOM.as_bytes());
+    stargate(MSG_CREATE_DENOM, value)
+}
+
+/// `MsgSetBeforeSendHook { sender = 1, denom = 2, cosmwasm_address = 3 }`, pointing the hook at
+/// the series itself.
+pub fn set_before_send_hook_msg(sender: &Addr) -> CosmosMsg {
+    let mut value = vec![];
+    push_bytes(&mut value, 1, sender.as_bytes());
+    push_bytes(&mut value, 2, bond_denom(sender).as_bytes());
+    push_bytes(&mut value, 3, sender.as_bytes());
+    stargate(MSG_SET_BEFORE_SEND_HOOK, value)
+}
+
+/// `MsgMint { sender = 1, amount = 2 }`, minting into the sender's own balance.
+pub fn mint_msg(sender: &Addr, amount: &Coin) -> CosmosMsg {
+    let mut value = vec![];
+    push_bytes(&mut value, 1, sender.as_bytes());
+    push_bytes(&mut value, 2, &encode_coin(amount));
+    stargate(MSG_MINT, value)
+}
+
+/// `MsgBurn { sender = 1, amount = 2 }`, burning from the sender's own balance.
+pub fn burn_msg(sender: &Addr, amount: &Coin) -> CosmosMsg {
+    let mut value = vec![];
+    push_bytes(&mut value, 1, sender.as_bytes());
+    push_bytes(&mut value, 2, &encode_coin(amount));
+    stargate(MSG_BURN, value)
+}
@@ -1,4 +1,20 @@
+use std::fmt;
+
 use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{StdError, StdResult, Uint128};
+
+/// `major.minor.patch` of a cw2 contract version, for ordering migrations.
+pub fn parse_version(version: &str) -> StdResult<(u64, u64, u64)> {
+    let parts = version
+        .split('.')
+        .map(|p| p.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| StdError::generic_err(format!("version {version}: {e}")))?;
+    match parts[..] {
+        [major, minor, patch] => Ok((major, minor, patch)),
+        _ => Err(StdError::generic_err(format!("version {version}: expected major.minor.patch"))),
+    }
+}
 
 #[cw_serde]
 pub struct BandPriceConfig {
@@ -18,11 +34,97 @@ pub enum ImpactMode {
     BandOracleScript,
 }
 
+#[cw_serde]
+pub enum TokenMode {
+    /// Bond token balances live in the series contract and move through its CW20 interface
+    Cw20,
+    /// The series mints and burns a native `factory/<series>/heb` denom; holders move it with
+    /// bank sends, which the series tracks through a TokenFactory before-send hook
+    TokenFactory,
+}
+
+/// Series lifecycle. `can_transition_to` is the transition table.
+#[cw_serde]
+#[derive(Copy)]
+pub enum SeriesStatus {
+    /// instantiated, no collateral posted yet
+    Created,
+    /// collateral posted, sale not opened yet
+    Collateralized,
+    SaleOpen,
+    /// sale closed before any bond was sold; it can reopen or be cancelled
+    SaleClosed,
+    /// sale over, bonds outstanding and accruing interest
+    Active,
+    /// past `maturity_ts` with bonds outstanding
+    Matured,
+    /// borrower failed to repay after maturity
+    Defaulted,
+    /// a liquidation ran and the position is still below `liquidation_ratio_bps`
+    Liquidating,
+    /// all principal and interest repaid, at maturity or by calling every bond
+    Settled,
+    /// closed before any bond was sold
+    Cancelled,
+}
+
+impl SeriesStatus {
+    pub fn can_transition_to(self, next: SeriesStatus) -> bool {
+        use SeriesStatus::*;
+        matches!(
+            (self, next),
+            (Created, Collateralized | Cancelled)
+                | (Collateralized, SaleOpen | Cancelled)
+                | (SaleOpen, SaleClosed | Active | Matured | Liquidating)
+                | (SaleClosed, SaleOpen | Cancelled)
+                | (Active, Matured | Liquidating | Settled)
+                | (Matured, Liquidating | Defaulted | Settled)
+                | (Liquidating, Active | Matured | Defaulted | Settled)
+                | (Defaulted, Settled)
+        )
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SeriesStatus::Created => "created",
+            SeriesStatus::Collateralized => "collateralized",
+            SeriesStatus::SaleOpen => "sale_open",
+            SeriesStatus::SaleClosed => "sale_closed",
+            SeriesStatus::Active => "active",
+            SeriesStatus::Matured => "matured",
+            SeriesStatus::Defaulted => "defaulted",
+            SeriesStatus::Liquidating => "liquidating",
+            SeriesStatus::Settled => "settled",
+            SeriesStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl fmt::Display for SeriesStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// From `ts` until the next call date the borrower may call bonds at par plus `premium_bps`.
+#[cw_serde]
+pub struct CallDate {
+    pub ts: u64,
+    pub premium_bps: u32,
+}
+
+/// Principal due on `ts`, as bps of the principal sold.
+#[cw_serde]
+pub struct Installment {
+    pub ts: u64,
+    pub principal_bps: u32,
+}
+
 #[cw_serde]
 pub struct ImpactCheckpoint {
     pub ts: u64,
-    /// Target retired credits by this checkpoint (micro units / chain units)
-    pub target_retired: String,
+    /// Target retired credits by this checkpoint, in micro-credits (1 credit = 1_000_000)
+    pub target_retired: Uint128,
 }
 
 #[cw_serde]
@@ -40,7 +142,7 @@ pub struct SeriesTerms {
     pub borrower: String,
     pub collateral_denom: String,
     pub principal_denom: String,
-    pub principal_cap: String,
+    pub principal_cap: Uint128,
     pub maturity_ts: u64,
     pub base_rate_apr_bps: u32,
     pub penalty_rate_apr_bps: u32,
@@ -48,6 +150,28 @@ pub struct SeriesTerms {
     pub initial_collateral_ratio_bps: u32,
     pub liquidation_ratio_bps: u32,
     pub liquidation_bonus_bps: u32,
+    /// Max share of outstanding debt a single liquidation may repay (bps). Defaults to 50%.
+    #[serde(default)]
+    pub close_factor_bps: Option<u32>,
+    /// How bond tokens are represented. Defaults to `Cw20`.
+    #[serde(default)]
+    pub token_mode: Option<TokenMode>,
+    /// Time after `maturity_ts` the borrower has to repay before the series can be declared in
+    /// default. Defaults to 7 days.
+    #[serde(default)]
+    pub default_grace_period_seconds: Option<u64>,
+    /// Time after each coupon date the borrower has to fund the coupon before the penalty APR
+    /// applies. Defaults to 3 days.
+    #[serde(default)]
+    pub coupon_grace_period_seconds: Option<u64>,
+    /// Dates from which the borrower may call bonds early, in increasing order and before
+    /// `maturity_ts`. Not callable when unset.
+    #[serde(default)]
+    pub call_schedule: Option<Vec<CallDate>>,
+    /// Scheduled principal installments in increasing date order, the last no later than
+    /// `maturity_ts`; whatever they leave is repaid at maturity. Bullet repayment when unset.
+    #[serde(default)]
+    pub amortization: Option<Vec<Installment>>,
     pub oracle: BandPriceConfig,
     pub impact: ImpactConfig,
 }
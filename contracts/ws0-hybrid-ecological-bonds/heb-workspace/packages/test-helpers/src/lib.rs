@@ -0,0 +1,430 @@
+//! cw-multi-test harness running `bond_factory` and `bond_series` together.
+//!
+//! [`Suite`] wires both contracts into one [`App`] with funded accounts, creates series through
+//! the factory's reply path and stands in for the two chain services the series talks to: the
+//! Band oracle over IBC (see [`Suite::set_price`]) and the ecocredit `Supply` query (see
+//! [`Suite::set_retired`]). Dev-only; add it under `[dev-dependencies]`.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result as AnyResult};
+use cosmwasm_std::testing::{mock_ibc_packet_ack, mock_ibc_packet_recv, MockApi, MockStorage};
+use cosmwasm_std::{
+    coins, from_json, to_json_binary, Addr, Api, Binary, BlockInfo, Coin, Decimal256, DepsMut,
+    Empty, Env, IbcAcknowledgement, IbcPacketAckMsg, IbcPacketReceiveMsg, Querier, Response,
+    Storage, Uint128,
+};
+use cw_multi_test::{
+    App, AppBuilder, AppResponse, BankKeeper, Contract, ContractWrapper, DistributionKeeper,
+    Executor, FailingModule, GovFailingModule, IbcAcceptingModule, StakeKeeper, Stargate,
+    WasmKeeper,
+};
+use heb_types::{BandPriceConfig, ImpactConfig, ImpactMode, SeriesTerms};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use bond_series::ecocredit::{QuerySupplyResponse, SUPPLY_QUERY_PATH};
+use bond_series::error::ContractError;
+use bond_series::msg::{ExecuteMsg, QueryMsg, StateResponse, SudoMsg};
+use bond_series::oracle::{
+    AcknowledgementMsg, BandAcknowledgement, OracleRequestPacketData, OracleResponsePacketData,
+    ResolveStatus, PRICE_MULTIPLIER,
+};
+
+pub const ADMIN: &str = "admin";
+pub const BORROWER: &str = "borrower";
+pub const FEE_RECIPIENT: &str = "fees";
+pub const LENDER: &str = "lender";
+pub const LENDER2: &str = "lender2";
+pub const LIQUIDATOR: &str = "liquidator";
+pub const KEEPER: &str = "keeper";
+pub const COLLATERAL: &str = "uregen";
+pub const PRINCIPAL: &str = "ibc/USDC";
+pub const BAND_CHANNEL: &str = "channel-0";
+pub const YEAR: u64 = bond_series::math::SECONDS_PER_YEAR;
+
+/// Starting bank balance of every account in both denoms.
+pub const STARTING_BALANCE: u128 = 100_000_000;
+
+/// Stand-in for the ecocredit module's `Supply` query.
+#[derive(Default)]
+pub struct EcocreditSupply {
+    /// retired amount (decimal credits) per batch denom; unknown batches error
+    pub retired: HashMap<String, String>,
+}
+
+impl Stargate for EcocreditSupply {
+    fn query(
+        &self,
+        _api: &dyn Api,
+        _storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        path: String,
+        data: Binary,
+    ) -> AnyResult<Binary> {
+        if path != SUPPLY_QUERY_PATH {
+            bail!("unexpected stargate query {path}");
+        }
+        // QuerySupplyRequest { batch_denom = 1 } with a one-byte length
+        let batch = String::from_utf8(data[2..].to_vec())?;
+        let retired = self
+            .retired
+            .get(&batch)
+            .ok_or_else(|| anyhow!("batch {batch} not found"))?;
+        Ok(to_json_binary(&QuerySupplyResponse {
+            tradable_amount: "0".to_string(),
+            retired_amount: retired.clone(),
+            cancelled_amount: "0".to_string(),
+        })?)
+    }
+}
+
+pub type HebApp = App<
+    BankKeeper,
+    MockApi,
+    MockStorage,
+    FailingModule<Empty, Empty, Empty>,
+    WasmKeeper<Empty, Empty>,
+    StakeKeeper,
+    DistributionKeeper,
+    IbcAcceptingModule,
+    GovFailingModule,
+    EcocreditSupply,
+>;
+
+/// Sudo messages the harness sends the series: the chain's own, plus packets it relays.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SeriesSudo {
+    Chain(SudoMsg),
+    Relayer(RelayerMsg),
+}
+
+/// Multi-test has no IBC relayer, so packets reach the series' IBC entry points through sudo.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayerMsg {
+    IbcPacketReceive(IbcPacketReceiveMsg),
+    IbcPacketAck(IbcPacketAckMsg),
+}
+
+fn series_sudo(deps: DepsMut, env: Env, msg: SeriesSudo) -> Result<Response, ContractError> {
+    match msg {
+        SeriesSudo::Chain(msg) => bond_series::sudo(deps, env, msg),
+        SeriesSudo::Relayer(RelayerMsg::IbcPacketReceive(msg)) => {
+            let res = bond_series::ibc::ibc_packet_receive(deps, env, msg)
+                .unwrap_or_else(|never| match never {});
+            Ok(Response::new()
+                .add_submessages(res.messages)
+                .add_attributes(res.attributes)
+                .add_events(res.events)
+                .set_data(res.acknowledgement))
+        }
+        SeriesSudo::Relayer(RelayerMsg::IbcPacketAck(msg)) => {
+            let res = bond_series::ibc::ibc_packet_ack(deps, env, msg)?;
+            Ok(Response::new()
+                .add_submessages(res.messages)
+                .add_attributes(res.attributes)
+                .add_events(res.events))
+        }
+    }
+}
+
+pub fn series_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(
+        ContractWrapper::new(
+            bond_series::execute,
+            bond_series::instantiate,
+            bond_series::query,
+        )
+        .with_sudo(series_sudo)
+        .with_migrate(bond_series::migrate),
+    )
+}
+
+pub fn factory_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(
+        ContractWrapper::new(
+            bond_factory::execute,
+            bond_factory::instantiate,
+            bond_factory::query,
+        )
+        .with_reply(bond_factory::reply)
+        .with_migrate(bond_factory::migrate),
+    )
+}
+
+/// Factory and series code on one chain, with every named account funded in both denoms.
+pub struct Suite {
+    pub app: HebApp,
+    pub factory: Addr,
+    pub series_code_id: u64,
+}
+
+impl Suite {
+    /// Factory allowing `PRINCIPAL` with a 25_000 bps minimum initial collateral ratio and no
+    /// protocol fee.
+    pub fn new() -> Self {
+        Self::with_fee(0)
+    }
+
+    pub fn with_fee(protocol_fee_bps: u32) -> Self {
+        let mut app = AppBuilder::new()
+            .with_ibc(IbcAcceptingModule::new())
+            .with_stargate(EcocreditSupply::default())
+            .build(|router, _api, storage| {
+                for who in [ADMIN, BORROWER, LENDER, LENDER2, LIQUIDATOR, KEEPER] {
+                    let funds = vec![
+                        Coin::new(STARTING_BALANCE, COLLATERAL),
+                        Coin::new(STARTING_BALANCE, PRINCIPAL),
+                    ];
+                    router
+                        .bank
+                        .init_balance(storage, &Addr::unchecked(who), funds)
+                        .unwrap();
+                }
+            });
+        let series_code_id = app.store_code(series_contract());
+        let factory_code_id = app.store_code(factory_contract());
+        let factory = app
+            .instantiate_contract(
+                factory_code_id,
+                Addr::unchecked(ADMIN),
+                &bond_factory::msg::InstantiateMsg {
+                    admin: ADMIN.to_string(),
+                    allowed_principal_denoms: vec![PRINCIPAL.to_string()],
+                    min_initial_collateral_ratio_bps: 25_000,
+                    protocol_fee_bps,
+                    fee_recipient: FEE_RECIPIENT.to_string(),
+                    bond_series_code_id: series_code_id,
+                },
+                &[],
+                "heb-bond-factory",
+                Some(ADMIN.to_string()),
+            )
+            .unwrap();
+        Suite {
+            app,
+            factory,
+            series_code_id,
+        }
+    }
+
+    pub fn now(&self) -> u64 {
+        self.app.block_info().time.seconds()
+    }
+
+    /// Move block time (and height) forward by `seconds`.
+    pub fn advance(&mut self, seconds: u64) {
+        self.app.update_block(|block| {
+            block.time = block.time.plus_seconds(seconds);
+            block.height += seconds / 5;
+        });
+    }
+
+    /// 10_000 principal cap, 10% base APR plus 5% penalty, two years to maturity, 25_000 bps
+    /// initial and 15_000 bps liquidation ratio.
+    pub fn terms(&self) -> SeriesTerms {
+        SeriesTerms {
+            borrower: BORROWER.to_string(),
+            collateral_denom: COLLATERAL.to_string(),
+            principal_denom: PRINCIPAL.to_string(),
+            principal_cap: Uint128::new(10_000),
+            maturity_ts: self.now() + 2 * YEAR,
+            base_rate_apr_bps: 1_000,
+            penalty_rate_apr_bps: 500,
+            coupon_period_seconds: None,
+            initial_collateral_ratio_bps: 25_000,
+            liquidation_ratio_bps: 15_000,
+            liquidation_bonus_bps: 500,
+            close_factor_bps: None,
+            token_mode: None,
+            default_grace_period_seconds: None,
+            coupon_grace_period_seconds: None,
+            call_schedule: None,
+            amortization: None,
+            oracle: BandPriceConfig {
+                band_ibc_channel: BAND_CHANNEL.to_string(),
+                regen_price_script_id: 1,
+                max_price_age_seconds: 3_600,
+            },
+            impact: ImpactConfig {
+                mode: ImpactMode::OnChainEcocreditBatches,
+                batch_ids: vec![],
+                band_impact_script_id: None,
+                checkpoints: vec![],
+            },
+        }
+    }
+
+    /// `CreateSeries` on the factory; returns the address its reply registered.
+    pub fn create_series(&mut self, sender: &str, terms: SeriesTerms) -> AnyResult<Addr> {
+        let res = self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.factory.clone(),
+            &bond_factory::msg::ExecuteMsg::CreateSeries {
+                terms: Box::new(terms),
+            },
+            &[],
+        )?;
+        let addr = attribute(&res, "series_created", "address")
+            .ok_or_else(|| anyhow!("no series_created attribute"))?;
+        Ok(Addr::unchecked(addr))
+    }
+
+    pub fn execute(
+        &mut self,
+        sender: &str,
+        series: &Addr,
+        msg: &ExecuteMsg,
+        funds: &[Coin],
+    ) -> AnyResult<AppResponse> {
+        self.app
+            .execute_contract(Addr::unchecked(sender), series.clone(), msg, funds)
+    }
+
+    pub fn query<T: DeserializeOwned>(&self, series: &Addr, msg: &QueryMsg) -> T {
+        self.app.wrap().query_wasm_smart(series, msg).unwrap()
+    }
+
+    pub fn state(&self, series: &Addr) -> StateResponse {
+        self.query(series, &QueryMsg::State {})
+    }
+
+    /// Bond tokens held by `who`.
+    pub fn token_balance(&self, series: &Addr, who: &str) -> u128 {
+        let res: bond_series::msg::BalanceResponse = self.query(
+            series,
+            &QueryMsg::Balance {
+                address: who.to_string(),
+            },
+        );
+        res.balance.u128()
+    }
+
+    /// Bank balance of `who` (an account name or contract address) in `denom`.
+    pub fn bank_balance(&self, who: impl Into<String>, denom: &str) -> u128 {
+        self.app
+            .wrap()
+            .query_balance(who, denom)
+            .unwrap()
+            .amount
+            .u128()
+    }
+
+    pub fn deposit(&mut self, series: &Addr, amount: u128) -> AnyResult<AppResponse> {
+        self.execute(
+            BORROWER,
+            series,
+            &ExecuteMsg::DepositCollateral {},
+            &coins(amount, COLLATERAL),
+        )
+    }
+
+    pub fn buy(&mut self, series: &Addr, lender: &str, amount: u128) -> AnyResult<AppResponse> {
+        self.execute(
+            lender,
+            series,
+            &ExecuteMsg::Buy { min_tokens: None },
+            &coins(amount, PRINCIPAL),
+        )
+    }
+
+    /// Run the Band round trip: `UpdateOraclePrice`, BandChain's acknowledgement of the request,
+    /// then the response packet resolved and relayed at the current block time. `price` is
+    /// principal units per uregen.
+    pub fn set_price(&mut self, series: &Addr, price: Decimal256) -> AnyResult<()> {
+        let res = self.execute(KEEPER, series, &ExecuteMsg::UpdateOraclePrice {}, &[])?;
+        let client_id = attribute(&res, "update_oracle_price", "client_id")
+            .ok_or_else(|| anyhow!("no price request sent"))?;
+        // the series only reads the client id back from its own request packet
+        let req = OracleRequestPacketData {
+            client_id: client_id.clone(),
+            oracle_script_id: 1u64.into(),
+            calldata: Binary::default(),
+            ask_count: 4u64.into(),
+            min_count: 3u64.into(),
+            fee_limit: vec![],
+            prepare_gas: 0u64.into(),
+            execute_gas: 0u64.into(),
+        };
+        let ack = to_json_binary(&BandAcknowledgement {
+            request_id: 1u64.into(),
+        })?;
+        let ack = IbcAcknowledgement::new(to_json_binary(&AcknowledgementMsg::Result(ack))?);
+        let msg = SeriesSudo::Relayer(RelayerMsg::IbcPacketAck(mock_ibc_packet_ack(
+            BAND_CHANNEL,
+            &req,
+            ack,
+        )?));
+        self.app.wasm_sudo(series.clone(), &msg)?;
+        let rate = (price * Decimal256::from_ratio(PRICE_MULTIPLIER, 1u64)).to_uint_floor();
+        let mut result = 1u32.to_be_bytes().to_vec();
+        result.extend_from_slice(&rate.to_string().parse::<u64>()?.to_be_bytes());
+        let resp = OracleResponsePacketData {
+            client_id,
+            request_id: 1u64.into(),
+            ans_count: 4u64.into(),
+            request_time: self.now().into(),
+            resolve_time: self.now().into(),
+            resolve_status: ResolveStatus::Success,
+            result: Binary::from(result),
+        };
+        let msg = SeriesSudo::Relayer(RelayerMsg::IbcPacketReceive(mock_ibc_packet_recv(
+            BAND_CHANNEL,
+            &resp,
+        )?));
+        let res = self.app.wasm_sudo(series.clone(), &msg)?;
+        let ack: AcknowledgementMsg = from_json(res.data.unwrap_or_default())?;
+        match ack {
+            AcknowledgementMsg::Result(_) => Ok(()),
+            AcknowledgementMsg::Error(err) => bail!("price rejected: {err}"),
+        }
+    }
+
+    /// Report `retired` credits (decimal string) for `batch` from the ecocredit module.
+    pub fn set_retired(&mut self, batch: &str, retired: &str) {
+        self.app.init_modules(|router, _, _| {
+            router
+                .stargate
+                .retired
+                .insert(batch.to_string(), retired.to_string())
+        });
+    }
+
+    /// Series created by the admin, `collateral` deposited, a price published and the sale open.
+    pub fn open_series(&mut self, terms: SeriesTerms, collateral: u128, price: Decimal256) -> Addr {
+        let series = self.create_series(ADMIN, terms).unwrap();
+        self.deposit(&series, collateral).unwrap();
+        self.set_price(&series, price).unwrap();
+        self.execute(BORROWER, &series, &ExecuteMsg::OpenSale {}, &[])
+            .unwrap();
+        series
+    }
+}
+
+impl Default for Suite {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Value of `key` on the first wasm event carrying `action = <action>`.
+pub fn attribute(res: &AppResponse, action: &str, key: &str) -> Option<String> {
+    res.events
+        .iter()
+        .filter(|e| e.ty == "wasm")
+        .find(|e| {
+            e.attributes
+                .iter()
+                .any(|a| a.key == "action" && a.value == action)
+        })
+        .and_then(|e| e.attributes.iter().find(|a| a.key == key))
+        .map(|a| a.value.clone())
+}
+
+/// The contract error behind a failed execute.
+pub fn contract_error(err: anyhow::Error) -> ContractError {
+    err.downcast().expect("not a series contract error")
+}
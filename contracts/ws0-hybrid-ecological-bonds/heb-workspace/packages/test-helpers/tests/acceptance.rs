@@ -0,0 +1,399 @@
+//! docs/ACCEPTANCE_TESTS.md, one test per scenario, with the factory and series deployed together.
+
+use cosmwasm_std::{coins, Decimal256, Uint128};
+use heb_types::{ImpactCheckpoint, SeriesStatus};
+
+use bond_series::error::ContractError;
+use bond_series::msg::{
+    AccruedInterestResponse, AprReason, EffectiveAprResponse, ExecuteMsg, QueryMsg,
+};
+use heb_test_helpers::*;
+
+/// 0.25 principal units per uregen.
+fn price() -> Decimal256 {
+    Decimal256::percent(25)
+}
+
+/// Collateral covering the default 10_000 cap at 25_000 bps and 0.25.
+const SALE_COLLATERAL: u128 = 100_000;
+
+/// 1_000 sold to `LENDER` against 10_000 uregen: CR 25_000 bps at 0.25.
+fn position(suite: &mut Suite) -> cosmwasm_std::Addr {
+    let mut terms = suite.terms();
+    terms.principal_cap = Uint128::new(1_000);
+    let series = suite.open_series(terms, 10_000, price());
+    suite.buy(&series, LENDER, 1_000).unwrap();
+    series
+}
+
+fn accrued(suite: &Suite, series: &cosmwasm_std::Addr, who: &str) -> u128 {
+    let res: AccruedInterestResponse = suite.query(
+        series,
+        &QueryMsg::AccruedInterest {
+            address: who.to_string(),
+        },
+    );
+    res.accrued.u128()
+}
+
+fn repay(suite: &mut Suite, series: &cosmwasm_std::Addr, amount: u128) {
+    suite
+        .execute(
+            BORROWER,
+            series,
+            &ExecuteMsg::Repay {},
+            &coins(amount, PRINCIPAL),
+        )
+        .unwrap();
+}
+
+#[test]
+fn scenario_a_create_series_via_factory() {
+    let mut suite = Suite::new();
+    let terms = suite.terms();
+    let series = suite.create_series(ADMIN, terms.clone()).unwrap();
+
+    let list: bond_factory::msg::SeriesListResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.factory,
+            &bond_factory::msg::QueryMsg::SeriesList {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    let registered: Vec<_> = list.series.iter().map(|s| s.address.as_str()).collect();
+    assert_eq!(registered, vec![series.as_str()]);
+    assert_eq!(list.series[0].borrower, BORROWER);
+    let state = suite.state(&series);
+    assert_eq!(state.status, SeriesStatus::Created);
+
+    // someone else may not create a series for the borrower; the borrower may
+    let err = suite.create_series(LENDER, terms.clone()).unwrap_err();
+    assert_eq!(
+        err.downcast::<bond_factory::error::ContractError>()
+            .unwrap(),
+        bond_factory::error::ContractError::Unauthorized
+    );
+    suite.create_series(BORROWER, terms).unwrap();
+}
+
+#[test]
+fn scenario_b_deposit_collateral() {
+    let mut suite = Suite::new();
+    let series = suite.create_series(ADMIN, suite.terms()).unwrap();
+    suite.deposit(&series, 1_000_000).unwrap();
+    assert_eq!(
+        suite.state(&series).collateral_locked,
+        Uint128::new(1_000_000)
+    );
+    assert_eq!(suite.bank_balance(&series, COLLATERAL), 1_000_000);
+
+    let err = suite
+        .execute(
+            LENDER,
+            &series,
+            &ExecuteMsg::DepositCollateral {},
+            &coins(1_000, COLLATERAL),
+        )
+        .unwrap_err();
+    assert_eq!(contract_error(err), ContractError::Unauthorized);
+}
+
+#[test]
+fn scenario_c_open_sale_requires_collateral() {
+    let mut suite = Suite::new();
+    let series = suite.create_series(ADMIN, suite.terms()).unwrap();
+    suite.deposit(&series, SALE_COLLATERAL / 2).unwrap();
+    suite.set_price(&series, price()).unwrap();
+    let err = suite
+        .execute(BORROWER, &series, &ExecuteMsg::OpenSale {}, &[])
+        .unwrap_err();
+    assert_eq!(contract_error(err), ContractError::CollateralTooLow);
+
+    suite.deposit(&series, SALE_COLLATERAL / 2).unwrap();
+    suite
+        .execute(BORROWER, &series, &ExecuteMsg::OpenSale {}, &[])
+        .unwrap();
+    assert!(suite.state(&series).sale_open);
+}
+
+#[test]
+fn scenario_d_buy_mints_tokens_and_pays_proceeds() {
+    // 1% protocol fee
+    let mut suite = Suite::with_fee(100);
+    let series = suite.open_series(suite.terms(), SALE_COLLATERAL, price());
+    let borrower_before = suite.bank_balance(BORROWER, PRINCIPAL);
+
+    suite.buy(&series, LENDER, 1_000).unwrap();
+    assert_eq!(suite.token_balance(&series, LENDER), 1_000);
+    let state = suite.state(&series);
+    assert_eq!(state.total_principal_sold, Uint128::new(1_000));
+    assert_eq!(state.total_principal_outstanding, Uint128::new(1_000));
+    assert_eq!(suite.bank_balance(FEE_RECIPIENT, PRINCIPAL), 10);
+    assert_eq!(
+        suite.bank_balance(BORROWER, PRINCIPAL),
+        borrower_before + 990
+    );
+
+    let err = suite
+        .execute(
+            LENDER,
+            &series,
+            &ExecuteMsg::Buy { min_tokens: None },
+            &coins(1_000, COLLATERAL),
+        )
+        .unwrap_err();
+    assert!(matches!(contract_error(err), ContractError::Payment(_)));
+}
+
+#[test]
+fn scenario_e_cap_enforcement() {
+    let mut suite = Suite::new();
+    let series = suite.open_series(suite.terms(), SALE_COLLATERAL, price());
+    suite.buy(&series, LENDER, 9_900).unwrap();
+
+    let err = suite.buy(&series, LENDER2, 200).unwrap_err();
+    assert_eq!(
+        contract_error(err),
+        ContractError::PrincipalCapExceeded {
+            remaining: Uint128::new(100)
+        }
+    );
+    suite.buy(&series, LENDER2, 100).unwrap();
+    assert_eq!(
+        suite.state(&series).total_principal_sold,
+        Uint128::new(10_000)
+    );
+}
+
+#[test]
+fn scenario_f_interest_accrual_index() {
+    let mut suite = Suite::new();
+    let series = position(&mut suite);
+    suite.advance(YEAR);
+
+    // any state-changing message accrues; a transfer also syncs both accounts
+    let transfer = ExecuteMsg::Transfer {
+        recipient: LENDER2.to_string(),
+        amount: Uint128::new(1),
+    };
+    suite.execute(LENDER, &series, &transfer, &[]).unwrap();
+    let index = suite.state(&series).global_interest_index;
+    assert!(index > Decimal256::permille(1_099) && index < Decimal256::permille(1_101));
+    assert_eq!(accrued(&suite, &series, LENDER), 100);
+    assert_eq!(accrued(&suite, &series, LENDER2), 0);
+}
+
+#[test]
+fn scenario_g_repay_reduces_outstanding() {
+    let mut suite = Suite::new();
+    let series = suite.open_series(suite.terms(), SALE_COLLATERAL, price());
+    suite.buy(&series, LENDER, 10_000).unwrap();
+
+    repay(&mut suite, &series, 2_500);
+    assert_eq!(
+        suite.state(&series).total_principal_outstanding,
+        Uint128::new(7_500)
+    );
+
+    // clipped to outstanding, the excess refunded
+    let before = suite.bank_balance(BORROWER, PRINCIPAL);
+    repay(&mut suite, &series, 8_000);
+    assert!(suite.state(&series).total_principal_outstanding.is_zero());
+    assert_eq!(suite.bank_balance(BORROWER, PRINCIPAL), before - 7_500);
+}
+
+#[test]
+fn scenario_h_claim_interest_pays_from_contract_balance() {
+    let mut suite = Suite::new();
+    let series = position(&mut suite);
+    suite.advance(YEAR);
+
+    let err = suite
+        .execute(LENDER, &series, &ExecuteMsg::ClaimInterest {}, &[])
+        .unwrap_err();
+    assert!(matches!(
+        contract_error(err),
+        ContractError::InterestNotFunded { .. }
+    ));
+
+    repay(&mut suite, &series, 100);
+    let lender_before = suite.bank_balance(LENDER, PRINCIPAL);
+    let series_before = suite.bank_balance(&series, PRINCIPAL);
+    suite
+        .execute(LENDER, &series, &ExecuteMsg::ClaimInterest {}, &[])
+        .unwrap();
+    assert_eq!(suite.bank_balance(LENDER, PRINCIPAL), lender_before + 100);
+    assert_eq!(suite.bank_balance(&series, PRINCIPAL), series_before - 100);
+    assert_eq!(accrued(&suite, &series, LENDER), 0);
+}
+
+#[test]
+fn scenario_i_redeem_at_maturity() {
+    let mut suite = Suite::new();
+    let series = position(&mut suite);
+    let redeem = ExecuteMsg::RedeemAtMaturity {
+        amount: Uint128::new(1_000),
+    };
+    let err = suite.execute(LENDER, &series, &redeem, &[]).unwrap_err();
+    assert_eq!(contract_error(err), ContractError::NotMatured);
+
+    // outstanding falls when the borrower repays; redemption pays out of that repayment
+    suite.advance(2 * YEAR);
+    repay(&mut suite, &series, 2_000);
+    assert!(suite.state(&series).total_principal_outstanding.is_zero());
+    let before = suite.bank_balance(LENDER, PRINCIPAL);
+    suite.execute(LENDER, &series, &redeem, &[]).unwrap();
+    assert_eq!(suite.token_balance(&series, LENDER), 0);
+    assert_eq!(suite.bank_balance(LENDER, PRINCIPAL), before + 1_000);
+}
+
+#[test]
+fn scenario_j_stale_oracle_blocks_liquidation() {
+    let mut suite = Suite::new();
+    let series = position(&mut suite);
+    suite.set_price(&series, Decimal256::percent(10)).unwrap();
+    suite.advance(3_601);
+
+    let liquidate = ExecuteMsg::Liquidate {
+        max_repay: Uint128::new(100),
+    };
+    let err = suite
+        .execute(LIQUIDATOR, &series, &liquidate, &coins(100, PRINCIPAL))
+        .unwrap_err();
+    assert_eq!(contract_error(err), ContractError::OracleStale);
+
+    suite.set_price(&series, Decimal256::percent(10)).unwrap();
+    suite
+        .execute(LIQUIDATOR, &series, &liquidate, &coins(100, PRINCIPAL))
+        .unwrap();
+}
+
+#[test]
+fn scenario_k_liquidation_transfers_collateral_with_bonus() {
+    let mut suite = Suite::new();
+    let series = position(&mut suite);
+    // CR 10_000 bps at 0.1, below the 15_000 bps liquidation ratio
+    suite.set_price(&series, Decimal256::percent(10)).unwrap();
+    let liquidate = ExecuteMsg::Liquidate {
+        max_repay: Uint128::new(500),
+    };
+    suite
+        .execute(LIQUIDATOR, &series, &liquidate, &coins(500, PRINCIPAL))
+        .unwrap();
+    // 500 * 1.05 / 0.1
+    assert_eq!(
+        suite.bank_balance(LIQUIDATOR, COLLATERAL),
+        STARTING_BALANCE + 5_250
+    );
+    let state = suite.state(&series);
+    assert_eq!(state.total_principal_outstanding, Uint128::new(500));
+    assert_eq!(state.collateral_locked, Uint128::new(4_750));
+
+    // at 0.01 the rest of the collateral covers less than the repayment: it is all paid out
+    // and the remaining debt stays
+    suite.set_price(&series, Decimal256::percent(1)).unwrap();
+    suite
+        .execute(LIQUIDATOR, &series, &liquidate, &coins(500, PRINCIPAL))
+        .unwrap();
+    let state = suite.state(&series);
+    assert!(state.collateral_locked.u128() < 100);
+    assert!(state.total_principal_outstanding.u128() > 400);
+    assert!(suite.bank_balance(LIQUIDATOR, COLLATERAL) <= STARTING_BALANCE + 10_000);
+}
+
+#[test]
+fn scenario_l_impact_checkpoint_changes_apr() {
+    let mut suite = Suite::new();
+    let mut terms = suite.terms();
+    terms.impact.batch_ids = vec!["C01-001".to_string()];
+    terms.impact.checkpoints = vec![ImpactCheckpoint {
+        ts: suite.now() + YEAR / 2,
+        // 500 credits
+        target_retired: Uint128::new(500_000_000),
+    }];
+    let series = suite.open_series(terms, SALE_COLLATERAL, price());
+    suite.set_retired("C01-001", "0");
+    suite.advance(YEAR / 2);
+
+    let apr = |suite: &Suite| -> EffectiveAprResponse {
+        suite.query(&series, &QueryMsg::EffectiveApr {})
+    };
+    suite
+        .execute(KEEPER, &series, &ExecuteMsg::CheckpointImpact {}, &[])
+        .unwrap();
+    assert!(!suite.state(&series).last_impact.unwrap().met);
+    let res = apr(&suite);
+    assert_eq!(res.apr_bps, 1_500);
+    assert_eq!(res.reason, AprReason::ImpactMissed);
+
+    suite.set_retired("C01-001", "500");
+    suite
+        .execute(KEEPER, &series, &ExecuteMsg::CheckpointImpact {}, &[])
+        .unwrap();
+    assert!(suite.state(&series).last_impact.unwrap().met);
+    assert_eq!(apr(&suite).apr_bps, 1_000);
+}
+
+#[test]
+fn scenario_m_pause_behavior() {
+    let mut suite = Suite::new();
+    let series = position(&mut suite);
+    suite.advance(YEAR);
+    suite.set_price(&series, Decimal256::percent(10)).unwrap();
+    suite
+        .execute(ADMIN, &series, &ExecuteMsg::Pause {}, &[])
+        .unwrap();
+
+    let err = suite.buy(&series, LENDER2, 100).unwrap_err();
+    assert_eq!(contract_error(err), ContractError::Paused);
+    let liquidate = ExecuteMsg::Liquidate {
+        max_repay: Uint128::new(100),
+    };
+    let err = suite
+        .execute(LIQUIDATOR, &series, &liquidate, &coins(100, PRINCIPAL))
+        .unwrap_err();
+    assert_eq!(contract_error(err), ContractError::Paused);
+
+    repay(&mut suite, &series, 100);
+    suite
+        .execute(LENDER, &series, &ExecuteMsg::ClaimInterest {}, &[])
+        .unwrap();
+    suite.advance(YEAR);
+    repay(&mut suite, &series, 2_000);
+    let redeem = ExecuteMsg::RedeemAtMaturity {
+        amount: Uint128::new(1_000),
+    };
+    suite.execute(LENDER, &series, &redeem, &[]).unwrap();
+}
+
+#[test]
+fn scenario_n_denom_invariants() {
+    let mut suite = Suite::new();
+    let series = suite.open_series(suite.terms(), SALE_COLLATERAL, price());
+    suite.buy(&series, LENDER, 1_000).unwrap();
+    suite.set_price(&series, Decimal256::percent(10)).unwrap();
+
+    let wrong = [
+        (BORROWER, ExecuteMsg::DepositCollateral {}, PRINCIPAL),
+        (LENDER2, ExecuteMsg::Buy { min_tokens: None }, COLLATERAL),
+        (BORROWER, ExecuteMsg::Repay {}, COLLATERAL),
+        (
+            LIQUIDATOR,
+            ExecuteMsg::Liquidate {
+                max_repay: Uint128::new(100),
+            },
+            COLLATERAL,
+        ),
+    ];
+    for (sender, msg, denom) in wrong {
+        let err = suite
+            .execute(sender, &series, &msg, &coins(100, denom))
+            .unwrap_err();
+        let err = contract_error(err);
+        assert!(matches!(err, ContractError::Payment(_)), "{err:?}");
+    }
+}
@@ -0,0 +1,266 @@
+//! Stateful model test: random sequences of holder, borrower, liquidator and keeper actions
+//! against one callable, amortizing series, with the series invariants checked after every step.
+//!
+//! Actions may fail (stale price, sale closed, nothing to claim, ...); a failed transaction is
+//! reverted by the chain and the invariants must hold either way.
+
+use cosmwasm_std::{coins, Addr, Decimal256, Uint128};
+use proptest::prelude::*;
+
+use bond_series::msg::{CallQuoteResponse, ExecuteMsg, PendingRedemptionResponse, QueryMsg};
+use heb_test_helpers::*;
+use heb_types::{CallDate, Installment};
+
+const HOLDERS: [&str; 3] = [LENDER, LENDER2, LIQUIDATOR];
+
+#[derive(Clone, Debug)]
+enum Action {
+    Buy {
+        holder: usize,
+        amount: u128,
+    },
+    Transfer {
+        from: usize,
+        to: usize,
+        amount: u128,
+    },
+    /// also pays amortization installments
+    Repay {
+        amount: u128,
+    },
+    CallBonds {
+        amount: u128,
+    },
+    ClaimRedemption {
+        holder: usize,
+    },
+    ClaimInterest {
+        holder: usize,
+    },
+    Liquidate {
+        max_repay: u128,
+    },
+    RedeemAtMaturity {
+        holder: usize,
+        amount: u128,
+    },
+    /// principal units per uregen, in percent
+    UpdatePrice {
+        percent: u64,
+    },
+    /// seconds
+    Advance {
+        seconds: u64,
+    },
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    let holder = 0..HOLDERS.len();
+    prop_oneof![
+        (holder.clone(), 1u128..4_000).prop_map(|(holder, amount)| Action::Buy { holder, amount }),
+        (holder.clone(), holder.clone(), 1u128..2_000)
+            .prop_map(|(from, to, amount)| Action::Transfer { from, to, amount }),
+        (1u128..5_000).prop_map(|amount| Action::Repay { amount }),
+        (1u128..2_000).prop_map(|amount| Action::CallBonds { amount }),
+        holder
+            .clone()
+            .prop_map(|holder| Action::ClaimRedemption { holder }),
+        holder
+            .clone()
+            .prop_map(|holder| Action::ClaimInterest { holder }),
+        (1u128..3_000).prop_map(|max_repay| Action::Liquidate { max_repay }),
+        (holder, 1u128..2_000)
+            .prop_map(|(holder, amount)| Action::RedeemAtMaturity { holder, amount }),
+        (1u64..40).prop_map(|percent| Action::UpdatePrice { percent }),
+        (1u64..120 * 24 * 3_600).prop_map(|seconds| Action::Advance { seconds }),
+    ]
+}
+
+fn apply(suite: &mut Suite, series: &Addr, action: &Action) {
+    // failures are part of the model: the chain reverts them
+    let _ = match *action {
+        Action::Buy { holder, amount } => suite.buy(series, HOLDERS[holder], amount),
+        Action::Transfer { from, to, amount } => suite.execute(
+            HOLDERS[from],
+            series,
+            &ExecuteMsg::Transfer {
+                recipient: HOLDERS[to].to_string(),
+                amount: Uint128::new(amount),
+            },
+            &[],
+        ),
+        Action::Repay { amount } => suite.execute(
+            BORROWER,
+            series,
+            &ExecuteMsg::Repay {},
+            &coins(amount, PRINCIPAL),
+        ),
+        Action::CallBonds { amount } => {
+            // funded with exactly what the quote asks for, when the series is callable at all
+            let quote: Result<CallQuoteResponse, _> = suite.app.wrap().query_wasm_smart(
+                series,
+                &QueryMsg::CallQuote {
+                    amount: Uint128::new(amount),
+                },
+            );
+            match quote {
+                Ok(quote) if !quote.total.is_zero() => suite.execute(
+                    BORROWER,
+                    series,
+                    &ExecuteMsg::CallBonds {
+                        amount: Uint128::new(amount),
+                    },
+                    &coins(quote.total.u128(), PRINCIPAL),
+                ),
+                _ => Ok(Default::default()),
+            }
+        }
+        Action::ClaimRedemption { holder } => suite.execute(
+            HOLDERS[holder],
+            series,
+            &ExecuteMsg::ClaimRedemption {},
+            &[],
+        ),
+        Action::ClaimInterest { holder } => {
+            suite.execute(HOLDERS[holder], series, &ExecuteMsg::ClaimInterest {}, &[])
+        }
+        Action::Liquidate { max_repay } => suite.execute(
+            KEEPER,
+            series,
+            &ExecuteMsg::Liquidate {
+                max_repay: Uint128::new(max_repay),
+            },
+            &coins(max_repay, PRINCIPAL),
+        ),
+        Action::RedeemAtMaturity { holder, amount } => suite.execute(
+            HOLDERS[holder],
+            series,
+            &ExecuteMsg::RedeemAtMaturity {
+                amount: Uint128::new(amount),
+            },
+            &[],
+        ),
+        Action::UpdatePrice { percent } => suite
+            .set_price(series, Decimal256::percent(percent))
+            .map(|_| Default::default()),
+        Action::Advance { seconds } => {
+            suite.advance(seconds);
+            Ok(Default::default())
+        }
+    };
+}
+
+/// Invariants after every step; `index` carries the last global interest index seen.
+fn check(suite: &Suite, series: &Addr, index: &mut Decimal256) -> Result<(), TestCaseError> {
+    let st = suite.state(series);
+
+    let accounts: cw20::AllAccountsResponse = suite.query(
+        series,
+        &QueryMsg::AllAccounts {
+            start_after: None,
+            limit: Some(30),
+        },
+    );
+    let balances: u128 = accounts
+        .accounts
+        .iter()
+        .map(|a| suite.token_balance(series, a))
+        .sum();
+    // balances round up after a redemption, each by less than a token, until the fractions
+    // holders kept add up to whole tokens and go back into the supply
+    let holders = accounts.accounts.len() as u128;
+    prop_assert!(
+        Uint128::new(balances) >= st.total_supply
+            && Uint128::new(balances) <= st.total_supply + Uint128::new(holders),
+        "holder balances {} vs supply {}",
+        balances,
+        st.total_supply
+    );
+    let payouts: u128 = accounts
+        .accounts
+        .iter()
+        .map(|a| {
+            let res: PendingRedemptionResponse =
+                suite.query(series, &QueryMsg::PendingRedemption { address: a.clone() });
+            prop_assert!(res.payout >= res.redeemed, "{} redeemed without par", a);
+            Ok(res.payout.u128())
+        })
+        .sum::<Result<u128, TestCaseError>>()?;
+    // the premium paid on those fractions stays in the reserve
+    prop_assert!(
+        Uint128::new(payouts) <= st.redemption_reserve,
+        "pending redemption payouts {} above reserve {}",
+        payouts,
+        st.redemption_reserve
+    );
+
+    // funded interest, repaid principal and redemptions all sit in the contract until paid out
+    let liabilities = st.interest_funded + st.principal_reserve + st.redemption_reserve;
+    let held = suite.bank_balance(series, PRINCIPAL);
+    prop_assert!(
+        Uint128::new(held) >= liabilities,
+        "principal held {} below liabilities {}",
+        held,
+        liabilities
+    );
+    let collateral = suite.bank_balance(series, COLLATERAL);
+    prop_assert!(
+        Uint128::new(collateral) >= st.collateral_locked,
+        "collateral held {} below locked {}",
+        collateral,
+        st.collateral_locked
+    );
+
+    prop_assert!(
+        st.global_interest_index >= *index,
+        "interest index fell from {} to {}",
+        index,
+        st.global_interest_index
+    );
+    *index = st.global_interest_index;
+    Ok(())
+}
+
+/// Callable from two months at 2% and from six months at par; 20% of principal due after four
+/// months and another 30% after eight.
+fn terms(suite: &Suite) -> heb_types::SeriesTerms {
+    let now = suite.now();
+    let month = YEAR / 12;
+    let mut terms = suite.terms();
+    terms.call_schedule = Some(vec![
+        CallDate {
+            ts: now + 2 * month,
+            premium_bps: 200,
+        },
+        CallDate {
+            ts: now + 6 * month,
+            premium_bps: 0,
+        },
+    ]);
+    terms.amortization = Some(vec![
+        Installment {
+            ts: now + 4 * month,
+            principal_bps: 2_000,
+        },
+        Installment {
+            ts: now + 8 * month,
+            principal_bps: 3_000,
+        },
+    ]);
+    terms
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn series_invariants_hold(actions in prop::collection::vec(action(), 1..40)) {
+        let mut suite = Suite::new();
+        let series = suite.open_series(terms(&suite), 100_000, Decimal256::percent(25));
+        let mut index = suite.state(&series).global_interest_index;
+        for action in &actions {
+            apply(&mut suite, &series, action);
+            check(&suite, &series, &mut index)?;
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Factory series registry: indexed lookups, cursors and status refresh.
+
+use cosmwasm_std::Addr;
+
+use bond_factory::error::ContractError;
+use bond_factory::msg::{ExecuteMsg, QueryMsg, SeriesListResponse, SeriesResponse};
+use cw_multi_test::Executor;
+use heb_test_helpers::*;
+use heb_types::SeriesStatus;
+
+const OTHER_PRINCIPAL: &str = "ibc/OTHER";
+
+fn query_list(suite: &Suite, msg: QueryMsg) -> Vec<u64> {
+    let res: SeriesListResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.factory, &msg)
+        .unwrap();
+    res.series.into_iter().map(|s| s.id).collect()
+}
+
+fn query_series(suite: &Suite, msg: QueryMsg) -> SeriesResponse {
+    suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.factory, &msg)
+        .unwrap()
+}
+
+/// Four series: 1 and 4 for `BORROWER` in `PRINCIPAL`, 2 for `LENDER2`, 3 in `OTHER_PRINCIPAL`.
+/// Maturities in years from now: 2, 1, 3, 1.
+fn registry_suite() -> (Suite, Vec<Addr>) {
+    let mut suite = Suite::new();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(ADMIN),
+            suite.factory.clone(),
+            &ExecuteMsg::UpdateConfig {
+                admin: None,
+                allowed_principal_denoms: Some(vec![
+                    PRINCIPAL.to_string(),
+                    OTHER_PRINCIPAL.to_string(),
+                ]),
+                min_initial_collateral_ratio_bps: None,
+                protocol_fee_bps: None,
+                fee_recipient: None,
+                bond_series_code_id: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+    let now = suite.now();
+    let mut series = vec![];
+    for (borrower, denom, years) in [
+        (BORROWER, PRINCIPAL, 2),
+        (LENDER2, PRINCIPAL, 1),
+        (BORROWER, OTHER_PRINCIPAL, 3),
+        (BORROWER, PRINCIPAL, 1),
+    ] {
+        let mut terms = suite.terms();
+        terms.borrower = borrower.to_string();
+        terms.principal_denom = denom.to_string();
+        terms.maturity_ts = now + years * YEAR;
+        series.push(suite.create_series(ADMIN, terms).unwrap());
+    }
+    (suite, series)
+}
+
+#[test]
+fn records_carry_the_series_terms() {
+    let (suite, series) = registry_suite();
+    let record = query_series(&suite, QueryMsg::Series { id: 3 });
+    assert_eq!(record.address, series[2].to_string());
+    assert_eq!(record.borrower, BORROWER);
+    assert_eq!(record.principal_denom, OTHER_PRINCIPAL);
+    assert_eq!(record.maturity_ts, suite.now() + 3 * YEAR);
+    assert_eq!(record.code_id, suite.series_code_id);
+    assert_eq!(record.created_at, Some(suite.now()));
+    assert_eq!(record.status, SeriesStatus::Created);
+
+    let by_address = query_series(
+        &suite,
+        QueryMsg::SeriesByAddress {
+            address: series[1].to_string(),
+        },
+    );
+    assert_eq!(by_address.id, 2);
+    let err = suite
+        .app
+        .wrap()
+        .query_wasm_smart::<SeriesResponse>(
+            &suite.factory,
+            &QueryMsg::SeriesByAddress {
+                address: LENDER.to_string(),
+            },
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("not found"), "{err}");
+}
+
+#[test]
+fn lists_page_by_id() {
+    let (suite, _) = registry_suite();
+    let list = |start_after, limit| query_list(&suite, QueryMsg::SeriesList { start_after, limit });
+    assert_eq!(list(None, None), vec![1, 2, 3, 4]);
+    assert_eq!(list(None, Some(2)), vec![1, 2]);
+    assert_eq!(list(Some(2), Some(2)), vec![3, 4]);
+    assert_eq!(list(Some(4), None), Vec::<u64>::new());
+}
+
+#[test]
+fn looks_up_by_borrower_and_denom() {
+    let (suite, _) = registry_suite();
+    let by_borrower = |borrower: &str, start_after, limit| {
+        query_list(
+            &suite,
+            QueryMsg::SeriesByBorrower {
+                borrower: borrower.to_string(),
+                start_after,
+                limit,
+            },
+        )
+    };
+    assert_eq!(by_borrower(BORROWER, None, None), vec![1, 3, 4]);
+    assert_eq!(by_borrower(BORROWER, Some(1), Some(1)), vec![3]);
+    assert_eq!(by_borrower(LENDER2, None, None), vec![2]);
+    assert_eq!(by_borrower(LENDER, None, None), Vec::<u64>::new());
+
+    let by_denom = |denom: &str, start_after| {
+        query_list(
+            &suite,
+            QueryMsg::SeriesByDenom {
+                denom: denom.to_string(),
+                start_after,
+                limit: None,
+            },
+        )
+    };
+    assert_eq!(by_denom(PRINCIPAL, None), vec![1, 2, 4]);
+    assert_eq!(by_denom(PRINCIPAL, Some(2)), vec![4]);
+    assert_eq!(by_denom(OTHER_PRINCIPAL, None), vec![3]);
+}
+
+#[test]
+fn ranges_by_maturity() {
+    let (suite, _) = registry_suite();
+    let now = suite.now();
+    let by_maturity = |from, to, start_after, limit| {
+        query_list(
+            &suite,
+            QueryMsg::SeriesByMaturity {
+                from,
+                to,
+                start_after,
+                limit,
+            },
+        )
+    };
+    // ordered by maturity, then id
+    assert_eq!(by_maturity(None, None, None, None), vec![2, 4, 1, 3]);
+    assert_eq!(
+        by_maturity(Some(now + YEAR), Some(now + 2 * YEAR), None, None),
+        vec![2, 4, 1]
+    );
+    assert_eq!(
+        by_maturity(Some(now + YEAR + 1), None, None, None),
+        vec![1, 3]
+    );
+    assert_eq!(by_maturity(None, Some(now + YEAR), None, None), vec![2, 4]);
+    // the cursor continues within a shared maturity and across to the next
+    assert_eq!(by_maturity(None, None, Some(2), Some(2)), vec![4, 1]);
+    assert_eq!(by_maturity(None, None, Some(1), None), vec![3]);
+    // a cursor before `from` does not widen the range
+    assert_eq!(
+        by_maturity(Some(now + 2 * YEAR), None, Some(2), None),
+        vec![1, 3]
+    );
+}
+
+#[test]
+fn refresh_copies_series_status() {
+    let (mut suite, series) = registry_suite();
+    suite.deposit(&series[0], 1_000).unwrap();
+    let status = |suite: &Suite| query_series(suite, QueryMsg::Series { id: 1 }).status;
+    assert_eq!(status(&suite), SeriesStatus::Created);
+
+    // anyone may refresh
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(LENDER),
+            suite.factory.clone(),
+            &ExecuteMsg::RefreshSeries { ids: vec![1, 2] },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(status(&suite), SeriesStatus::Collateralized);
+    assert_eq!(
+        query_series(&suite, QueryMsg::Series { id: 2 }).status,
+        SeriesStatus::Created
+    );
+
+    let err = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(LENDER),
+            suite.factory.clone(),
+            &ExecuteMsg::RefreshSeries { ids: vec![5] },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::SeriesNotFound { id: 5 }
+    );
+}
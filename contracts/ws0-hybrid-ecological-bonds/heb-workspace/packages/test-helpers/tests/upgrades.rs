@@ -0,0 +1,238 @@
+//! Factory-driven series upgrades.
+
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult,
+    WasmMsg,
+};
+use serde::{Deserialize, Serialize};
+
+use bond_factory::error::ContractError;
+use bond_factory::migrations::v0_1::SERIES;
+use bond_factory::msg::{ExecuteMsg, MigrateMsg, QueryMsg, SeriesResponse};
+use bond_factory::state::{Config, CONFIG, SERIES_COUNT};
+use cw_multi_test::{Contract, ContractWrapper, Executor};
+use heb_test_helpers::*;
+use heb_types::SeriesStatus;
+
+fn migrate_series(
+    suite: &mut Suite,
+    sender: &str,
+    ids: Vec<u64>,
+    new_code_id: u64,
+) -> anyhow::Result<()> {
+    let msg = ExecuteMsg::MigrateSeries {
+        ids,
+        new_code_id,
+        msg: to_json_binary(&bond_series::msg::MigrateMsg {}).unwrap(),
+    };
+    suite
+        .app
+        .execute_contract(Addr::unchecked(sender), suite.factory.clone(), &msg, &[])?;
+    Ok(())
+}
+
+fn recorded_code_id(suite: &Suite, id: u64) -> u64 {
+    let res: SeriesResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.factory, &QueryMsg::Series { id })
+        .unwrap();
+    res.code_id
+}
+
+#[test]
+fn factory_migrates_a_batch_of_series() {
+    let mut suite = Suite::new();
+    let first = suite.create_series(ADMIN, suite.terms()).unwrap();
+    let second = suite.create_series(ADMIN, suite.terms()).unwrap();
+    let old_code_id = suite.series_code_id;
+    assert_eq!(recorded_code_id(&suite, 1), old_code_id);
+    let info = suite.app.wrap().query_wasm_contract_info(&first).unwrap();
+    assert_eq!(info.admin, Some(suite.factory.to_string()));
+
+    let new_code_id = suite.app.store_code(series_contract());
+    let err = migrate_series(&mut suite, LENDER, vec![1, 2], new_code_id).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::Unauthorized
+    );
+    let err = migrate_series(&mut suite, ADMIN, vec![1, 3], new_code_id).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::SeriesNotFound { id: 3 }
+    );
+    // the failed batch changed nothing
+    assert_eq!(recorded_code_id(&suite, 1), old_code_id);
+
+    migrate_series(&mut suite, ADMIN, vec![1, 2], new_code_id).unwrap();
+    for (id, series) in [(1, &first), (2, &second)] {
+        let info = suite.app.wrap().query_wasm_contract_info(series).unwrap();
+        assert_eq!(info.code_id, new_code_id);
+        assert_eq!(recorded_code_id(&suite, id), new_code_id);
+    }
+    // the series keeps working on the new code
+    suite.deposit(&first, 1_000).unwrap();
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct LegacyFactoryMsg {
+    config: Config,
+    series: Vec<String>,
+}
+
+/// Stand-in for factory v0.1: instantiate writes its storage layout, cw2 version included.
+fn legacy_factory() -> Box<dyn Contract<Empty>> {
+    fn instantiate(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: LegacyFactoryMsg,
+    ) -> StdResult<Response> {
+        cw2::set_contract_version(deps.storage, "heb-bond-factory", "0.1.0")?;
+        CONFIG.save(deps.storage, &msg.config)?;
+        SERIES_COUNT.save(deps.storage, &(msg.series.len() as u64))?;
+        for (i, addr) in msg.series.into_iter().enumerate() {
+            SERIES.save(deps.storage, i as u64 + 1, &addr)?;
+        }
+        Ok(Response::new())
+    }
+    fn execute(_: DepsMut, _: Env, _: MessageInfo, _: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+    fn query(_: Deps, _: Env, _: Empty) -> StdResult<Binary> {
+        to_json_binary(&Empty {})
+    }
+    Box::new(ContractWrapper::new(execute, instantiate, query))
+}
+
+/// Swap the suite's factory for a v0.1 registry of `series`, migrated in place to the current code.
+fn migrate_legacy_registry(suite: &mut Suite, series: &[&Addr]) -> Addr {
+    // factory 0.1 left its admin account as the wasm admin of the series it created
+    let factory = suite.factory.clone();
+    for series in series {
+        set_wasm_admin(suite, &factory, series, ADMIN);
+    }
+    let legacy_code_id = suite.app.store_code(legacy_factory());
+    let config = Config {
+        admin: ADMIN.to_string(),
+        allowed_principal_denoms: vec![PRINCIPAL.to_string()],
+        min_initial_collateral_ratio_bps: 25_000,
+        protocol_fee_bps: 0,
+        fee_recipient: FEE_RECIPIENT.to_string(),
+        bond_series_code_id: suite.series_code_id,
+    };
+    let legacy = suite
+        .app
+        .instantiate_contract(
+            legacy_code_id,
+            Addr::unchecked(ADMIN),
+            &LegacyFactoryMsg {
+                config,
+                series: series.iter().map(|s| s.to_string()).collect(),
+            },
+            &[],
+            "heb-bond-factory",
+            Some(ADMIN.to_string()),
+        )
+        .unwrap();
+    let code_id = suite.app.store_code(factory_contract());
+    suite
+        .app
+        .migrate_contract(
+            Addr::unchecked(ADMIN),
+            legacy.clone(),
+            &MigrateMsg {},
+            code_id,
+        )
+        .unwrap();
+    let version = cw2::query_contract_info(&suite.app.wrap(), &legacy).unwrap();
+    assert_eq!(version.version, "0.2.0");
+    suite.factory = legacy.clone();
+    legacy
+}
+
+fn set_wasm_admin(suite: &mut Suite, admin: &Addr, series: &Addr, new_admin: &str) {
+    let msg = WasmMsg::UpdateAdmin {
+        contract_addr: series.to_string(),
+        admin: new_admin.to_string(),
+    };
+    suite.app.execute(admin.clone(), msg.into()).unwrap();
+}
+
+#[test]
+fn factory_migrates_a_v0_1_registry_in_place() {
+    let mut suite = Suite::new();
+    let first = suite.create_series(ADMIN, suite.terms()).unwrap();
+    let second = suite.create_series(ADMIN, suite.terms()).unwrap();
+    suite.deposit(&first, 1_000).unwrap();
+    migrate_legacy_registry(&mut suite, &[&first, &second]);
+    let record: SeriesResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(&suite.factory, &QueryMsg::Series { id: 1 })
+        .unwrap();
+    assert_eq!(record.address, first.to_string());
+    assert_eq!(record.borrower, BORROWER);
+    assert_eq!(record.code_id, suite.series_code_id);
+    assert_eq!(record.created_at, None);
+    assert_eq!(record.wasm_admin, Some(ADMIN.to_string()));
+    assert_eq!(record.status, SeriesStatus::Collateralized);
+    let record: SeriesResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(
+            &suite.factory,
+            &QueryMsg::SeriesByAddress {
+                address: second.to_string(),
+            },
+        )
+        .unwrap();
+    assert_eq!((record.id, record.status), (2, SeriesStatus::Created));
+
+    // numbering carries on after the migrated series
+    suite.create_series(ADMIN, suite.terms()).unwrap();
+    assert_eq!(recorded_code_id(&suite, 3), suite.series_code_id);
+}
+
+#[test]
+fn factory_only_migrates_v0_1_series_handed_over_to_it() {
+    let mut suite = Suite::new();
+    let imported = suite.create_series(ADMIN, suite.terms()).unwrap();
+    let factory = migrate_legacy_registry(&mut suite, &[&imported]);
+    let created = suite.create_series(ADMIN, suite.terms()).unwrap();
+
+    let new_code_id = suite.app.store_code(series_contract());
+    let err = migrate_series(&mut suite, ADMIN, vec![2, 1], new_code_id).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NotSeriesAdmin { id: 1 }
+    );
+    // handing it over is not enough until the registry has re-read the admin
+    set_wasm_admin(
+        &mut suite,
+        &Addr::unchecked(ADMIN),
+        &imported,
+        factory.as_str(),
+    );
+    let err = migrate_series(&mut suite, ADMIN, vec![1], new_code_id).unwrap_err();
+    assert_eq!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::NotSeriesAdmin { id: 1 }
+    );
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(LENDER),
+            factory.clone(),
+            &ExecuteMsg::RefreshSeries { ids: vec![1] },
+            &[],
+        )
+        .unwrap();
+
+    migrate_series(&mut suite, ADMIN, vec![1, 2], new_code_id).unwrap();
+    for (id, series) in [(1, &imported), (2, &created)] {
+        let info = suite.app.wrap().query_wasm_contract_info(series).unwrap();
+        assert_eq!(info.code_id, new_code_id);
+        assert_eq!(recorded_code_id(&suite, id), new_code_id);
+    }
+}